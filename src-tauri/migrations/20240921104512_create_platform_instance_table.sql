@@ -0,0 +1,24 @@
+-- Create the 'platform_instance' table.
+CREATE TABLE IF NOT EXISTS platform_instance (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    platform TEXT NOT NULL,
+    base_url TEXT NOT NULL,
+    token TEXT,
+    created_at DATETIME NOT NULL
+);
+
+-- Create an index on the 'platform' and 'base_url' columns
+CREATE UNIQUE INDEX IF NOT EXISTS idx_platform_instance_platform_base_url on platform_instance (platform, base_url);
+
+
+
+-- Record which instance a repo or user belongs to, NULL being the platform's public host.
+ALTER TABLE repo ADD COLUMN instance_id INTEGER REFERENCES platform_instance (id) ON DELETE RESTRICT;
+ALTER TABLE user ADD COLUMN instance_id INTEGER REFERENCES platform_instance (id) ON DELETE RESTRICT;
+
+-- Create an index on the 'instance_id' column
+CREATE INDEX IF NOT EXISTS idx_repo_instance_id on repo (instance_id);
+
+-- Usernames are only unique per platform instance.
+DROP INDEX IF EXISTS idx_user_user;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_user_platform_instance_user on user (platform, IFNULL(instance_id, 0), user);
@@ -0,0 +1,60 @@
+-- Create the 'gitlab_project' table.
+CREATE TABLE IF NOT EXISTS gitlab_project (
+    repo_id INTEGER PRIMARY KEY NOT NULL,
+    id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    name_with_namespace TEXT NOT NULL,
+    path TEXT NOT NULL,
+    path_with_namespace TEXT NOT NULL,
+    description TEXT,
+    default_branch TEXT NOT NULL,
+    visibility TEXT NOT NULL,
+    web_url TEXT NOT NULL,
+    star_count INTEGER NOT NULL,
+    forks_count INTEGER NOT NULL,
+    open_issues_count INTEGER,
+    archived BOOLEAN NOT NULL CHECK (archived IN (0, 1)),
+    empty_repo BOOLEAN NOT NULL CHECK (empty_repo IN (0, 1)),
+    license_key TEXT,
+    license_name TEXT,
+    namespace_id INTEGER NOT NULL,
+    created_at DATETIME NOT NULL,
+    last_activity_at DATETIME NOT NULL,
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+
+
+-- Create the 'gitlab_project_topic' table.
+CREATE TABLE IF NOT EXISTS gitlab_project_topic (
+    repo_id INTEGER NOT NULL,
+    topic TEXT NOT NULL,
+    FOREIGN KEY (repo_id)
+        REFERENCES gitlab_project (repo_id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'repo_id' column
+CREATE INDEX IF NOT EXISTS idx_gitlab_project_topic_repo_id on gitlab_project_topic (repo_id);
+
+
+
+-- Create the 'gitlab_namespace' table.
+CREATE TABLE IF NOT EXISTS gitlab_namespace (
+    user_id INTEGER PRIMARY KEY NOT NULL,
+    id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    path TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind in ('user', 'group')),
+    full_path TEXT NOT NULL,
+    avatar_url TEXT,
+    web_url TEXT NOT NULL,
+    FOREIGN KEY (user_id)
+        REFERENCES user (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
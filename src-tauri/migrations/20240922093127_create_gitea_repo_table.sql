@@ -0,0 +1,73 @@
+-- Create the 'gitea_repo' table.
+CREATE TABLE IF NOT EXISTS gitea_repo (
+    repo_id INTEGER PRIMARY KEY NOT NULL,
+    id INTEGER NOT NULL,
+    owner_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    full_name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    empty BOOLEAN NOT NULL CHECK (empty IN (0, 1)),
+    private BOOLEAN NOT NULL CHECK (private IN (0, 1)),
+    fork BOOLEAN NOT NULL CHECK (fork IN (0, 1)),
+    template BOOLEAN NOT NULL CHECK (template IN (0, 1)),
+    mirror BOOLEAN NOT NULL CHECK (mirror IN (0, 1)),
+    size INTEGER NOT NULL,
+    language TEXT NOT NULL,
+    html_url TEXT NOT NULL,
+    website TEXT NOT NULL,
+    stars_count INTEGER NOT NULL,
+    forks_count INTEGER NOT NULL,
+    watchers_count INTEGER NOT NULL,
+    open_issues_count INTEGER NOT NULL,
+    open_pr_counter INTEGER NOT NULL,
+    release_counter INTEGER NOT NULL,
+    default_branch TEXT NOT NULL,
+    archived BOOLEAN NOT NULL CHECK (archived IN (0, 1)),
+    has_issues BOOLEAN NOT NULL CHECK (has_issues IN (0, 1)),
+    has_wiki BOOLEAN NOT NULL CHECK (has_wiki IN (0, 1)),
+    has_pull_requests BOOLEAN NOT NULL CHECK (has_pull_requests IN (0, 1)),
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+
+
+-- Create the 'gitea_repo_topic' table.
+CREATE TABLE IF NOT EXISTS gitea_repo_topic (
+    repo_id INTEGER NOT NULL,
+    topic TEXT NOT NULL,
+    FOREIGN KEY (repo_id)
+        REFERENCES gitea_repo (repo_id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'repo_id' column
+CREATE INDEX IF NOT EXISTS idx_gitea_repo_topic_repo_id on gitea_repo_topic (repo_id);
+
+
+
+-- Create the 'gitea_user' table.
+CREATE TABLE IF NOT EXISTS gitea_user (
+    user_id INTEGER PRIMARY KEY NOT NULL,
+    id INTEGER NOT NULL,
+    login TEXT NOT NULL,
+    full_name TEXT NOT NULL,
+    avatar_url TEXT NOT NULL,
+    html_url TEXT NOT NULL,
+    description TEXT NOT NULL,
+    website TEXT NOT NULL,
+    location TEXT NOT NULL,
+    followers_count INTEGER NOT NULL,
+    following_count INTEGER NOT NULL,
+    starred_repos_count INTEGER NOT NULL,
+    created DATETIME NOT NULL,
+    FOREIGN KEY (user_id)
+        REFERENCES user (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
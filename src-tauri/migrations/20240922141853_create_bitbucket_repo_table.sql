@@ -0,0 +1,44 @@
+-- Create the 'bitbucket_repo' table.
+CREATE TABLE IF NOT EXISTS bitbucket_repo (
+    repo_id INTEGER PRIMARY KEY NOT NULL,
+    uuid TEXT NOT NULL,
+    workspace_uuid TEXT NOT NULL,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL,
+    full_name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    is_private BOOLEAN NOT NULL CHECK (is_private IN (0, 1)),
+    fork_policy TEXT NOT NULL,
+    language TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    has_issues BOOLEAN NOT NULL CHECK (has_issues IN (0, 1)),
+    has_wiki BOOLEAN NOT NULL CHECK (has_wiki IN (0, 1)),
+    mainbranch TEXT NOT NULL,
+    project_key TEXT,
+    project_name TEXT,
+    html_url TEXT NOT NULL,
+    created_on DATETIME NOT NULL,
+    updated_on DATETIME NOT NULL,
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+
+
+-- Create the 'bitbucket_workspace' table.
+CREATE TABLE IF NOT EXISTS bitbucket_workspace (
+    user_id INTEGER PRIMARY KEY NOT NULL,
+    uuid TEXT NOT NULL,
+    slug TEXT NOT NULL,
+    name TEXT NOT NULL,
+    is_private BOOLEAN NOT NULL CHECK (is_private IN (0, 1)),
+    avatar_url TEXT NOT NULL,
+    html_url TEXT NOT NULL,
+    created_on DATETIME,
+    FOREIGN KEY (user_id)
+        REFERENCES user (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
@@ -0,0 +1,4 @@
+-- Hosts that serve the API and raw file contents from other URLs than the base URL,
+-- e.g. GitHub Enterprise Server. NULL uses the platform's default paths on the base URL.
+ALTER TABLE platform_instance ADD COLUMN api_url TEXT;
+ALTER TABLE platform_instance ADD COLUMN content_url TEXT;
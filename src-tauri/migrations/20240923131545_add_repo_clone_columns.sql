@@ -0,0 +1,6 @@
+-- Track the local bare mirror of repos added with 'clone_data'.
+-- 'clone_status' is one of 'none', 'cloning', 'cloned' or 'failed'.
+ALTER TABLE repo ADD COLUMN clone_status TEXT NOT NULL DEFAULT 'none';
+ALTER TABLE repo ADD COLUMN clone_size INTEGER;
+ALTER TABLE repo ADD COLUMN clone_commit TEXT;
+ALTER TABLE repo ADD COLUMN clone_fetched_at DATETIME;
@@ -0,0 +1,9 @@
+-- Record where in the parent repo's tree a submodule is checked out.
+ALTER TABLE submodule ADD COLUMN path TEXT NOT NULL DEFAULT '';
+
+-- The original index on 'submodule_repo_id' reused the name of the 'repo_id' index,
+-- so it was never created.
+CREATE INDEX IF NOT EXISTS idx_submodule_submodule_repo_id on submodule (submodule_repo_id);
+
+-- Create a unique index on the 'repo_id', 'submodule_repo_id' and 'path' columns
+CREATE UNIQUE INDEX IF NOT EXISTS idx_submodule_repo_id_submodule_repo_id_path on submodule (repo_id, submodule_repo_id, path);
@@ -0,0 +1,17 @@
+-- Create the 'sync_run' table.
+CREATE TABLE IF NOT EXISTS sync_run (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    repo_id INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('auto', 'manual')),
+    started_at DATETIME NOT NULL,
+    finished_at DATETIME,
+    outcome TEXT NOT NULL CHECK (outcome IN ('running', 'success', 'failed', 'rate_limited')),
+    error TEXT,
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'repo_id' and 'started_at' columns
+CREATE INDEX IF NOT EXISTS idx_sync_run_repo_id_started_at on sync_run (repo_id, started_at);
@@ -0,0 +1,40 @@
+-- Add the time of the last poll to the 'watch_repo_event' table.
+ALTER TABLE watch_repo_event ADD COLUMN polled_at DATETIME;
+
+
+
+-- Create the 'repo_event_state' table.
+CREATE TABLE IF NOT EXISTS repo_event_state (
+    watch_id INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    version TEXT NOT NULL,
+    PRIMARY KEY (watch_id, key),
+    FOREIGN KEY (watch_id)
+        REFERENCES watch_repo_event (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+
+
+-- Create the 'repo_event_log' table.
+CREATE TABLE IF NOT EXISTS repo_event_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    repo_id INTEGER NOT NULL,
+    event TEXT NOT NULL,
+    action TEXT NOT NULL CHECK (action IN ('created', 'updated')),
+    key TEXT NOT NULL,
+    title TEXT NOT NULL,
+    url TEXT,
+    occurred_at DATETIME,
+    created_at DATETIME NOT NULL,
+    read_at DATETIME,
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'repo_id' and 'created_at' columns
+CREATE INDEX IF NOT EXISTS idx_repo_event_log_repo_id on repo_event_log (repo_id);
+CREATE INDEX IF NOT EXISTS idx_repo_event_log_created_at on repo_event_log (created_at);
@@ -0,0 +1,22 @@
+-- Create the 'repo_metadata_history' table.
+CREATE TABLE IF NOT EXISTS repo_metadata_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    repo_id INTEGER NOT NULL,
+    captured_at DATETIME NOT NULL,
+    stars INTEGER,
+    forks INTEGER,
+    watchers INTEGER,
+    open_issues INTEGER,
+    default_branch TEXT NOT NULL,
+    archived BOOLEAN CHECK (archived IN (0, 1)),
+    visibility TEXT,
+    license TEXT,
+    topics TEXT,
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'repo_id' and 'captured_at' columns
+CREATE INDEX IF NOT EXISTS idx_repo_metadata_history_repo_id_captured_at on repo_metadata_history (repo_id, captured_at);
@@ -0,0 +1,36 @@
+-- Recreate the 'rate_limit' table keyed by platform, host, token and resource. The old
+-- rows only describe the current rate limit windows, so they are dropped.
+DROP TABLE IF EXISTS rate_limit;
+
+CREATE TABLE IF NOT EXISTS rate_limit (
+    platform TEXT NOT NULL,
+    host TEXT NOT NULL,
+    token TEXT NOT NULL,
+    resource TEXT NOT NULL,
+    max INTEGER NOT NULL,
+    remaining INTEGER NOT NULL,
+    used INTEGER NOT NULL,
+    reset_at TIMESTAMP NOT NULL,
+    updated_at DATETIME NOT NULL,
+    PRIMARY KEY (platform, host, token, resource)
+);
+
+
+
+-- Create the 'sync_run_rate_limit' table.
+CREATE TABLE IF NOT EXISTS sync_run_rate_limit (
+    sync_run_id INTEGER NOT NULL,
+    platform TEXT NOT NULL,
+    host TEXT NOT NULL,
+    token TEXT NOT NULL,
+    resource TEXT NOT NULL,
+    calls INTEGER NOT NULL,
+    remaining INTEGER,
+    FOREIGN KEY (sync_run_id)
+        REFERENCES sync_run (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'sync_run_id' column
+CREATE INDEX IF NOT EXISTS idx_sync_run_rate_limit_sync_run_id on sync_run_rate_limit (sync_run_id);
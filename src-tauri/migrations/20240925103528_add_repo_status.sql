@@ -0,0 +1,3 @@
+-- Add the ingest status to the 'repo' table. Repos stay 'adding' until all of their
+-- data is stored, so repos left behind by a crash can be removed on startup.
+ALTER TABLE repo ADD COLUMN status TEXT NOT NULL DEFAULT 'ready' CHECK (status IN ('adding', 'ready'));
@@ -0,0 +1,27 @@
+-- Add the 'name' and 'depth' columns to the 'repo_tree_item' table.
+ALTER TABLE repo_tree_item ADD COLUMN name TEXT NOT NULL DEFAULT '';
+ALTER TABLE repo_tree_item ADD COLUMN depth INTEGER NOT NULL DEFAULT 0;
+
+-- Create an index on the 'repo_id' and 'path' columns
+CREATE INDEX IF NOT EXISTS idx_repo_tree_item_repo_id_path on repo_tree_item (repo_id, path);
+
+
+
+-- Fill in the name and depth from the path. Trimming every character other than '/'
+-- from the end of the path leaves the parent directory with a trailing '/'.
+UPDATE repo_tree_item
+SET
+    name = substr(path, length(rtrim(path, replace(path, '/', ''))) + 1),
+    depth = length(path) - length(replace(path, '/', ''));
+
+
+
+-- Recompute the parents by full path, they were matched by directory name before.
+UPDATE repo_tree_item
+SET parent_id = (
+    SELECT parent.id
+    FROM repo_tree_item AS parent
+    WHERE parent.repo_id = repo_tree_item.repo_id
+    AND parent.type = 'tree'
+    AND parent.path = rtrim(rtrim(repo_tree_item.path, replace(repo_tree_item.path, '/', '')), '/')
+);
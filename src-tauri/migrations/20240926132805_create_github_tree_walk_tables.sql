@@ -0,0 +1,40 @@
+-- Create the 'github_tree_walk' table.
+CREATE TABLE IF NOT EXISTS github_tree_walk (
+    repo_id INTEGER PRIMARY KEY,
+    sha TEXT NOT NULL,
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+
+
+-- Create the 'github_tree_walk_dir' table.
+CREATE TABLE IF NOT EXISTS github_tree_walk_dir (
+    repo_id INTEGER NOT NULL,
+    path TEXT NOT NULL,
+    sha TEXT NOT NULL,
+    PRIMARY KEY (repo_id, path),
+    FOREIGN KEY (repo_id)
+        REFERENCES github_tree_walk (repo_id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+
+
+-- Create the 'github_tree_walk_item' table.
+CREATE TABLE IF NOT EXISTS github_tree_walk_item (
+    repo_id INTEGER NOT NULL,
+    path TEXT NOT NULL,
+    mode TEXT NOT NULL,
+    type TEXT NOT NULL,
+    sha TEXT NOT NULL,
+    size INTEGER,
+    PRIMARY KEY (repo_id, path),
+    FOREIGN KEY (repo_id)
+        REFERENCES github_tree_walk (repo_id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
@@ -0,0 +1,68 @@
+-- Create the 'repo_tree' table again, with a tree per ref of a repo.
+CREATE TABLE IF NOT EXISTS repo_tree_by_ref (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    repo_id INTEGER NOT NULL,
+    ref TEXT NOT NULL,
+    sha TEXT NOT NULL,
+    truncated BOOLEAN NOT NULL CHECK (truncated IN (0, 1)),
+    is_default BOOLEAN NOT NULL CHECK (is_default IN (0, 1)) DEFAULT 0,
+    UNIQUE (repo_id, ref),
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- The stored trees are of the default branch.
+INSERT INTO repo_tree_by_ref (repo_id, ref, sha, truncated, is_default)
+SELECT
+    t.repo_id,
+    COALESCE(
+        (SELECT default_branch FROM github_repo WHERE repo_id = t.repo_id),
+        (SELECT default_branch FROM gitlab_project WHERE repo_id = t.repo_id),
+        (SELECT default_branch FROM gitea_repo WHERE repo_id = t.repo_id),
+        (SELECT mainbranch FROM bitbucket_repo WHERE repo_id = t.repo_id),
+        t.sha
+    ),
+    t.sha,
+    t.truncated,
+    1
+FROM repo_tree AS t;
+
+DROP TABLE repo_tree;
+ALTER TABLE repo_tree_by_ref RENAME TO repo_tree;
+
+-- Create an index on the 'repo_id' column
+CREATE INDEX IF NOT EXISTS idx_repo_tree_repo_id on repo_tree (repo_id);
+
+
+
+-- Create the 'repo_blob' table.
+CREATE TABLE IF NOT EXISTS repo_blob (
+    repo_id INTEGER NOT NULL,
+    sha TEXT NOT NULL,
+    size INTEGER,
+    PRIMARY KEY (repo_id, sha),
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+INSERT OR IGNORE INTO repo_blob (repo_id, sha, size)
+SELECT repo_id, sha, size FROM repo_tree_item WHERE type = 'blob';
+
+
+
+-- Add the 'tree_id' column to the 'repo_tree_item' table, blob sizes are stored once
+-- per repo in 'repo_blob'.
+ALTER TABLE repo_tree_item ADD COLUMN tree_id INTEGER
+    REFERENCES repo_tree (id) ON DELETE CASCADE ON UPDATE CASCADE;
+
+UPDATE repo_tree_item
+SET tree_id = (SELECT id FROM repo_tree WHERE repo_id = repo_tree_item.repo_id);
+
+ALTER TABLE repo_tree_item DROP COLUMN size;
+
+-- Create an index on the 'tree_id' and 'path' columns
+CREATE INDEX IF NOT EXISTS idx_repo_tree_item_tree_id_path on repo_tree_item (tree_id, path);
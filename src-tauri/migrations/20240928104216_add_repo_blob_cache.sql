@@ -0,0 +1,10 @@
+-- Add the 'rev' column to the 'repo_tree' table, the revision the files of the tree
+-- are fetched at.
+ALTER TABLE repo_tree ADD COLUMN rev TEXT;
+
+-- Add the 'cached_at' column to the 'repo_blob' table, set once the content is cached.
+ALTER TABLE repo_blob ADD COLUMN cached_at DATETIME;
+
+-- Add the 'prefetch_blobs' and 'prefetch_max_size' columns to the 'repo' table.
+ALTER TABLE repo ADD COLUMN prefetch_blobs BOOLEAN CHECK (prefetch_blobs IN (0, 1)) DEFAULT 0;
+ALTER TABLE repo ADD COLUMN prefetch_max_size INTEGER NOT NULL DEFAULT 1048576;
@@ -0,0 +1,110 @@
+-- Add the 'archive_issues' column to the 'repo' table.
+ALTER TABLE repo ADD COLUMN archive_issues BOOLEAN CHECK (archive_issues IN (0, 1)) DEFAULT 0;
+
+
+
+-- Create the 'github_issue' table.
+CREATE TABLE IF NOT EXISTS github_issue (
+    id INTEGER PRIMARY KEY NOT NULL,
+    repo_id INTEGER NOT NULL,
+    number INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    body TEXT,
+    state TEXT NOT NULL CHECK (state IN ('open', 'closed')),
+    state_reason TEXT,
+    user_login TEXT,
+    user_id INTEGER,
+    locked BOOLEAN NOT NULL CHECK (locked IN (0, 1)),
+    comments INTEGER NOT NULL,
+    html_url TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    closed_at DATETIME,
+    UNIQUE (repo_id, number),
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'repo_id' and 'updated_at' columns
+CREATE INDEX IF NOT EXISTS idx_github_issue_repo_id_updated_at on github_issue (repo_id, updated_at);
+
+
+
+-- Create the 'github_issue_label' table.
+CREATE TABLE IF NOT EXISTS github_issue_label (
+    issue_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    color TEXT NOT NULL,
+    description TEXT,
+    PRIMARY KEY (issue_id, name),
+    FOREIGN KEY (issue_id)
+        REFERENCES github_issue (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+
+
+-- Create the 'github_issue_assignee' table.
+CREATE TABLE IF NOT EXISTS github_issue_assignee (
+    issue_id INTEGER NOT NULL,
+    login TEXT NOT NULL,
+    user_id INTEGER NOT NULL,
+    PRIMARY KEY (issue_id, login),
+    FOREIGN KEY (issue_id)
+        REFERENCES github_issue (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+
+
+-- Create the 'github_issue_comment' table.
+CREATE TABLE IF NOT EXISTS github_issue_comment (
+    id INTEGER PRIMARY KEY NOT NULL,
+    issue_id INTEGER NOT NULL,
+    body TEXT,
+    user_login TEXT,
+    user_id INTEGER,
+    html_url TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    FOREIGN KEY (issue_id)
+        REFERENCES github_issue (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'issue_id' column
+CREATE INDEX IF NOT EXISTS idx_github_issue_comment_issue_id on github_issue_comment (issue_id);
+
+
+
+-- Create the 'github_issue_asset' table, images of issue and comment bodies mirrored
+-- to 'assets/repos/{user}/{repo}/issues/{id}.{ext}'.
+CREATE TABLE IF NOT EXISTS github_issue_asset (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    repo_id INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    ext TEXT,
+    UNIQUE (repo_id, url),
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+
+
+-- Create the 'github_issue_sync' table, where incremental refreshes continue from.
+CREATE TABLE IF NOT EXISTS github_issue_sync (
+    repo_id INTEGER PRIMARY KEY NOT NULL,
+    issues_since DATETIME,
+    comments_since DATETIME,
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
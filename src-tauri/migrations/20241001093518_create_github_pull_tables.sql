@@ -0,0 +1,115 @@
+-- Add the 'archive_pulls' column to the 'repo' table.
+ALTER TABLE repo ADD COLUMN archive_pulls BOOLEAN CHECK (archive_pulls IN (0, 1)) DEFAULT 0;
+
+
+
+-- Create the 'github_pull' table.
+CREATE TABLE IF NOT EXISTS github_pull (
+    id INTEGER PRIMARY KEY NOT NULL,
+    repo_id INTEGER NOT NULL,
+    number INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    body TEXT,
+    state TEXT NOT NULL CHECK (state IN ('open', 'closed')),
+    draft BOOLEAN NOT NULL CHECK (draft IN (0, 1)),
+    user_login TEXT,
+    user_id INTEGER,
+    head_ref TEXT NOT NULL,
+    head_sha TEXT NOT NULL,
+    base_ref TEXT NOT NULL,
+    base_sha TEXT NOT NULL,
+    html_url TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    closed_at DATETIME,
+    merged_at DATETIME,
+    UNIQUE (repo_id, number),
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'repo_id' and 'updated_at' columns
+CREATE INDEX IF NOT EXISTS idx_github_pull_repo_id_updated_at on github_pull (repo_id, updated_at);
+
+
+
+-- Create the 'github_pull_diff' table, the '.diff' text of a pull request compressed
+-- with zlib, as of its head commit.
+CREATE TABLE IF NOT EXISTS github_pull_diff (
+    pull_id INTEGER PRIMARY KEY NOT NULL,
+    head_sha TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    diff BLOB NOT NULL,
+    FOREIGN KEY (pull_id)
+        REFERENCES github_pull (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+
+
+-- Create the 'github_pull_review' table.
+CREATE TABLE IF NOT EXISTS github_pull_review (
+    id INTEGER PRIMARY KEY NOT NULL,
+    pull_id INTEGER NOT NULL,
+    body TEXT,
+    state TEXT NOT NULL,
+    user_login TEXT,
+    user_id INTEGER,
+    commit_id TEXT,
+    html_url TEXT NOT NULL,
+    submitted_at DATETIME,
+    FOREIGN KEY (pull_id)
+        REFERENCES github_pull (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'pull_id' column
+CREATE INDEX IF NOT EXISTS idx_github_pull_review_pull_id on github_pull_review (pull_id);
+
+
+
+-- Create the 'github_pull_review_comment' table, comments anchored to a line of a file
+-- in the diff.
+CREATE TABLE IF NOT EXISTS github_pull_review_comment (
+    id INTEGER PRIMARY KEY NOT NULL,
+    pull_id INTEGER NOT NULL,
+    review_id INTEGER,
+    in_reply_to_id INTEGER,
+    body TEXT NOT NULL,
+    user_login TEXT,
+    user_id INTEGER,
+    path TEXT NOT NULL,
+    line INTEGER,
+    start_line INTEGER,
+    original_line INTEGER,
+    side TEXT,
+    commit_id TEXT NOT NULL,
+    diff_hunk TEXT NOT NULL,
+    html_url TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    FOREIGN KEY (pull_id)
+        REFERENCES github_pull (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'pull_id' column
+CREATE INDEX IF NOT EXISTS idx_github_pull_review_comment_pull_id on github_pull_review_comment (pull_id);
+
+
+
+-- Create the 'github_pull_sync' table, where incremental refreshes continue from.
+CREATE TABLE IF NOT EXISTS github_pull_sync (
+    repo_id INTEGER PRIMARY KEY NOT NULL,
+    pulls_since DATETIME,
+    review_comments_since DATETIME,
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
@@ -0,0 +1,109 @@
+-- Add the 'archive_discussions' column to the 'repo' table.
+ALTER TABLE repo ADD COLUMN archive_discussions BOOLEAN CHECK (archive_discussions IN (0, 1)) DEFAULT 0;
+
+
+
+-- Create the 'github_discussion_category' table, IDs are GraphQL node IDs.
+CREATE TABLE IF NOT EXISTS github_discussion_category (
+    id TEXT PRIMARY KEY NOT NULL,
+    repo_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    emoji TEXT NOT NULL,
+    description TEXT,
+    is_answerable BOOLEAN NOT NULL CHECK (is_answerable IN (0, 1)),
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+
+
+-- Create the 'github_discussion' table.
+CREATE TABLE IF NOT EXISTS github_discussion (
+    id TEXT PRIMARY KEY NOT NULL,
+    repo_id INTEGER NOT NULL,
+    number INTEGER NOT NULL,
+    category_id TEXT,
+    title TEXT NOT NULL,
+    body TEXT NOT NULL,
+    author_login TEXT,
+    url TEXT NOT NULL,
+    answer_id TEXT,
+    closed BOOLEAN NOT NULL CHECK (closed IN (0, 1)),
+    locked BOOLEAN NOT NULL CHECK (locked IN (0, 1)),
+    upvote_count INTEGER NOT NULL,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    UNIQUE (repo_id, number),
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE,
+    FOREIGN KEY (category_id)
+        REFERENCES github_discussion_category (id)
+            ON DELETE SET NULL
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'repo_id' and 'updated_at' columns
+CREATE INDEX IF NOT EXISTS idx_github_discussion_repo_id_updated_at on github_discussion (repo_id, updated_at);
+
+
+
+-- Create the 'github_discussion_comment' table, replies reference the comment they
+-- belong to.
+CREATE TABLE IF NOT EXISTS github_discussion_comment (
+    id TEXT PRIMARY KEY NOT NULL,
+    discussion_id TEXT NOT NULL,
+    reply_to_id TEXT,
+    body TEXT NOT NULL,
+    author_login TEXT,
+    url TEXT NOT NULL,
+    is_answer BOOLEAN NOT NULL CHECK (is_answer IN (0, 1)),
+    upvote_count INTEGER NOT NULL,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    FOREIGN KEY (discussion_id)
+        REFERENCES github_discussion (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE,
+    FOREIGN KEY (reply_to_id)
+        REFERENCES github_discussion_comment (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'discussion_id' column
+CREATE INDEX IF NOT EXISTS idx_github_discussion_comment_discussion_id on github_discussion_comment (discussion_id);
+
+
+
+-- Create the 'github_discussion_reaction' table, the reaction counts of a discussion
+-- or one of its comments.
+CREATE TABLE IF NOT EXISTS github_discussion_reaction (
+    subject_id TEXT NOT NULL,
+    discussion_id TEXT NOT NULL,
+    content TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    PRIMARY KEY (subject_id, content),
+    FOREIGN KEY (discussion_id)
+        REFERENCES github_discussion (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'discussion_id' column
+CREATE INDEX IF NOT EXISTS idx_github_discussion_reaction_discussion_id on github_discussion_reaction (discussion_id);
+
+
+
+-- Create the 'github_discussion_sync' table, where incremental refreshes continue from.
+CREATE TABLE IF NOT EXISTS github_discussion_sync (
+    repo_id INTEGER PRIMARY KEY NOT NULL,
+    discussions_since DATETIME,
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
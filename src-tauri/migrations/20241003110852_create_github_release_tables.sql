@@ -0,0 +1,70 @@
+-- Add the release columns to the 'repo' table, assets are only downloaded up to
+-- 'release_asset_max_size' bytes.
+ALTER TABLE repo ADD COLUMN archive_releases BOOLEAN CHECK (archive_releases IN (0, 1)) DEFAULT 0;
+ALTER TABLE repo ADD COLUMN download_release_assets BOOLEAN CHECK (download_release_assets IN (0, 1)) DEFAULT 0;
+ALTER TABLE repo ADD COLUMN release_asset_max_size INTEGER NOT NULL DEFAULT 104857600;
+
+
+
+-- Create the 'repo_release_asset_glob' table, the asset names to download. Every
+-- asset is downloaded if a repo has none.
+CREATE TABLE IF NOT EXISTS repo_release_asset_glob (
+    repo_id INTEGER NOT NULL,
+    glob TEXT NOT NULL,
+    PRIMARY KEY (repo_id, glob),
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+
+
+-- Create the 'github_release' table.
+CREATE TABLE IF NOT EXISTS github_release (
+    id INTEGER PRIMARY KEY NOT NULL,
+    repo_id INTEGER NOT NULL,
+    tag_name TEXT NOT NULL,
+    name TEXT,
+    body TEXT,
+    draft BOOLEAN NOT NULL CHECK (draft IN (0, 1)),
+    prerelease BOOLEAN NOT NULL CHECK (prerelease IN (0, 1)),
+    author_login TEXT,
+    html_url TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    published_at DATETIME,
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'repo_id' column
+CREATE INDEX IF NOT EXISTS idx_github_release_repo_id on github_release (repo_id);
+
+
+
+-- Create the 'github_release_asset' table, downloaded assets are stored at
+-- 'releases/{repo_id}/{id}/{name}' with their SHA-256 checksum.
+CREATE TABLE IF NOT EXISTS github_release_asset (
+    id INTEGER PRIMARY KEY NOT NULL,
+    release_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    label TEXT,
+    content_type TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    download_count INTEGER NOT NULL,
+    browser_download_url TEXT NOT NULL,
+    digest TEXT,
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    sha256 TEXT,
+    downloaded_at DATETIME,
+    FOREIGN KEY (release_id)
+        REFERENCES github_release (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create an index on the 'release_id' column
+CREATE INDEX IF NOT EXISTS idx_github_release_asset_release_id on github_release_asset (release_id);
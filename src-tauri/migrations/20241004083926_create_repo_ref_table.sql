@@ -0,0 +1,18 @@
+-- Create the 'repo_ref' table, the branches and tags of a repo as of its last sync.
+CREATE TABLE IF NOT EXISTS repo_ref (
+    repo_id INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('branch', 'tag')),
+    name TEXT NOT NULL,
+    sha TEXT NOT NULL,
+    protected BOOLEAN NOT NULL DEFAULT 0 CHECK (protected IN (0, 1)),
+    annotated BOOLEAN CHECK (annotated IN (0, 1)),
+    tagger TEXT,
+    tagged_at DATETIME,
+    message TEXT,
+    updated_at DATETIME NOT NULL,
+    PRIMARY KEY (repo_id, kind, name),
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
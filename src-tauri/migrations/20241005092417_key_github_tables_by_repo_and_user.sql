@@ -0,0 +1,214 @@
+-- GitHub IDs are only unique per host, so the GitHub tables are keyed by the repo and
+-- user they belong to instead, for repos and users of Enterprise Server instances.
+
+-- Create the 'github_repo' table again, keyed by 'repo_id'.
+CREATE TABLE IF NOT EXISTS github_repo_by_repo (
+    repo_id INTEGER PRIMARY KEY NOT NULL,
+    id INTEGER NOT NULL,
+    node_id TEXT,
+    name TEXT NOT NULL,
+    full_name TEXT NOT NULL,
+    private BOOLEAN NOT NULL CHECK (private IN (0, 1)),
+    description TEXT NOT NULL,
+    fork BOOLEAN NOT NULL CHECK (fork IN (0, 1)),
+    created_at DATETIME NOT NULL,
+    updated_at DATETIME NOT NULL,
+    pushed_at DATETIME NOT NULL,
+    homepage TEXT,
+    size INTEGER NOT NULL,
+    stargazers_count INTEGER NOT NULL,
+    watchers_count INTEGER NOT NULL,
+    language TEXT NOT NULL,
+    has_issues BOOLEAN NOT NULL CHECK (has_issues IN (0, 1)),
+    has_projects BOOLEAN NOT NULL CHECK (has_projects IN (0, 1)),
+    has_downloads BOOLEAN NOT NULL CHECK (has_downloads IN (0, 1)),
+    has_wiki BOOLEAN NOT NULL CHECK (has_wiki IN (0, 1)),
+    has_pages BOOLEAN NOT NULL CHECK (has_pages IN (0, 1)),
+    has_discussions BOOLEAN NOT NULL CHECK (has_discussions IN (0, 1)),
+    forks_count INTEGER NOT NULL,
+    archived BOOLEAN NOT NULL CHECK (archived IN (0, 1)),
+    disabled BOOLEAN NOT NULL CHECK (disabled IN (0, 1)),
+    open_issues_count INTEGER NOT NULL,
+    allow_forking BOOLEAN NOT NULL CHECK (allow_forking IN (0, 1)),
+    is_template BOOLEAN NOT NULL CHECK (is_template IN (0, 1)),
+    web_commit_signoff_required BOOLEAN NOT NULL CHECK (web_commit_signoff_required IN (0, 1)),
+    visibility TEXT NOT NULL,
+    forks INTEGER NOT NULL,
+    open_issues INTEGER NOT NULL,
+    watchers INTEGER NOT NULL,
+    default_branch TEXT NOT NULL,
+    network_count INTEGER NOT NULL,
+    subscribers_count INTEGER NOT NULL,
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- The columns are in the same order.
+INSERT INTO github_repo_by_repo SELECT * FROM github_repo;
+
+
+
+-- Create the 'github_repo_owner' table again, with one owner per repo.
+CREATE TABLE IF NOT EXISTS github_repo_owner_by_repo (
+    repo_id INTEGER PRIMARY KEY NOT NULL,
+    login TEXT NOT NULL,
+    id INTEGER NOT NULL,
+    node_id TEXT NOT NULL,
+    gravatar_id TEXT NOT NULL,
+    type TEXT NOT NULL,
+    site_admin BOOLEAN NOT NULL CHECK (site_admin IN (0, 1)),
+    FOREIGN KEY (repo_id)
+        REFERENCES github_repo_by_repo (repo_id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+INSERT OR REPLACE INTO github_repo_owner_by_repo (
+    repo_id, login, id, node_id, gravatar_id, type, site_admin
+)
+SELECT g.repo_id, o.login, o.id, o.node_id, o.gravatar_id, o.type, o.site_admin
+FROM github_repo_owner o
+JOIN github_repo g ON g.id = o.github_repo_id;
+
+
+
+-- Create the 'github_repo_org' table again, with one organization per repo.
+CREATE TABLE IF NOT EXISTS github_repo_org_by_repo (
+    repo_id INTEGER PRIMARY KEY NOT NULL,
+    login TEXT NOT NULL,
+    id INTEGER NOT NULL,
+    node_id TEXT NOT NULL,
+    gravatar_id TEXT NOT NULL,
+    type TEXT NOT NULL,
+    site_admin BOOLEAN NOT NULL CHECK (site_admin IN (0, 1)),
+    FOREIGN KEY (repo_id)
+        REFERENCES github_repo_by_repo (repo_id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+INSERT OR REPLACE INTO github_repo_org_by_repo (
+    repo_id, login, id, node_id, gravatar_id, type, site_admin
+)
+SELECT g.repo_id, o.login, o.id, o.node_id, o.gravatar_id, o.type, o.site_admin
+FROM github_repo_org o
+JOIN github_repo g ON g.id = o.github_repo_id;
+
+
+
+-- Create the 'github_repo_topic' table again.
+CREATE TABLE IF NOT EXISTS github_repo_topic_by_repo (
+    repo_id INTEGER NOT NULL,
+    topic TEXT NOT NULL,
+    FOREIGN KEY (repo_id)
+        REFERENCES github_repo_by_repo (repo_id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+INSERT INTO github_repo_topic_by_repo (repo_id, topic)
+SELECT g.repo_id, t.topic
+FROM github_repo_topic t
+JOIN github_repo g ON g.id = t.github_repo_id;
+
+
+
+-- Create the 'github_repo_license' table again, with one license per repo.
+CREATE TABLE IF NOT EXISTS github_repo_license_by_repo (
+    repo_id INTEGER PRIMARY KEY NOT NULL,
+    "key" TEXT NOT NULL,
+    name TEXT NOT NULL,
+    spdx_id TEXT NOT NULL,
+    node_id TEXT NOT NULL,
+    FOREIGN KEY (repo_id)
+        REFERENCES github_repo_by_repo (repo_id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+INSERT OR REPLACE INTO github_repo_license_by_repo (repo_id, "key", name, spdx_id, node_id)
+SELECT g.repo_id, l."key", l.name, l.spdx_id, l.node_id
+FROM github_repo_license l
+JOIN github_repo g ON g.id = l.github_repo_id;
+
+
+
+-- Create the 'github_repo_custom_property' table again.
+CREATE TABLE IF NOT EXISTS github_repo_custom_property_by_repo (
+    repo_id INTEGER NOT NULL,
+    "key" TEXT NOT NULL,
+    value TEXT NOT NULL,
+    FOREIGN KEY (repo_id)
+        REFERENCES github_repo_by_repo (repo_id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+INSERT INTO github_repo_custom_property_by_repo (repo_id, "key", value)
+SELECT g.repo_id, c."key", c.value
+FROM github_repo_custom_property c
+JOIN github_repo g ON g.id = c.github_repo_id;
+
+
+
+DROP TABLE github_repo_owner;
+DROP TABLE github_repo_org;
+DROP TABLE github_repo_topic;
+DROP TABLE github_repo_license;
+DROP TABLE github_repo_custom_property;
+DROP TABLE github_repo;
+
+ALTER TABLE github_repo_by_repo RENAME TO github_repo;
+ALTER TABLE github_repo_owner_by_repo RENAME TO github_repo_owner;
+ALTER TABLE github_repo_org_by_repo RENAME TO github_repo_org;
+ALTER TABLE github_repo_topic_by_repo RENAME TO github_repo_topic;
+ALTER TABLE github_repo_license_by_repo RENAME TO github_repo_license;
+ALTER TABLE github_repo_custom_property_by_repo RENAME TO github_repo_custom_property;
+
+-- Create an index on the 'repo_id' column
+CREATE INDEX IF NOT EXISTS idx_github_repo_topic_repo_id on github_repo_topic (repo_id);
+
+-- Create an index on the 'repo_id' column
+CREATE INDEX IF NOT EXISTS idx_github_repo_custom_property_repo_id on github_repo_custom_property (repo_id);
+
+
+
+-- Create the 'github_user' table again, keyed by 'user_id'.
+CREATE TABLE IF NOT EXISTS github_user_by_user (
+    user_id INTEGER PRIMARY KEY NOT NULL,
+    login TEXT NOT NULL,
+    id INTEGER NOT NULL,
+    node_id TEXT NOT NULL,
+    gravatar_id TEXT NOT NULL,
+    type TEXT NOT NULL CHECK (type in ('User', 'Organization')),
+    site_admin BOOLEAN NOT NULL CHECK (site_admin IN (0, 1)),
+    name TEXT,
+    company TEXT,
+    blog TEXT NOT NULL,
+    location TEXT,
+    hireable TEXT,
+    bio TEXT,
+    twitter_username TEXT,
+    public_repos INTEGER NOT NULL,
+    public_gists INTEGER NOT NULL,
+    followers INTEGER NOT NULL,
+    following INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (user_id)
+        REFERENCES user (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+INSERT OR REPLACE INTO github_user_by_user
+SELECT
+    user_id, login, id, node_id, gravatar_id, type, site_admin, name, company, blog,
+    location, hireable, bio, twitter_username, public_repos, public_gists, followers,
+    following, created_at, updated_at
+FROM github_user;
+
+DROP TABLE github_user;
+ALTER TABLE github_user_by_user RENAME TO github_user;
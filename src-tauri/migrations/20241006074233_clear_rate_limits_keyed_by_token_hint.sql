@@ -0,0 +1,3 @@
+-- Rate limits are now keyed by a digest of the token instead of its last characters. The
+-- old rows only describe the current rate limit windows, so they are dropped.
+DELETE FROM rate_limit;
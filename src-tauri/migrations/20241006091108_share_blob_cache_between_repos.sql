@@ -0,0 +1,7 @@
+-- Create an index on the 'sha' column of the 'repo_blob' table, cached blobs are shared
+-- by every repo that has them and only removed once no row references them.
+CREATE INDEX IF NOT EXISTS idx_repo_blob_sha on repo_blob (sha);
+
+-- The cache moved from a directory per repo to one keyed by SHA only, so the blobs are
+-- fetched again.
+UPDATE repo_blob SET cached_at = NULL;
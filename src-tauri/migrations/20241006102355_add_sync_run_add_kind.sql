@@ -0,0 +1,49 @@
+-- Optional steps of adding a repo that fail are recorded as sync runs of the 'add' kind,
+-- the next sync retries them.
+
+-- Create the 'sync_run' table again, with the 'add' kind.
+CREATE TABLE IF NOT EXISTS sync_run_with_add (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    repo_id INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('add', 'auto', 'manual')),
+    started_at DATETIME NOT NULL,
+    finished_at DATETIME,
+    outcome TEXT NOT NULL CHECK (outcome IN ('running', 'success', 'failed', 'rate_limited')),
+    error TEXT,
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- Create the 'sync_run_rate_limit' table again, referencing the new 'sync_run' table.
+CREATE TABLE IF NOT EXISTS sync_run_rate_limit_with_add (
+    sync_run_id INTEGER NOT NULL,
+    platform TEXT NOT NULL,
+    host TEXT NOT NULL,
+    token TEXT NOT NULL,
+    resource TEXT NOT NULL,
+    calls INTEGER NOT NULL,
+    remaining INTEGER,
+    FOREIGN KEY (sync_run_id)
+        REFERENCES sync_run_with_add (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+INSERT INTO sync_run_with_add SELECT * FROM sync_run;
+INSERT INTO sync_run_rate_limit_with_add SELECT * FROM sync_run_rate_limit;
+
+
+
+DROP TABLE sync_run_rate_limit;
+DROP TABLE sync_run;
+
+ALTER TABLE sync_run_with_add RENAME TO sync_run;
+ALTER TABLE sync_run_rate_limit_with_add RENAME TO sync_run_rate_limit;
+
+-- Create an index on the 'repo_id' and 'started_at' columns
+CREATE INDEX IF NOT EXISTS idx_sync_run_repo_id_started_at on sync_run (repo_id, started_at);
+
+-- Create an index on the 'sync_run_id' column
+CREATE INDEX IF NOT EXISTS idx_sync_run_rate_limit_sync_run_id on sync_run_rate_limit (sync_run_id);
@@ -0,0 +1,74 @@
+-- Bitbucket doesn't expose the SHAs of files, they are unknown until the content of a
+-- file is fetched. The size of such a file is stored with its tree item until then, the
+-- others have theirs in 'repo_blob'.
+
+-- Create the 'repo_tree_item' table again, with an optional 'sha' and a 'size' column.
+CREATE TABLE IF NOT EXISTS repo_tree_item_with_unknown_sha (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    repo_id INTEGER NOT NULL,
+    tree_id INTEGER,
+    parent_id INTEGER,
+    path TEXT NOT NULL,
+    name TEXT NOT NULL DEFAULT '',
+    depth INTEGER NOT NULL DEFAULT 0,
+    mode TEXT NOT NULL CHECK (mode in ('100644', '100755', '040000', '120000', '160000')),
+    type TEXT NOT NULL CHECK (type in ('tree', 'blob', 'commit')),
+    sha TEXT,
+    size INTEGER,
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE,
+    FOREIGN KEY (tree_id)
+        REFERENCES repo_tree (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE,
+    FOREIGN KEY (parent_id)
+        REFERENCES repo_tree_item_with_unknown_sha (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+-- The SHA of the listed commit was stored for every Bitbucket item, which identifies no
+-- file. Their sizes shared a single 'repo_blob' row and are unknown until the next sync.
+INSERT INTO repo_tree_item_with_unknown_sha (
+    id, repo_id, tree_id, parent_id, path, name, depth, mode, type, sha
+)
+SELECT
+    i.id,
+    i.repo_id,
+    i.tree_id,
+    i.parent_id,
+    i.path,
+    i.name,
+    i.depth,
+    i.mode,
+    i.type,
+    CASE WHEN r.platform = 'bitbucket' THEN NULL ELSE i.sha END
+FROM repo_tree_item AS i
+JOIN repo AS r ON r.id = i.repo_id;
+
+-- Keep the IDs of deleted items from being reused.
+DELETE FROM sqlite_sequence WHERE name = 'repo_tree_item_with_unknown_sha';
+INSERT INTO sqlite_sequence (name, seq)
+SELECT 'repo_tree_item_with_unknown_sha', seq FROM sqlite_sequence WHERE name = 'repo_tree_item';
+
+DELETE FROM repo_blob WHERE repo_id IN (SELECT id FROM repo WHERE platform = 'bitbucket');
+
+
+
+DROP TABLE repo_tree_item;
+
+ALTER TABLE repo_tree_item_with_unknown_sha RENAME TO repo_tree_item;
+
+-- Create an index on the 'repo_id' column
+CREATE INDEX IF NOT EXISTS idx_repo_tree_item_repo_id on repo_tree_item (repo_id);
+
+-- Create an index on the 'parent_id' column
+CREATE INDEX IF NOT EXISTS idx_repo_tree_item_parent_id on repo_tree_item (parent_id);
+
+-- Create an index on the 'repo_id' and 'path' columns
+CREATE INDEX IF NOT EXISTS idx_repo_tree_item_repo_id_path on repo_tree_item (repo_id, path);
+
+-- Create an index on the 'tree_id' and 'path' columns
+CREATE INDEX IF NOT EXISTS idx_repo_tree_item_tree_id_path on repo_tree_item (tree_id, path);
@@ -0,0 +1,50 @@
+-- Owners and organizations were keyed by their GitHub ID before being keyed by repo, so
+-- repos sharing one kept a single row between them. Repos without theirs get a copy of
+-- the row of another repo of the same owner on the same instance.
+INSERT INTO github_repo_owner (repo_id, login, id, node_id, gravatar_id, type, site_admin)
+SELECT g.repo_id, o.login, o.id, o.node_id, o.gravatar_id, o.type, o.site_admin
+FROM github_repo AS g
+JOIN repo AS r ON r.id = g.repo_id
+JOIN repo AS other ON other.platform = r.platform
+    AND IFNULL(other.instance_id, 0) = IFNULL(r.instance_id, 0)
+    AND LOWER(other.user) = LOWER(r.user)
+JOIN github_repo_owner AS o ON o.repo_id = other.id
+WHERE g.repo_id NOT IN (SELECT repo_id FROM github_repo_owner)
+GROUP BY g.repo_id;
+
+INSERT INTO github_repo_org (repo_id, login, id, node_id, gravatar_id, type, site_admin)
+SELECT g.repo_id, o.login, o.id, o.node_id, o.gravatar_id, o.type, o.site_admin
+FROM github_repo AS g
+JOIN repo AS r ON r.id = g.repo_id
+JOIN repo AS other ON other.platform = r.platform
+    AND IFNULL(other.instance_id, 0) = IFNULL(r.instance_id, 0)
+    AND LOWER(other.user) = LOWER(r.user)
+JOIN github_repo_org AS o ON o.repo_id = other.id
+WHERE g.repo_id NOT IN (SELECT repo_id FROM github_repo_org)
+GROUP BY g.repo_id;
+
+
+
+-- If the repo that kept the row was removed, the row is gone as well. It is taken from the
+-- stored owner user instead.
+INSERT INTO github_repo_owner (repo_id, login, id, node_id, gravatar_id, type, site_admin)
+SELECT g.repo_id, gu.login, gu.id, gu.node_id, gu.gravatar_id, gu.type, gu.site_admin
+FROM github_repo AS g
+JOIN repo AS r ON r.id = g.repo_id
+JOIN user AS u ON u.platform = r.platform
+    AND IFNULL(u.instance_id, 0) = IFNULL(r.instance_id, 0)
+    AND LOWER(u.user) = LOWER(r.user)
+JOIN github_user AS gu ON gu.user_id = u.id
+WHERE g.repo_id NOT IN (SELECT repo_id FROM github_repo_owner)
+GROUP BY g.repo_id;
+
+INSERT INTO github_repo_org (repo_id, login, id, node_id, gravatar_id, type, site_admin)
+SELECT g.repo_id, gu.login, gu.id, gu.node_id, gu.gravatar_id, gu.type, gu.site_admin
+FROM github_repo AS g
+JOIN repo AS r ON r.id = g.repo_id
+JOIN user AS u ON u.platform = r.platform
+    AND IFNULL(u.instance_id, 0) = IFNULL(r.instance_id, 0)
+    AND LOWER(u.user) = LOWER(r.user)
+JOIN github_user AS gu ON gu.user_id = u.id AND gu.type = 'Organization'
+WHERE g.repo_id NOT IN (SELECT repo_id FROM github_repo_org)
+GROUP BY g.repo_id;
@@ -0,0 +1,13 @@
+-- Syncs that were running when the app exited can't finish anymore, and would keep the
+-- index from being created.
+UPDATE sync_run
+SET
+    finished_at = strftime('%Y-%m-%dT%H:%M:%S+00:00', 'now'),
+    outcome = 'failed',
+    error = 'Interrupted'
+WHERE outcome = 'running';
+
+-- Create a unique index on the 'repo_id' column of running syncs, a repo is synced once at
+-- a time.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sync_run_running_repo_id on sync_run (repo_id)
+WHERE outcome = 'running';
@@ -0,0 +1,3 @@
+-- Add the 'raw_sha256' column to the 'repo_readme' table. The stored content links to the
+-- downloaded images, so syncs compare the README as fetched by this hash instead.
+ALTER TABLE repo_readme ADD COLUMN raw_sha256 TEXT;
@@ -0,0 +1,54 @@
+-- Walks are keyed by their root tree as well, so walks of different refs of a repo don't
+-- mix their state. Walks in progress are dropped, the next fetch of their tree starts over.
+DROP TABLE github_tree_walk_item;
+DROP TABLE github_tree_walk_dir;
+DROP TABLE github_tree_walk;
+
+
+
+-- Create the 'github_tree_walk' table again, keyed by 'repo_id' and 'sha'.
+CREATE TABLE IF NOT EXISTS github_tree_walk (
+    repo_id INTEGER NOT NULL,
+    sha TEXT NOT NULL,
+    -- Whether a directory had more entries than GitHub returns for a tree.
+    truncated BOOLEAN NOT NULL DEFAULT 0 CHECK (truncated IN (0, 1)),
+    started_at DATETIME NOT NULL,
+    PRIMARY KEY (repo_id, sha),
+    FOREIGN KEY (repo_id)
+        REFERENCES repo (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+
+
+-- Create the 'github_tree_walk_dir' table again, keyed by the root tree as well.
+CREATE TABLE IF NOT EXISTS github_tree_walk_dir (
+    repo_id INTEGER NOT NULL,
+    root_sha TEXT NOT NULL,
+    path TEXT NOT NULL,
+    sha TEXT NOT NULL,
+    PRIMARY KEY (repo_id, root_sha, path),
+    FOREIGN KEY (repo_id, root_sha)
+        REFERENCES github_tree_walk (repo_id, sha)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
+
+
+
+-- Create the 'github_tree_walk_item' table again, keyed by the root tree as well.
+CREATE TABLE IF NOT EXISTS github_tree_walk_item (
+    repo_id INTEGER NOT NULL,
+    root_sha TEXT NOT NULL,
+    path TEXT NOT NULL,
+    mode TEXT NOT NULL,
+    type TEXT NOT NULL,
+    sha TEXT NOT NULL,
+    size INTEGER,
+    PRIMARY KEY (repo_id, root_sha, path),
+    FOREIGN KEY (repo_id, root_sha)
+        REFERENCES github_tree_walk (repo_id, sha)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
@@ -0,0 +1,373 @@
+use std::path::PathBuf;
+
+use chrono::Utc;
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use sqlx::{prelude::FromRow, SqlitePool};
+use tauri::AppHandle;
+use tokio::{fs, time::Instant};
+use tracing::{error, info};
+
+use crate::{
+    commands::repo::AddRepoProgress,
+    error::AppResult,
+    platforms::provider::{PlatformProvider, RepoContext},
+    utils::{
+        data::progress_percentage,
+        dirs::{ensure_dir, get_data_dir},
+    },
+};
+
+/// Bytes git looks at to tell binary files from text.
+const BINARY_CHECK_LEN: usize = 8000;
+
+/// The cache path of a blob, split by the first two characters of the SHA like git does.
+/// Blobs are cached once for every repo that has them, `repo_blob` rows reference them.
+pub fn get_blob_cache_path(sha: &str) -> PathBuf {
+    get_data_dir().join("blobs").join(&sha[..2]).join(sha)
+}
+
+/// The SHA git gives a blob with this content, for files of platforms that don't expose
+/// theirs.
+pub fn git_blob_sha(bytes: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(format!("blob {}\0", bytes.len()));
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+/// The content of a blob, decoded if it is text.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BlobContent {
+    Text {
+        /// The detected encoding, e.g. 'utf-8' or 'utf-16le'.
+        encoding: String,
+        content: String,
+    },
+    Binary {
+        /// The MIME type detected from the file signature, if known.
+        mime: Option<String>,
+    },
+}
+
+#[derive(Serialize)]
+pub struct Blob {
+    pub sha: String,
+    pub size: u64,
+    /// The cached file, e.g. to show images through the asset protocol.
+    pub path: String,
+    #[serde(flatten)]
+    pub content: BlobContent,
+}
+
+/// Decode a text file, detecting the encoding from a byte order mark and falling back
+/// to Latin-1 if it isn't valid UTF-8. Files with a NUL byte near the start are binary.
+pub fn decode_blob(bytes: &[u8]) -> BlobContent {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|c| from_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    let text = |encoding: &str, content: String| BlobContent::Text {
+        encoding: encoding.to_string(),
+        content,
+    };
+
+    if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return text("utf-8", String::from_utf8_lossy(rest).into_owned());
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        return text("utf-16le", utf16(rest, u16::from_le_bytes));
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        return text("utf-16be", utf16(rest, u16::from_be_bytes));
+    }
+
+    if bytes[..bytes.len().min(BINARY_CHECK_LEN)].contains(&0) {
+        return BlobContent::Binary {
+            mime: detect_mime(bytes).map(String::from),
+        };
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(content) => text("utf-8", content.to_string()),
+        Err(_) => text("iso-8859-1", bytes.iter().map(|&b| b as char).collect()),
+    }
+}
+
+/// The MIME type of common binary files by their signature.
+fn detect_mime(bytes: &[u8]) -> Option<&'static str> {
+    let signatures: [(&[u8], &str); 9] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xFF\xD8\xFF", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1F\x8B", "application/gzip"),
+        (b"\x00\x00\x01\x00", "image/x-icon"),
+        (b"\x00asm", "application/wasm"),
+    ];
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+    signatures
+        .iter()
+        .find(|(signature, _)| bytes.starts_with(signature))
+        .map(|(_, mime)| *mime)
+}
+
+/// Where a blob of a repo can be fetched from.
+#[derive(FromRow)]
+struct BlobSource {
+    /// Unknown until the content is fetched, for platforms that don't expose it.
+    sha: Option<String>,
+    path: String,
+    rev: String,
+}
+
+/// The path of a file in one of the trees of a repo and that tree's revision.
+async fn get_blob_source(repo_id: i64, item_id: i64, pool: &SqlitePool) -> AppResult<BlobSource> {
+    let query = "
+        SELECT i.sha, i.path, COALESCE(t.rev, t.sha) AS rev
+        FROM repo_tree_item AS i
+        JOIN repo_tree AS t ON t.id = i.tree_id
+        WHERE i.repo_id = ? AND i.id = ? AND i.type = 'blob'
+    ";
+    let source = sqlx::query_as::<_, BlobSource>(query)
+        .bind(repo_id)
+        .bind(item_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository blob from database"
+        })?
+        .ok_or("Blob not found in any tree of the repository")?;
+    Ok(source)
+}
+
+/// Fetch a blob from the platform and write it to the cache, returning its SHA with the
+/// content.
+async fn cache_blob(
+    ctx: &RepoContext,
+    source: &BlobSource,
+    provider: &dyn PlatformProvider,
+    pool: &SqlitePool,
+) -> AppResult<(String, Vec<u8>)> {
+    let bytes = provider
+        .fetch_blob(ctx, &source.rev, &source.path, source.sha.as_deref(), pool)
+        .await?;
+    let sha = source.sha.clone().unwrap_or_else(|| git_blob_sha(&bytes));
+
+    let path = get_blob_cache_path(&sha);
+    ensure_dir(path.parent().unwrap()).await?;
+    fs::write(&path, &bytes).await?;
+    if source.sha.is_none() {
+        add_fetched_blob(ctx.repo_id, source, &sha, bytes.len() as i64, pool).await?;
+    }
+    mark_blob_cached(&sha, pool).await?;
+
+    Ok((sha, bytes))
+}
+
+/// Store the SHA of a file fetched without one, for every tree of the repo at the same
+/// revision, and add its blob to the repo.
+async fn add_fetched_blob(
+    repo_id: i64,
+    source: &BlobSource,
+    sha: &str,
+    size: i64,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("{:?}", e);
+        "Error adding repository blob to database"
+    })?;
+
+    // The size is stored with the blob from now on.
+    let item_query = "
+        UPDATE repo_tree_item SET sha = ?, size = NULL
+        WHERE repo_id = ? AND path = ? AND type = 'blob' AND sha IS NULL AND tree_id IN (
+            SELECT id FROM repo_tree WHERE repo_id = ? AND COALESCE(rev, sha) = ?
+        )
+    ";
+    sqlx::query(item_query)
+        .bind(sha)
+        .bind(repo_id)
+        .bind(&source.path)
+        .bind(repo_id)
+        .bind(&source.rev)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating repository tree items in database"
+        })?;
+
+    // Files with the same content share a blob.
+    let blob_query = "INSERT OR IGNORE INTO repo_blob (repo_id, sha, size) VALUES (?, ?, ?)";
+    sqlx::query(blob_query)
+        .bind(repo_id)
+        .bind(sha)
+        .bind(size)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding repository blob to database"
+        })?;
+
+    tx.commit().await.map_err(|e| {
+        error!("{:?}", e);
+        "Error adding repository blob to database"
+    })?;
+    Ok(())
+}
+
+/// Mark a cached blob as cached for every repo that has it.
+async fn mark_blob_cached(sha: &str, pool: &SqlitePool) -> AppResult<()> {
+    let query = "UPDATE repo_blob SET cached_at = ? WHERE sha = ? AND cached_at IS NULL";
+    sqlx::query(query)
+        .bind(Utc::now().to_rfc3339())
+        .bind(sha)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating repository blob in database"
+        })?;
+    Ok(())
+}
+
+/// Read the blob of a tree item from the cache, fetching and caching it first if needed.
+pub async fn get_blob(
+    ctx: &RepoContext,
+    item_id: i64,
+    provider: &dyn PlatformProvider,
+    pool: &SqlitePool,
+) -> AppResult<Blob> {
+    let start = Instant::now();
+
+    let source = get_blob_source(ctx.repo_id, item_id, pool).await?;
+    let (sha, bytes) = match &source.sha {
+        Some(sha) if fs::try_exists(get_blob_cache_path(sha)).await? => {
+            (sha.clone(), fs::read(get_blob_cache_path(sha)).await?)
+        }
+        _ => cache_blob(ctx, &source, provider, pool).await?,
+    };
+    let path = get_blob_cache_path(&sha);
+
+    info!("got blob \"{sha}\" in {:?}", start.elapsed());
+
+    Ok(Blob {
+        sha,
+        size: bytes.len() as u64,
+        path: path.to_string_lossy().into_owned(),
+        content: decode_blob(&bytes),
+    })
+}
+
+/// Cache every blob of a repo up to a size, which weren't cached yet. Interrupted
+/// prefetches continue with the remaining blobs.
+pub async fn prefetch_repo_blobs(
+    ctx: &RepoContext,
+    max_size: i64,
+    provider: &dyn PlatformProvider,
+    pool: &SqlitePool,
+    app: &AppHandle,
+) -> AppResult<()> {
+    let start = Instant::now();
+    let platform = provider.platform().to_string();
+
+    // One source per blob, with MAX() SQLite takes the other columns from a row of the
+    // default tree if the blob is in it. Files without a SHA are fetched once per
+    // revision and path, their blobs are only known afterwards.
+    let query = "
+        SELECT b.sha, i.path, COALESCE(t.rev, t.sha) AS rev, MAX(t.is_default)
+        FROM repo_blob AS b
+        JOIN repo_tree_item AS i ON i.repo_id = b.repo_id AND i.sha = b.sha AND i.type = 'blob'
+        JOIN repo_tree AS t ON t.id = i.tree_id
+        WHERE b.repo_id = ? AND b.cached_at IS NULL AND COALESCE(b.size, 0) <= ?
+        GROUP BY b.sha
+        UNION ALL
+        SELECT NULL, i.path, COALESCE(t.rev, t.sha) AS rev, 0
+        FROM repo_tree_item AS i
+        JOIN repo_tree AS t ON t.id = i.tree_id
+        WHERE i.repo_id = ? AND i.type = 'blob' AND i.sha IS NULL AND COALESCE(i.size, 0) <= ?
+        GROUP BY rev, i.path
+    ";
+    let sources = sqlx::query_as::<_, BlobSource>(query)
+        .bind(ctx.repo_id)
+        .bind(max_size)
+        .bind(ctx.repo_id)
+        .bind(max_size)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository blobs from database"
+        })?;
+
+    let total = sources.len();
+    let mut last_progress = None;
+    for (i, source) in sources.iter().enumerate() {
+        // Another repo with the same file may have cached it already.
+        match &source.sha {
+            Some(sha) if fs::try_exists(get_blob_cache_path(sha)).await? => {
+                mark_blob_cached(sha, pool).await?;
+            }
+            _ => {
+                cache_blob(ctx, source, provider, pool).await?;
+            }
+        }
+
+        let progress = progress_percentage(i, total);
+        if last_progress != Some(progress) {
+            last_progress = Some(progress);
+            AddRepoProgress::Blobs.send(
+                &platform,
+                &ctx.user,
+                &ctx.repo,
+                progress,
+                i as u64 + 1,
+                total as u64,
+                app,
+            );
+        }
+    }
+
+    info!(
+        "prefetched {} blobs of {}/{} in {:?}",
+        total,
+        ctx.user,
+        ctx.repo,
+        start.elapsed()
+    );
+
+    Ok(())
+}
+
+/// Remove the cached blobs of `shas` that no repo has anymore, after their `repo_blob`
+/// rows were deleted.
+pub async fn remove_cached_blobs(shas: &[String], pool: &SqlitePool) -> AppResult<()> {
+    let query = "SELECT EXISTS (SELECT 1 FROM repo_blob WHERE sha = ?)";
+    for sha in shas {
+        let referenced = sqlx::query_scalar::<_, bool>(query)
+            .bind(sha)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error getting repository blob from database"
+            })?;
+        let path = get_blob_cache_path(sha);
+        if !referenced && fs::try_exists(&path).await? {
+            fs::remove_file(&path).await?;
+        }
+    }
+    Ok(())
+}
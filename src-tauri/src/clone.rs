@@ -0,0 +1,179 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+use chrono::Utc;
+use gix::{
+    credentials::{helper::Action, protocol::Outcome},
+    remote::Direction,
+    sec::identity::Account,
+};
+use sqlx::SqlitePool;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{error::AppResult, platforms::provider::CloneRemote, utils::dirs::get_data_dir};
+
+/// Where the bare mirror of a repo is stored.
+pub fn get_clone_dir(repo_id: i64) -> PathBuf {
+    get_data_dir().join(format!("clones/{repo_id}.git"))
+}
+
+/// Every branch and tag is mirrored under the same name.
+const MIRROR_REFSPECS: [&str; 2] = ["+refs/heads/*:refs/heads/*", "+refs/tags/*:refs/tags/*"];
+
+struct CloneOutcome {
+    commit: Option<String>,
+    size: u64,
+}
+
+// The error type is gix's, so its size can't be reduced here.
+#[allow(clippy::result_large_err)]
+fn credentials_helper(
+    credentials: Option<(String, String)>,
+) -> impl FnMut(Action) -> gix::credentials::protocol::Result {
+    move |action| match (action, &credentials) {
+        (Action::Get(ctx), Some((username, password))) => Ok(Some(Outcome {
+            identity: Account {
+                username: username.clone(),
+                password: password.clone(),
+            },
+            next: ctx.into(),
+        })),
+        _ => Ok(None),
+    }
+}
+
+fn dir_size(dir: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+fn head_commit(repo: &gix::Repository) -> Option<String> {
+    repo.head_id().ok().map(|id| id.to_string())
+}
+
+/// Clone a new bare mirror, or fetch into the existing one. The credentials are only
+/// used for the connection and never written to the repo config.
+fn update_clone_blocking(
+    dir: &Path,
+    remote: CloneRemote,
+) -> Result<CloneOutcome, Box<dyn std::error::Error + Send + Sync>> {
+    let interrupt = AtomicBool::new(false);
+
+    let repo = if dir.exists() {
+        let repo = gix::open(dir)?;
+        let origin = repo.find_remote("origin")?;
+        origin
+            .connect(Direction::Fetch)?
+            .with_credentials(credentials_helper(remote.credentials))
+            .prepare_fetch(gix::progress::Discard, Default::default())?
+            .receive(gix::progress::Discard, &interrupt)?;
+        repo
+    } else {
+        let credentials = remote.credentials;
+        let (repo, _) = gix::prepare_clone_bare(remote.url.as_str(), dir)?
+            .configure_remote(|mut remote| {
+                remote.replace_refspecs(MIRROR_REFSPECS, Direction::Fetch)?;
+                Ok(remote)
+            })
+            .configure_connection(move |connection| {
+                connection.set_credentials(credentials_helper(credentials.clone()));
+                Ok(())
+            })
+            .fetch_only(gix::progress::Discard, &interrupt)?;
+        repo
+    };
+
+    Ok(CloneOutcome {
+        commit: head_commit(&repo),
+        size: dir_size(dir)?,
+    })
+}
+
+async fn set_clone_status(repo_id: i64, status: &str, pool: &SqlitePool) -> AppResult<()> {
+    let query = "UPDATE repo SET clone_status = ? WHERE id = ?";
+    sqlx::query(query)
+        .bind(status)
+        .bind(repo_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating repository clone status in database"
+        })?;
+    Ok(())
+}
+
+/// Clone the repo as a bare mirror, or fetch the new commits if it was already cloned.
+pub async fn update_repo_clone(
+    repo_id: i64,
+    remote: CloneRemote,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let start = Instant::now();
+    let dir = get_clone_dir(repo_id);
+
+    set_clone_status(repo_id, "cloning", pool).await?;
+
+    let clone_dir = dir.clone();
+    let outcome = tokio::task::spawn_blocking(move || update_clone_blocking(&clone_dir, remote))
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error waiting for repository clone"
+        })?;
+
+    let outcome = match outcome {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            error!("{:?}", e);
+            set_clone_status(repo_id, "failed", pool).await?;
+            return Err(format!("Error cloning repository: {e}").into());
+        }
+    };
+
+    let query = "
+        UPDATE repo
+        SET clone_status = 'cloned', clone_size = ?, clone_commit = ?, clone_fetched_at = ?
+        WHERE id = ?
+    ";
+    sqlx::query(query)
+        .bind(outcome.size as i64)
+        .bind(&outcome.commit)
+        .bind(Utc::now().to_rfc3339())
+        .bind(repo_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating repository clone in database"
+        })?;
+
+    info!(
+        "updated clone of repo \"{}\" at {:?} in {:?}",
+        repo_id,
+        outcome.commit,
+        start.elapsed()
+    );
+
+    Ok(())
+}
+
+pub async fn remove_repo_clone(repo_id: i64) -> AppResult<()> {
+    let dir = get_clone_dir(repo_id);
+    if tokio::fs::try_exists(&dir).await? {
+        tokio::fs::remove_dir_all(&dir).await?;
+    }
+    Ok(())
+}
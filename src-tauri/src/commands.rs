@@ -1,3 +1,12 @@
+pub mod auth;
+pub mod discussion;
+pub mod events;
+pub mod instance;
+pub mod issue;
+pub mod pull;
+pub mod rate_limit;
+pub mod release;
 pub mod repo;
 pub mod settings;
+pub mod sync;
 pub mod user;
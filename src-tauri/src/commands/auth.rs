@@ -0,0 +1,50 @@
+use tauri::State;
+use tokio::time::Instant;
+use tracing::info;
+
+use crate::{
+    error::AppResult,
+    platforms::github::models::{GitHubTokenInfo, GitHubTokenScopes},
+    state::AppState,
+};
+
+/// Validate a GitHub token, or the configured one if `token` is not provided.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn validate_github_token(
+    token: Option<String>,
+    state: State<'_, AppState>,
+) -> AppResult<GitHubTokenInfo> {
+    let start = Instant::now();
+    let (github, pool) = {
+        let state = state.lock().await;
+        (state.apis.github.clone(), state.pool.clone())
+    };
+
+    let token_info = github
+        .fetch_token_info(token.as_deref().filter(|t| !t.is_empty()), &pool)
+        .await?;
+
+    info!(
+        "validated github token for \"{}\" in {:?}",
+        token_info.login,
+        start.elapsed()
+    );
+
+    Ok(token_info)
+}
+
+/// Report what the configured GitHub token can access.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_github_token_scopes(state: State<'_, AppState>) -> AppResult<GitHubTokenScopes> {
+    let start = Instant::now();
+    let (github, pool) = {
+        let state = state.lock().await;
+        (state.apis.github.clone(), state.pool.clone())
+    };
+
+    let token_info = github.fetch_token_info(None, &pool).await?;
+
+    info!("got github token scopes in {:?}", start.elapsed());
+
+    Ok(token_info.scopes)
+}
@@ -0,0 +1,118 @@
+use tauri::State;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    commands::issue::{get_archive_repo, DEFAULT_LIST_LIMIT},
+    error::AppResult,
+    platforms::github::discussion::{
+        get_github_discussion, get_github_discussion_categories, get_github_discussions,
+        GitHubDiscussionCategory, GitHubDiscussionPreview, GitHubFullDiscussion,
+    },
+    state::AppState,
+};
+
+/// The discussion categories of a repo, by name.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_discussion_categories(
+    repo_id: i64,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<GitHubDiscussionCategory>> {
+    let start = Instant::now();
+    let state = state.lock().await;
+
+    get_archive_repo(repo_id, &state.pool).await?;
+    let categories = get_github_discussion_categories(repo_id, &state.pool).await?;
+
+    info!(
+        "fetched discussion categories of repo \"{repo_id}\" in {:?}",
+        start.elapsed()
+    );
+
+    Ok(categories)
+}
+
+/// The archived discussions of a repo, most recently updated first, optionally only
+/// those of a category.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_discussions(
+    repo_id: i64,
+    category_id: Option<String>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<GitHubDiscussionPreview>> {
+    let start = Instant::now();
+    let state = state.lock().await;
+
+    get_archive_repo(repo_id, &state.pool).await?;
+    let discussions = get_github_discussions(
+        repo_id,
+        category_id.as_deref(),
+        offset.unwrap_or(0),
+        limit.unwrap_or(DEFAULT_LIST_LIMIT),
+        &state.pool,
+    )
+    .await?;
+
+    info!(
+        "fetched discussions of repo \"{repo_id}\" in {:?}",
+        start.elapsed()
+    );
+
+    Ok(discussions)
+}
+
+/// An archived discussion of a repo with its reactions and threaded comments.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_discussion(
+    repo_id: i64,
+    number: i64,
+    state: State<'_, AppState>,
+) -> AppResult<GitHubFullDiscussion> {
+    let start = Instant::now();
+    let state = state.lock().await;
+
+    get_archive_repo(repo_id, &state.pool).await?;
+    let discussion = get_github_discussion(repo_id, number, &state.pool).await?;
+
+    info!(
+        "fetched discussion #{number} of repo \"{repo_id}\" in {:?}",
+        start.elapsed()
+    );
+
+    Ok(discussion)
+}
+
+/// Set whether to archive the discussions of a repo, which happens on the next sync.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_repo_archive_discussions(
+    id: i64,
+    archive_discussions: bool,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let start = Instant::now();
+    let state = state.lock().await;
+
+    if archive_discussions {
+        get_archive_repo(id, &state.pool).await?;
+    }
+
+    let query = "UPDATE repo SET archive_discussions = ? WHERE id = ?";
+    sqlx::query(query)
+        .bind(archive_discussions)
+        .bind(id)
+        .execute(&state.pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating repository in database"
+        })?;
+
+    info!(
+        "set discussion archive of repo \"{id}\" in {:?}",
+        start.elapsed()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use tauri::State;
+use tokio::time::Instant;
+use tracing::info;
+
+use crate::{
+    error::AppResult,
+    events::{self, RepoEventLogEntry},
+    state::AppState,
+};
+
+/// The feed of watched events of a repo, or of every repo, newest first.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_event_feed(
+    repo_id: Option<i64>,
+    unread_only: bool,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<RepoEventLogEntry>> {
+    let start = Instant::now();
+    let state = state.lock().await;
+    let entries =
+        events::get_repo_event_log(repo_id, unread_only, limit.unwrap_or(100), &state.pool).await?;
+    info!("fetched event feed in {:?}", start.elapsed());
+    Ok(entries)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_events_read(
+    ids: Vec<i64>,
+    read: bool,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let start = Instant::now();
+    let state = state.lock().await;
+    events::set_repo_events_read(&ids, read, &state.pool).await?;
+    info!("marked {} events in {:?}", ids.len(), start.elapsed());
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_all_events_read(
+    repo_id: Option<i64>,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let start = Instant::now();
+    let state = state.lock().await;
+    events::set_all_repo_events_read(repo_id, &state.pool).await?;
+    info!("marked all events read in {:?}", start.elapsed());
+    Ok(())
+}
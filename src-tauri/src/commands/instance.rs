@@ -0,0 +1,108 @@
+use std::str::FromStr;
+
+use chrono::Utc;
+use tauri::State;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    error::{AppError, AppResult},
+    instance::{self, normalize_base_url, Instance},
+    platforms::Platform,
+    state::AppState,
+};
+
+/// `api_url` and `content_url` are only needed when the host doesn't serve them at the
+/// platform's default paths.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn add_instance(
+    platform: String,
+    base_url: String,
+    api_url: Option<String>,
+    content_url: Option<String>,
+    token: Option<String>,
+    state: State<'_, AppState>,
+) -> AppResult<i64> {
+    let start = Instant::now();
+    let mut state = state.lock().await;
+
+    if !Platform::from_str(&platform)?.supports_instances() {
+        return AppError::new(&format!("Instances are not supported for {platform}"));
+    }
+    let Some(base_url) = normalize_base_url(&base_url) else {
+        return AppError::new("Instance URL must start with http:// or https://");
+    };
+    let api_url = normalize_optional_url(api_url)?;
+    let content_url = normalize_optional_url(content_url)?;
+
+    let query =
+        "INSERT INTO platform_instance (platform, base_url, api_url, content_url, token, created_at) VALUES (?, ?, ?, ?, ?, ?)";
+    let instance_id = sqlx::query(query)
+        .bind(&platform)
+        .bind(&base_url)
+        .bind(api_url)
+        .bind(content_url)
+        .bind(token.filter(|t| !t.is_empty()))
+        .bind(Utc::now().to_rfc3339())
+        .execute(&state.pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding platform instance into database"
+        })?
+        .last_insert_rowid();
+
+    let instance = instance::get_instance(instance_id, &state.pool).await?;
+    state.apis.register_instance(&instance)?;
+
+    info!(
+        "added {} instance \"{}\" in {:?}",
+        platform,
+        base_url,
+        start.elapsed()
+    );
+
+    Ok(instance_id)
+}
+
+fn normalize_optional_url(url: Option<String>) -> AppResult<Option<String>> {
+    match url.filter(|u| !u.trim().is_empty()) {
+        Some(url) => match normalize_base_url(&url) {
+            Some(url) => Ok(Some(url)),
+            None => AppError::new("Instance URLs must start with http:// or https://"),
+        },
+        None => Ok(None),
+    }
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_instances(state: State<'_, AppState>) -> AppResult<Vec<Instance>> {
+    let start = Instant::now();
+    let state = state.lock().await;
+    let instances = instance::get_instances(&state.pool).await?;
+    info!("fetched instances in {:?}", start.elapsed());
+    Ok(instances)
+}
+
+/// Instances that still have repos or users cannot be removed.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn remove_instance(id: i64, state: State<'_, AppState>) -> AppResult<()> {
+    let start = Instant::now();
+    let mut state = state.lock().await;
+
+    let query = "DELETE FROM platform_instance WHERE id = ?";
+    sqlx::query(query)
+        .bind(id)
+        .execute(&state.pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error deleting platform instance from database, it may still have repositories"
+        })?;
+
+    state.apis.unregister_instance(id);
+
+    info!("deleted instance \"{id}\" in {:?}", start.elapsed());
+
+    Ok(())
+}
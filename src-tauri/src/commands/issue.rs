@@ -0,0 +1,133 @@
+use std::str::FromStr;
+
+use sqlx::SqlitePool;
+use tauri::State;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    error::{AppError, AppResult},
+    platforms::{
+        github::issue::{get_github_issue, get_github_issues, GitHubFullIssue, GitHubIssuePreview},
+        provider::RepoContext,
+        Platform,
+    },
+    repo::Repo,
+    state::AppState,
+};
+
+/// Issues, pull requests and discussions are listed 50 at a time unless set otherwise.
+pub(super) const DEFAULT_LIST_LIMIT: i64 = 50;
+
+/// Load a repo, failing unless its issues, pull requests, discussions and releases can
+/// be archived.
+pub(super) async fn get_archive_repo(id: i64, pool: &SqlitePool) -> AppResult<Repo> {
+    let query = "SELECT * FROM repo WHERE id = ?";
+    let repo = sqlx::query_as::<_, Repo>(query)
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository from database"
+        })?;
+
+    if Platform::from_str(&repo.platform)? != Platform::GitHub {
+        return AppError::new(
+            "Archiving issues, pull requests, discussions and releases is only supported for GitHub.",
+        );
+    }
+
+    Ok(repo)
+}
+
+/// The archived issues of a repo, most recently updated first, optionally only those in
+/// a state ('open' or 'closed').
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_issues(
+    repo_id: i64,
+    state_filter: Option<String>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<GitHubIssuePreview>> {
+    let start = Instant::now();
+    let state = state.lock().await;
+
+    get_archive_repo(repo_id, &state.pool).await?;
+    let issues = get_github_issues(
+        repo_id,
+        state_filter.as_deref(),
+        offset.unwrap_or(0),
+        limit.unwrap_or(DEFAULT_LIST_LIMIT),
+        &state.pool,
+    )
+    .await?;
+
+    info!(
+        "fetched issues of repo \"{repo_id}\" in {:?}",
+        start.elapsed()
+    );
+
+    Ok(issues)
+}
+
+/// An archived issue of a repo with its labels, assignees and comments.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_issue(
+    repo_id: i64,
+    number: i64,
+    state: State<'_, AppState>,
+) -> AppResult<GitHubFullIssue> {
+    let start = Instant::now();
+    let state = state.lock().await;
+
+    let repo = get_archive_repo(repo_id, &state.pool).await?;
+    let ctx = RepoContext {
+        repo_id,
+        instance_id: repo.instance_id,
+        user: repo.user,
+        repo: repo.repo,
+    };
+    let issue = get_github_issue(&ctx, number, &state.pool).await?;
+
+    info!(
+        "fetched issue #{number} of repo \"{repo_id}\" in {:?}",
+        start.elapsed()
+    );
+
+    Ok(issue)
+}
+
+/// Set whether to archive the issues of a repo, which happens on the next sync.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_repo_archive_issues(
+    id: i64,
+    archive_issues: bool,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let start = Instant::now();
+    let state = state.lock().await;
+
+    if archive_issues {
+        get_archive_repo(id, &state.pool).await?;
+    }
+
+    let query = "UPDATE repo SET archive_issues = ? WHERE id = ?";
+    sqlx::query(query)
+        .bind(archive_issues)
+        .bind(id)
+        .execute(&state.pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating repository in database"
+        })?;
+
+    info!(
+        "set issue archive of repo \"{id}\" in {:?}",
+        start.elapsed()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use tauri::State;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    commands::issue::{get_archive_repo, DEFAULT_LIST_LIMIT},
+    error::AppResult,
+    platforms::github::pull::{
+        get_github_pull, get_github_pulls, GitHubFullPull, GitHubPullPreview,
+    },
+    state::AppState,
+};
+
+/// The archived pull requests of a repo, most recently updated first, optionally only
+/// those in a state ('open' or 'closed').
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_pulls(
+    repo_id: i64,
+    state_filter: Option<String>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<GitHubPullPreview>> {
+    let start = Instant::now();
+    let state = state.lock().await;
+
+    get_archive_repo(repo_id, &state.pool).await?;
+    let pulls = get_github_pulls(
+        repo_id,
+        state_filter.as_deref(),
+        offset.unwrap_or(0),
+        limit.unwrap_or(DEFAULT_LIST_LIMIT),
+        &state.pool,
+    )
+    .await?;
+
+    info!(
+        "fetched pull requests of repo \"{repo_id}\" in {:?}",
+        start.elapsed()
+    );
+
+    Ok(pulls)
+}
+
+/// An archived pull request of a repo with its reviews, review comments and diff.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_pull(
+    repo_id: i64,
+    number: i64,
+    state: State<'_, AppState>,
+) -> AppResult<GitHubFullPull> {
+    let start = Instant::now();
+    let state = state.lock().await;
+
+    get_archive_repo(repo_id, &state.pool).await?;
+    let pull = get_github_pull(repo_id, number, &state.pool).await?;
+
+    info!(
+        "fetched pull request #{number} of repo \"{repo_id}\" in {:?}",
+        start.elapsed()
+    );
+
+    Ok(pull)
+}
+
+/// Set whether to archive the pull requests of a repo, which happens on the next sync.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_repo_archive_pulls(
+    id: i64,
+    archive_pulls: bool,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let start = Instant::now();
+    let state = state.lock().await;
+
+    if archive_pulls {
+        get_archive_repo(id, &state.pool).await?;
+    }
+
+    let query = "UPDATE repo SET archive_pulls = ? WHERE id = ?";
+    sqlx::query(query)
+        .bind(archive_pulls)
+        .bind(id)
+        .execute(&state.pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating repository in database"
+        })?;
+
+    info!(
+        "set pull request archive of repo \"{id}\" in {:?}",
+        start.elapsed()
+    );
+
+    Ok(())
+}
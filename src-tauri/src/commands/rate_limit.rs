@@ -0,0 +1,32 @@
+use tauri::State;
+use tokio::time::Instant;
+use tracing::info;
+
+use crate::{
+    error::AppResult,
+    state::AppState,
+    utils::rate_limit::{self, RateLimit, RateLimitUsage},
+};
+
+/// The last known state of every rate limit, per platform, host, token and resource.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_rate_limits(state: State<'_, AppState>) -> AppResult<Vec<RateLimit>> {
+    let start = Instant::now();
+    let state = state.lock().await;
+    let rate_limits = rate_limit::get_rate_limits(&state.pool).await?;
+    info!("fetched rate limits in {:?}", start.elapsed());
+    Ok(rate_limits)
+}
+
+/// The calls each sync run of a repo, or of every repo, made per rate limit.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_rate_limit_usage(
+    repo_id: Option<i64>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<RateLimitUsage>> {
+    let start = Instant::now();
+    let state = state.lock().await;
+    let usage = rate_limit::get_rate_limit_usage(repo_id, &state.pool).await?;
+    info!("fetched rate limit usage in {:?}", start.elapsed());
+    Ok(usage)
+}
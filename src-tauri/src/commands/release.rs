@@ -0,0 +1,93 @@
+use tauri::State;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    commands::issue::get_archive_repo,
+    error::AppResult,
+    platforms::github::release::{
+        get_github_releases, get_release_asset_globs, set_release_asset_globs, GitHubRelease,
+    },
+    state::AppState,
+};
+
+/// The archived releases of a repo with their assets, most recently published first.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_releases(
+    repo_id: i64,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<GitHubRelease>> {
+    let start = Instant::now();
+    let state = state.lock().await;
+
+    get_archive_repo(repo_id, &state.pool).await?;
+    let releases = get_github_releases(repo_id, &state.pool).await?;
+
+    info!(
+        "fetched releases of repo \"{repo_id}\" in {:?}",
+        start.elapsed()
+    );
+
+    Ok(releases)
+}
+
+/// The asset name globs of a repo, every asset is downloaded if there are none.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_repo_release_asset_globs(
+    id: i64,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<String>> {
+    let start = Instant::now();
+    let state = state.lock().await;
+    let globs = get_release_asset_globs(id, &state.pool).await?;
+    info!(
+        "fetched release asset globs of repo \"{id}\" in {:?}",
+        start.elapsed()
+    );
+    Ok(globs)
+}
+
+/// Set whether to archive the releases of a repo and which of their assets to
+/// download, which happens on the next sync.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_repo_release_options(
+    id: i64,
+    archive_releases: bool,
+    download_release_assets: bool,
+    release_asset_max_size: i64,
+    release_asset_globs: Vec<String>,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let start = Instant::now();
+    let state = state.lock().await;
+
+    if archive_releases {
+        get_archive_repo(id, &state.pool).await?;
+    }
+
+    set_release_asset_globs(id, &release_asset_globs, &state.pool).await?;
+
+    let query = "
+        UPDATE repo
+        SET archive_releases = ?, download_release_assets = ?, release_asset_max_size = ?
+        WHERE id = ?
+    ";
+    sqlx::query(query)
+        .bind(archive_releases)
+        .bind(download_release_assets)
+        .bind(release_asset_max_size)
+        .bind(id)
+        .execute(&state.pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating repository in database"
+        })?;
+
+    info!(
+        "set release options of repo \"{id}\" in {:?}",
+        start.elapsed()
+    );
+
+    Ok(())
+}
@@ -1,38 +1,94 @@
-use std::str::FromStr;
+use std::{future::Future, str::FromStr};
 
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use sqlx::{prelude::FromRow, SqlitePool};
-use tauri::{AppHandle, Emitter, State};
+use tauri::{AppHandle, Emitter, Runtime, State};
 use tokio::time::Instant;
 use tracing::{error, info};
 
 use crate::{
+    blob::{self, prefetch_repo_blobs, Blob},
+    clone::update_repo_clone,
     error::{AppError, AppResult},
     events::watch_repo_events,
+    instance::{get_instance, get_instances},
     platforms::{
-        github::{add_github_repo, get_github_repo, get_github_repo_preview},
+        github::release::set_release_asset_globs,
+        provider::{PlatformProvider, RepoContext},
         Platform,
     },
-    repo::{PlatformRepoData, Repo, RepoTree, RepoTreeItem},
+    repo::{
+        add_repo_data, add_repo_metadata_snapshot, add_repo_tree, delete_repo,
+        get_repo_metadata_history, get_repo_trees, get_stored_repo_refs, refresh_repo_refs,
+        PlatformRepoData, Repo, RepoMetadataSnapshot, RepoTree, RepoTreeItem, StoredRepoRef,
+    },
     state::AppState,
+    submodule::{add_submodule_link, get_repo_submodules, resolve_submodule_url},
+    sync::{finish_sync_run, start_sync_run},
+    utils::data::{is_commit_sha, progress_percentage},
 };
 
-#[derive(Deserialize)]
+/// Files up to 1 MiB are prefetched unless set otherwise.
+const DEFAULT_PREFETCH_MAX_SIZE: i64 = 1024 * 1024;
+
+/// Release assets up to 100 MiB are downloaded unless set otherwise.
+const DEFAULT_RELEASE_ASSET_MAX_SIZE: i64 = 100 * 1024 * 1024;
+
+#[derive(Deserialize, Clone)]
 pub struct AddRepoData {
     platform: String,
+    /// The self-hosted instance to add the repo from, the platform's public host if `None`.
+    #[serde(default)]
+    instance_id: Option<i64>,
     user: String,
     repo: String,
-    /// Whether to add submodules as repos.
+    /// Whether to mirror the repo as a local bare clone.
     clone_data: bool,
     auto_sync: u8,
+    /// Whether to add the submodules as repos with the same options, linked to this one.
     add_submodules: bool,
     watch_events: Vec<String>,
+    /// Whether to cache the content of every file up to `prefetch_max_size` bytes.
+    #[serde(default)]
+    prefetch_blobs: bool,
+    #[serde(default)]
+    prefetch_max_size: Option<i64>,
+    /// Whether to archive the issues and their comments on every sync.
+    #[serde(default)]
+    archive_issues: bool,
+    /// Whether to archive the pull requests with their reviews and diffs on every sync.
+    #[serde(default)]
+    archive_pulls: bool,
+    /// Whether to archive the discussions with their comments on every sync.
+    #[serde(default)]
+    archive_discussions: bool,
+    /// Whether to archive the releases on every sync.
+    #[serde(default)]
+    archive_releases: bool,
+    /// Whether to download the release assets matching `release_asset_globs`, every
+    /// asset if there are none.
+    #[serde(default)]
+    download_release_assets: bool,
+    #[serde(default)]
+    release_asset_max_size: Option<i64>,
+    #[serde(default)]
+    release_asset_globs: Vec<String>,
 }
 
-/// Check if the repo already exists.
-async fn check_repo_exists(user: &str, repo: &str, pool: &SqlitePool) -> AppResult<bool> {
-    let exists = sqlx::query("SELECT id FROM repo WHERE user = ? AND repo = ?")
+/// Get the ID of a repo, if it was already added.
+async fn find_repo_id(
+    platform: &str,
+    instance_id: Option<i64>,
+    user: &str,
+    repo: &str,
+    pool: &SqlitePool,
+) -> AppResult<Option<i64>> {
+    let query =
+        "SELECT id FROM repo WHERE platform = ? AND instance_id IS ? AND user = ? AND repo = ?";
+    let repo_id = sqlx::query_scalar::<_, i64>(query)
+        .bind(platform)
+        .bind(instance_id)
         .bind(user)
         .bind(repo)
         .fetch_optional(pool)
@@ -41,7 +97,7 @@ async fn check_repo_exists(user: &str, repo: &str, pool: &SqlitePool) -> AppResu
             error!("{:?}", e);
             "Error checking if repository exists in database"
         })?;
-    Ok(exists.is_some())
+    Ok(repo_id)
 }
 
 #[derive(Serialize, Clone)]
@@ -63,11 +119,14 @@ pub enum AddRepoProgress {
     InsertTree,
     Readme,
     Owner,
+    Clone,
+    Blobs,
+    Submodules,
 }
 
 impl AddRepoProgress {
     #[allow(clippy::too_many_arguments)]
-    pub fn send(
+    pub fn send<R: Runtime>(
         self,
         platform: &str,
         user: &str,
@@ -75,7 +134,7 @@ impl AddRepoProgress {
         percentage: u8,
         step: u64,
         total_steps: u64,
-        app: &AppHandle,
+        app: &AppHandle<R>,
     ) {
         app.emit(
             "add-repo-progress",
@@ -100,23 +159,83 @@ pub async fn add_repo(
     app: AppHandle,
 ) -> AppResult<i64> {
     let start = Instant::now();
-    let state = state.lock().await;
-
-    if check_repo_exists(&repo.user, &repo.repo, &state.pool).await? {
+    let pool = state.lock().await.pool.clone();
+
+    if find_repo_id(
+        &repo.platform,
+        repo.instance_id,
+        &repo.user,
+        &repo.repo,
+        &pool,
+    )
+    .await?
+    .is_some()
+    {
         return AppError::new("Repository already exists.");
     }
 
-    let query =
-        "INSERT INTO repo (platform, user, repo, clone_data, auto_sync, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)";
+    let repo_id = add_new_repo(&repo, &state, &pool, &app).await?;
+
+    info!(
+        "added repo {}/{} in {:?}",
+        repo.user,
+        repo.repo,
+        start.elapsed()
+    );
+
+    Ok(repo_id)
+}
+
+/// The state is only locked to get the providers, so other commands aren't blocked
+/// while the repo and its submodules are fetched.
+async fn add_new_repo(
+    repo: &AddRepoData,
+    state: &AppState,
+    pool: &SqlitePool,
+    app: &AppHandle,
+) -> AppResult<i64> {
+    let platform = Platform::from_str(&repo.platform)?;
+    if let Some(instance_id) = repo.instance_id {
+        let instance = get_instance(instance_id, pool).await?;
+        if !platform.supports_instances() {
+            return AppError::new(&format!("Instances are not supported for {platform}."));
+        }
+        if instance.platform != repo.platform {
+            return AppError::new("The instance belongs to a different platform.");
+        }
+    }
+    let provider = state.lock().await.apis.get(platform, repo.instance_id)?;
+
+    let query = "
+        INSERT INTO repo (
+            platform, instance_id, user, repo, clone_data, auto_sync, created_at, updated_at,
+            status, prefetch_blobs, prefetch_max_size, archive_issues,
+            archive_pulls, archive_discussions, archive_releases, download_release_assets,
+            release_asset_max_size
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, 'adding', ?, ?, ?, ?, ?, ?, ?, ?)
+    ";
     let repo_id = sqlx::query(query)
         .bind(&repo.platform)
+        .bind(repo.instance_id)
         .bind(&repo.user)
         .bind(&repo.repo)
         .bind(repo.clone_data)
         .bind(repo.auto_sync)
         .bind(Utc::now().to_rfc3339())
         .bind(Utc::now().to_rfc3339())
-        .execute(&state.pool)
+        .bind(repo.prefetch_blobs)
+        .bind(repo.prefetch_max_size.unwrap_or(DEFAULT_PREFETCH_MAX_SIZE))
+        .bind(repo.archive_issues)
+        .bind(repo.archive_pulls)
+        .bind(repo.archive_discussions)
+        .bind(repo.archive_releases)
+        .bind(repo.download_release_assets)
+        .bind(
+            repo.release_asset_max_size
+                .unwrap_or(DEFAULT_RELEASE_ASSET_MAX_SIZE),
+        )
+        .execute(pool)
         .await
         .map_err(|e| {
             error!("{:?}", e);
@@ -124,46 +243,211 @@ pub async fn add_repo(
         })?
         .last_insert_rowid();
 
-    match Platform::from_str(&repo.platform)? {
-        Platform::Bitbucket => {}
-        Platform::GitHub => {
-            add_github_repo(
-                repo_id,
-                &repo.user,
-                &repo.repo,
-                &state.apis.github,
-                &state.pool,
-                &app,
-            )
-            .await?
-        }
-        Platform::GitLab => {}
-        Platform::Gitea => {}
+    if let Err(e) = add_new_repo_data(repo_id, repo, provider.as_ref(), state, pool, app).await {
+        remove_failed_repo(repo_id, pool).await;
+        return Err(e);
     }
 
+    let query = "UPDATE repo SET status = 'ready' WHERE id = ?";
+    sqlx::query(query)
+        .bind(repo_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating repository in database"
+        })?;
+
+    Ok(repo_id)
+}
+
+/// Remove a repo whose add failed, so a retry doesn't find it already added. Adding it
+/// fetches from the platform between writes and also writes files, which a transaction
+/// can't span, so the repo is deleted again instead. If that fails as well, it stays
+/// marked 'adding' and is removed on the next start.
+async fn remove_failed_repo(repo_id: i64, pool: &SqlitePool) {
+    if let Err(e) = delete_repo(repo_id, pool).await {
+        error!("{:?}", e);
+    }
+}
+
+/// Fetch and store everything of a new repo after its row was inserted.
+async fn add_new_repo_data(
+    repo_id: i64,
+    repo: &AddRepoData,
+    provider: &dyn PlatformProvider,
+    state: &AppState,
+    pool: &SqlitePool,
+    app: &AppHandle,
+) -> AppResult<()> {
+    let ctx = RepoContext {
+        repo_id,
+        instance_id: repo.instance_id,
+        user: repo.user.clone(),
+        repo: repo.repo.clone(),
+    };
+    let rev = add_repo_data(provider, &ctx, pool, app).await?;
+    add_repo_metadata_snapshot(repo_id, provider, pool).await?;
+
     if repo.clone_data {
-        // TODO: Clone the repository file contents.
+        AddRepoProgress::Clone.send(&repo.platform, &repo.user, &repo.repo, 0, 0, 1, app);
+        // The clone is recorded as failed and can be retried, so it doesn't fail the add.
+        if let Err(e) = update_repo_clone(repo_id, provider.clone_remote(&ctx), pool).await {
+            error!("{:?}", e);
+        }
+        AddRepoProgress::Clone.send(&repo.platform, &repo.user, &repo.repo, 100, 1, 1, app);
+    }
+
+    if repo.prefetch_blobs {
+        let max_size = repo.prefetch_max_size.unwrap_or(DEFAULT_PREFETCH_MAX_SIZE);
+        let prefetch = prefetch_repo_blobs(&ctx, max_size, provider, pool, app);
+        run_retried_add_step(repo_id, "Prefetching files", prefetch, pool).await?;
+    }
+
+    if repo.archive_issues {
+        let archive = provider.archive_issues(&ctx, pool);
+        run_retried_add_step(repo_id, "Archiving issues", archive, pool).await?;
+    }
+
+    if repo.archive_pulls {
+        let archive = provider.archive_pulls(&ctx, pool);
+        run_retried_add_step(repo_id, "Archiving pull requests", archive, pool).await?;
+    }
+
+    if repo.archive_discussions {
+        // Discussions need a token for the GraphQL API, without one this step fails.
+        let archive = provider.archive_discussions(&ctx, pool);
+        run_retried_add_step(repo_id, "Archiving discussions", archive, pool).await?;
+    }
+
+    if !repo.release_asset_globs.is_empty() {
+        set_release_asset_globs(repo_id, &repo.release_asset_globs, pool).await?;
+    }
+
+    if repo.archive_releases {
+        let archive = provider.archive_releases(&ctx, pool);
+        run_retried_add_step(repo_id, "Archiving releases", archive, pool).await?;
     }
 
     if !repo.watch_events.is_empty() {
-        watch_repo_events(repo_id, repo.watch_events, &state.pool).await?;
+        watch_repo_events(repo_id, repo.watch_events.clone(), pool).await?;
     }
 
+    // Stored after the watches are created, which records the refs as their first state.
+    let refresh = refresh_repo_refs(provider, &ctx, pool, app);
+    run_retried_add_step(repo_id, "Fetching branches and tags", refresh, pool).await?;
+
     if repo.add_submodules {
-        // TODO: Add the submodules in the repo with the same configuration.
-        // 1. Fetch the tree from API.
-        // 2. Extract submodules.
-        // 3. Fetch submodules and add the same way as repo.
+        // Syncs don't add submodules, a failure is only recorded.
+        let add = add_submodules(repo, provider, &ctx, &rev, state, pool, app);
+        run_retried_add_step(repo_id, "Adding submodules", add, pool).await?;
     }
 
-    info!(
-        "added repo {}/{} in {:?}",
-        repo.user,
-        repo.repo,
-        start.elapsed()
+    Ok(())
+}
+
+/// Run an optional step of adding a repo, so it doesn't fail the add. Most of them are
+/// picked up again by the next sync where they stopped. A failure is recorded as a failed
+/// sync run of the 'add' kind for the UI to show.
+async fn run_retried_add_step<T>(
+    repo_id: i64,
+    step: &str,
+    future: impl Future<Output = AppResult<T>>,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    if let Err(e) = future.await {
+        error!("{:?}", e);
+        let run_id = start_sync_run(repo_id, "add", pool).await?;
+        finish_sync_run(run_id, "failed", Some(format!("{step}: {e}")), pool).await?;
+    }
+    Ok(())
+}
+
+/// Add the submodules of a repo with the same options and link them to it. Submodules
+/// that were already added, including the repos further up a cycle since they are
+/// inserted before their submodules, are only linked.
+async fn add_submodules(
+    repo: &AddRepoData,
+    provider: &dyn PlatformProvider,
+    ctx: &RepoContext,
+    rev: &str,
+    state: &AppState,
+    pool: &SqlitePool,
+    app: &AppHandle,
+) -> AppResult<()> {
+    let modules = get_repo_submodules(provider, ctx, rev, pool).await?;
+    if modules.is_empty() {
+        return Ok(());
+    }
+
+    let instances = get_instances(pool).await?;
+    let total_steps = modules.len() as u64;
+
+    for (i, module) in modules.into_iter().enumerate() {
+        let progress = progress_percentage(i, total_steps as usize);
+        AddRepoProgress::Submodules.send(
+            &repo.platform,
+            &repo.user,
+            &repo.repo,
+            progress,
+            i as u64,
+            total_steps,
+            app,
+        );
+
+        let Some(target) = resolve_submodule_url(&module.url, provider.platform(), ctx, &instances)
+        else {
+            info!("skipping unsupported submodule url \"{}\"", module.url);
+            continue;
+        };
+
+        let platform = target.platform.to_string();
+        let existing_id = find_repo_id(
+            &platform,
+            target.instance_id,
+            &target.user,
+            &target.repo,
+            pool,
+        )
+        .await?;
+
+        let submodule_repo_id = match existing_id {
+            Some(id) => id,
+            None => {
+                let submodule = AddRepoData {
+                    platform,
+                    instance_id: target.instance_id,
+                    user: target.user,
+                    repo: target.repo,
+                    ..repo.clone()
+                };
+                // A submodule that can't be added (e.g. it is private) doesn't fail its parent.
+                match Box::pin(add_new_repo(&submodule, state, pool, app)).await {
+                    Ok(id) => id,
+                    Err(e) => {
+                        error!("{:?}", e);
+                        continue;
+                    }
+                }
+            }
+        };
+
+        if submodule_repo_id != ctx.repo_id {
+            add_submodule_link(ctx.repo_id, submodule_repo_id, &module.path, pool).await?;
+        }
+    }
+
+    AddRepoProgress::Submodules.send(
+        &repo.platform,
+        &repo.user,
+        &repo.repo,
+        100,
+        total_steps,
+        total_steps,
+        app,
     );
 
-    Ok(repo_id)
+    Ok(())
 }
 
 #[derive(Serialize)]
@@ -192,6 +476,7 @@ pub struct RepoPreview {
 pub struct DbRepoPreview {
     id: i64,
     platform: String,
+    instance_id: Option<i64>,
     repo: String,
     clone_data: bool,
     updated_at: String,
@@ -213,7 +498,8 @@ pub async fn get_repo_list(state: State<'_, AppState>) -> AppResult<Vec<RepoPrev
     let state = state.lock().await;
     let mut repos: Vec<RepoPreview> = Vec::new();
 
-    let query = "SELECT id, platform, repo, clone_data, updated_at FROM repo LIMIT 100";
+    let query =
+        "SELECT id, platform, instance_id, repo, clone_data, updated_at FROM repo LIMIT 100";
     let db_repos = sqlx::query_as::<_, DbRepoPreview>(query)
         .fetch_all(&state.pool)
         .await
@@ -223,11 +509,11 @@ pub async fn get_repo_list(state: State<'_, AppState>) -> AppResult<Vec<RepoPrev
         })?;
 
     for repo in db_repos {
-        // let (platform_repo, platform_owner) = match Platform::from_str(&repo.platform)? {
-        //     _ => get_github_repo_preview(repo.id, &state.pool).await?,
-        // };
-
-        let (platform_repo, platform_owner) = get_github_repo_preview(repo.id, &state.pool).await?;
+        let provider = state
+            .apis
+            .get(Platform::from_str(&repo.platform)?, repo.instance_id)?;
+        let (platform_repo, platform_owner) =
+            provider.get_repo_preview(repo.id, &state.pool).await?;
 
         repos.push(RepoPreview {
             id: repo.id,
@@ -262,6 +548,7 @@ pub struct FullRepo {
 pub async fn get_repo(
     id: i64,
     tree_id: Option<i64>,
+    tree_ref: Option<String>,
     state: State<'_, AppState>,
 ) -> AppResult<FullRepo> {
     let start = Instant::now();
@@ -277,46 +564,52 @@ pub async fn get_repo(
             "Error getting repository from database"
         })?;
 
-    let platform_repo = match Platform::from_str(&repo.platform)? {
-        Platform::Bitbucket => PlatformRepoData::Bitbucket,
-        Platform::GitHub => {
-            let a = get_github_repo(id, &state.pool).await?;
-            PlatformRepoData::GitHub(a)
-        }
-        Platform::GitLab => PlatformRepoData::GitLab,
-        Platform::Gitea => PlatformRepoData::Gitea,
-    };
+    let platform_repo = state
+        .apis
+        .get(Platform::from_str(&repo.platform)?, repo.instance_id)?
+        .get_repo(id, &state.pool)
+        .await?;
 
-    let tree_query = "SELECT sha, truncated FROM repo_tree WHERE repo_id = ?";
-    let tree = sqlx::query_as(tree_query)
+    // The default branch, unless the tree of another ref was added.
+    let tree_query = "
+        SELECT *
+        FROM repo_tree
+        WHERE repo_id = ? AND (ref = ? OR (? IS NULL AND is_default = 1))
+    ";
+    let tree = sqlx::query_as::<_, RepoTree>(tree_query)
         .bind(id)
-        .fetch_one(&state.pool)
+        .bind(&tree_ref)
+        .bind(&tree_ref)
+        .fetch_optional(&state.pool)
         .await
         .map_err(|e| {
             error!("{:?}", e);
             "Error getting repository tree from database"
-        })?;
+        })?
+        .ok_or("Repository tree not found, add the tree of this ref first")?;
 
     let parent_id_query = if let Some(tree_id) = tree_id {
-        &format!("parent_id = {}", tree_id)
+        &format!("i.parent_id = {}", tree_id)
     } else {
-        "parent_id IS NULL"
+        "i.parent_id IS NULL"
     };
     let tree_items_query = format!(
         "
-        SELECT *
-        FROM repo_tree_item
-        WHERE repo_id = ? AND {}
+        SELECT i.id, i.path, i.name, i.depth, i.mode, i.type, i.sha, COALESCE(b.size, i.size) AS size
+        FROM repo_tree_item AS i
+        LEFT JOIN repo_blob AS b ON b.repo_id = i.repo_id AND b.sha = i.sha AND i.type = 'blob'
+        WHERE i.tree_id = ? AND {}
         ORDER BY
             CASE
-                WHEN type = 'tree' THEN 0
+                WHEN i.type = 'tree' THEN 0
                 ELSE 1
-            END;
+            END,
+            i.name;
         ",
         parent_id_query
     );
     let tree_items = sqlx::query_as::<_, RepoTreeItem>(&tree_items_query)
-        .bind(id)
+        .bind(tree.id)
         .fetch_all(&state.pool)
         .await
         .map_err(|e| {
@@ -355,17 +648,323 @@ pub async fn remove_repo(id: i64, state: State<'_, AppState>) -> AppResult<()> {
     let start = Instant::now();
     let state = state.lock().await;
 
-    let query = "DELETE FROM repo WHERE id = ?";
+    delete_repo(id, &state.pool).await?;
+
+    info!("deleted repo \"{id}\" in {:?}", start.elapsed());
+
+    Ok(())
+}
+
+/// Clone the repo, or fetch into the existing clone.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn fetch_repo_clone(id: i64, state: State<'_, AppState>) -> AppResult<()> {
+    let start = Instant::now();
+    let pool = state.lock().await.pool.clone();
+
+    let query = "SELECT * FROM repo WHERE id = ?";
+    let repo = sqlx::query_as::<_, Repo>(query)
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository from database"
+        })?;
+
+    if !repo.clone_data {
+        return AppError::new("Repository data is not set to be cloned.");
+    }
+
+    let provider = state
+        .lock()
+        .await
+        .apis
+        .get(Platform::from_str(&repo.platform)?, repo.instance_id)?;
+    let ctx = RepoContext {
+        repo_id: repo.id,
+        instance_id: repo.instance_id,
+        user: repo.user,
+        repo: repo.repo,
+    };
+    update_repo_clone(id, provider.clone_remote(&ctx), &pool).await?;
+
+    info!("fetched clone of repo \"{id}\" in {:?}", start.elapsed());
+
+    Ok(())
+}
+
+/// The metadata snapshots of a repo, oldest first, to chart e.g. star growth.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_repo_history(
+    id: i64,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<RepoMetadataSnapshot>> {
+    let start = Instant::now();
+    let state = state.lock().await;
+    let history = get_repo_metadata_history(id, &state.pool).await?;
+    info!("fetched history of repo \"{id}\" in {:?}", start.elapsed());
+    Ok(history)
+}
+
+/// The stored branches and tags of a repo with their trees, for switching between them.
+/// Refs are refreshed on sync, repos added before refs were stored fetch them once here.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_repo_refs(
+    id: i64,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> AppResult<Vec<StoredRepoRef>> {
+    let start = Instant::now();
+    let pool = state.lock().await.pool.clone();
+
+    let mut refs = get_stored_repo_refs(id, &pool).await?;
+    if refs.is_empty() {
+        let query = "SELECT * FROM repo WHERE id = ?";
+        let repo = sqlx::query_as::<_, Repo>(query)
+            .bind(id)
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error getting repository from database"
+            })?;
+
+        let provider = state
+            .lock()
+            .await
+            .apis
+            .get(Platform::from_str(&repo.platform)?, repo.instance_id)?;
+        let ctx = RepoContext {
+            repo_id: repo.id,
+            instance_id: repo.instance_id,
+            user: repo.user,
+            repo: repo.repo,
+        };
+        refresh_repo_refs(provider.as_ref(), &ctx, &pool, &app).await?;
+        refs = get_stored_repo_refs(id, &pool).await?;
+    }
+
+    info!(
+        "fetched {} refs of repo \"{id}\" in {:?}",
+        refs.len(),
+        start.elapsed()
+    );
+
+    Ok(refs)
+}
+
+/// Fetch and store the tree of a branch, tag or commit SHA of a repo, replacing the
+/// one stored for it before. Trees other than the default branch's aren't synced.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn add_repo_ref_tree(
+    id: i64,
+    tree_ref: String,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> AppResult<RepoTree> {
+    let start = Instant::now();
+    let pool = state.lock().await.pool.clone();
+
+    let query = "SELECT * FROM repo WHERE id = ?";
+    let repo = sqlx::query_as::<_, Repo>(query)
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository from database"
+        })?;
+
+    let provider = state
+        .lock()
+        .await
+        .apis
+        .get(Platform::from_str(&repo.platform)?, repo.instance_id)?;
+    let ctx = RepoContext {
+        repo_id: repo.id,
+        instance_id: repo.instance_id,
+        user: repo.user.clone(),
+        repo: repo.repo.clone(),
+    };
+
+    // Tags are resolved to their commit, not every platform fetches trees by tag name.
+    let rev = if is_commit_sha(&tree_ref) {
+        tree_ref.clone()
+    } else {
+        provider
+            .fetch_refs(&ctx, &pool)
+            .await?
+            .into_iter()
+            .find(|r| r.name == tree_ref)
+            .map(|r| r.sha)
+            .ok_or("Branch or tag not found")?
+    };
+
+    let platform = provider.platform().to_string();
+    let tree = provider.fetch_tree(&ctx, &rev, &pool).await?;
+    let tree_id = add_repo_tree(
+        id, &tree_ref, false, &tree, &platform, &repo.user, &repo.repo, &pool, &app,
+    )
+    .await?;
+
+    let trees = get_repo_trees(id, &pool).await?;
+    let tree = trees
+        .into_iter()
+        .find(|tree| tree.id == tree_id)
+        .ok_or("Error getting repository tree from database")?;
+
+    info!(
+        "added tree of \"{tree_ref}\" of repo \"{id}\" in {:?}",
+        start.elapsed()
+    );
+
+    Ok(tree)
+}
+
+/// The stored trees of a repo, the default branch first.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_repo_ref_trees(id: i64, state: State<'_, AppState>) -> AppResult<Vec<RepoTree>> {
+    let start = Instant::now();
+    let state = state.lock().await;
+    let trees = get_repo_trees(id, &state.pool).await?;
+    info!("fetched trees of repo \"{id}\" in {:?}", start.elapsed());
+    Ok(trees)
+}
+
+/// The content of a file of a repo by its tree item ID, fetched from the platform unless
+/// its blob was cached.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_blob(id: i64, item_id: i64, state: State<'_, AppState>) -> AppResult<Blob> {
+    let start = Instant::now();
+    let pool = state.lock().await.pool.clone();
+
+    let query = "SELECT * FROM repo WHERE id = ?";
+    let repo = sqlx::query_as::<_, Repo>(query)
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository from database"
+        })?;
+
+    let provider = state
+        .lock()
+        .await
+        .apis
+        .get(Platform::from_str(&repo.platform)?, repo.instance_id)?;
+    let ctx = RepoContext {
+        repo_id: repo.id,
+        instance_id: repo.instance_id,
+        user: repo.user,
+        repo: repo.repo,
+    };
+    let blob = blob::get_blob(&ctx, item_id, provider.as_ref(), &pool).await?;
+
+    info!("fetched blob of repo \"{id}\" in {:?}", start.elapsed());
+
+    Ok(blob)
+}
+
+/// Set whether to cache the content of every file of a repo up to a size, which
+/// happens on the next sync.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_repo_prefetch(
+    id: i64,
+    prefetch_blobs: bool,
+    prefetch_max_size: i64,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let start = Instant::now();
+    let state = state.lock().await;
+
+    let query = "UPDATE repo SET prefetch_blobs = ?, prefetch_max_size = ? WHERE id = ?";
     sqlx::query(query)
+        .bind(prefetch_blobs)
+        .bind(prefetch_max_size)
         .bind(id)
         .execute(&state.pool)
         .await
         .map_err(|e| {
             error!("{:?}", e);
-            "Error deleting repository from database"
+            "Error updating repository in database"
         })?;
 
-    info!("deleted repo \"{id}\" in {:?}", start.elapsed());
+    info!("set prefetch of repo \"{id}\" in {:?}", start.elapsed());
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{repo::reconcile_repos, sync::get_sync_runs, utils::testing::memory_pool};
+
+    #[tokio::test]
+    async fn failed_add_step_is_recorded_as_sync_run() {
+        let pool = memory_pool().await;
+        let query = "
+            INSERT INTO repo (platform, user, repo, created_at, updated_at)
+            VALUES ('github', 'user', 'repo', '', '')
+        ";
+        let repo_id = sqlx::query(query)
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+
+        run_retried_add_step(repo_id, "Archiving issues", async { Ok(()) }, &pool)
+            .await
+            .unwrap();
+        let failing = async { AppError::new::<()>("Rate limit exceeded") };
+        run_retried_add_step(repo_id, "Archiving pull requests", failing, &pool)
+            .await
+            .unwrap();
+
+        let runs = get_sync_runs(repo_id, &pool).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].kind, "add");
+        assert_eq!(runs[0].outcome, "failed");
+        assert_eq!(
+            runs[0].error.as_deref(),
+            Some("Archiving pull requests: App: Rate limit exceeded")
+        );
+    }
+
+    #[tokio::test]
+    async fn repo_stays_adding_if_removing_a_failed_add_fails() {
+        let pool = memory_pool().await;
+        let query = "
+            INSERT INTO repo (platform, user, repo, created_at, updated_at, status)
+            VALUES ('github', 'user', 'failed-add', '', '', 'adding')
+        ";
+        let repo_id = sqlx::query(query)
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        let get_status = || async {
+            let query = "SELECT status FROM repo WHERE id = ?";
+            sqlx::query_scalar::<_, String>(query)
+                .bind(repo_id)
+                .fetch_optional(&pool)
+                .await
+                .unwrap()
+        };
+
+        let query = "
+            CREATE TEMP TRIGGER fail_repo_delete BEFORE DELETE ON repo
+            BEGIN SELECT RAISE(ABORT, 'disk I/O error'); END
+        ";
+        sqlx::query(query).execute(&pool).await.unwrap();
+        remove_failed_repo(repo_id, &pool).await;
+        assert_eq!(get_status().await.as_deref(), Some("adding"));
+
+        sqlx::query("DROP TRIGGER fail_repo_delete")
+            .execute(&pool)
+            .await
+            .unwrap();
+        reconcile_repos(&pool).await.unwrap();
+        assert_eq!(get_status().await, None);
+    }
+}
@@ -1,11 +1,14 @@
 use std::str::FromStr;
 
+use tauri::State;
 use tokio::time::Instant;
 use tracing::info;
 
 use crate::{
     error::AppResult,
-    settings::{load_settings, Settings, Theme},
+    platforms::Platform,
+    settings::{load_settings, save_settings, Settings, Theme},
+    state::AppState,
 };
 
 #[tauri::command(rename_all = "snake_case")]
@@ -24,3 +27,66 @@ pub async fn set_theme(theme: String) -> AppResult<Settings> {
     info!("updated settings in {:?}", start.elapsed());
     Ok(settings)
 }
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_github_token(
+    token: Option<String>,
+    state: State<'_, AppState>,
+) -> AppResult<Settings> {
+    let start = Instant::now();
+    let mut settings = load_settings().await?;
+    settings.auth.github_token = token.filter(|t| !t.is_empty());
+    save_settings(&settings).await?;
+
+    state
+        .lock()
+        .await
+        .apis
+        .github
+        .set_token(settings.auth.github_token.clone());
+
+    info!("updated github token in {:?}", start.elapsed());
+    Ok(settings)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_gitlab_token(
+    token: Option<String>,
+    state: State<'_, AppState>,
+) -> AppResult<Settings> {
+    let start = Instant::now();
+    let mut settings = load_settings().await?;
+    settings.auth.gitlab_token = token.filter(|t| !t.is_empty());
+    save_settings(&settings).await?;
+
+    state
+        .lock()
+        .await
+        .apis
+        .get(Platform::GitLab, None)?
+        .set_token(settings.auth.gitlab_token.clone());
+
+    info!("updated gitlab token in {:?}", start.elapsed());
+    Ok(settings)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_bitbucket_token(
+    token: Option<String>,
+    state: State<'_, AppState>,
+) -> AppResult<Settings> {
+    let start = Instant::now();
+    let mut settings = load_settings().await?;
+    settings.auth.bitbucket_token = token.filter(|t| !t.is_empty());
+    save_settings(&settings).await?;
+
+    state
+        .lock()
+        .await
+        .apis
+        .get(Platform::Bitbucket, None)?
+        .set_token(settings.auth.bitbucket_token.clone());
+
+    info!("updated bitbucket token in {:?}", start.elapsed());
+    Ok(settings)
+}
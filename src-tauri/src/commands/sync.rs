@@ -0,0 +1,57 @@
+use std::str::FromStr;
+
+use tauri::{AppHandle, State};
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    error::AppResult,
+    platforms::Platform,
+    repo::Repo,
+    state::AppState,
+    sync::{self, RepoSyncDiff, SyncRun},
+};
+
+/// Re-fetch the metadata, tree and README of a repo and return what changed.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn sync_repo(
+    id: i64,
+    state: State<'_, AppState>,
+    app: AppHandle,
+) -> AppResult<RepoSyncDiff> {
+    let start = Instant::now();
+    let pool = state.lock().await.pool.clone();
+
+    let query = "SELECT * FROM repo WHERE id = ?";
+    let repo = sqlx::query_as::<_, Repo>(query)
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository from database"
+        })?;
+
+    // The state is only locked to get the provider, so other commands aren't blocked while
+    // the repo syncs.
+    let provider = state
+        .lock()
+        .await
+        .apis
+        .get(Platform::from_str(&repo.platform)?, repo.instance_id)?;
+    let diff = sync::sync_repo(&repo, provider.as_ref(), "manual", &pool, &app).await?;
+
+    info!("synced repo \"{id}\" in {:?}", start.elapsed());
+
+    Ok(diff)
+}
+
+/// The most recent sync runs of a repo, newest first.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_sync_runs(repo_id: i64, state: State<'_, AppState>) -> AppResult<Vec<SyncRun>> {
+    let start = Instant::now();
+    let state = state.lock().await;
+    let runs = sync::get_sync_runs(repo_id, &state.pool).await?;
+    info!("fetched sync runs in {:?}", start.elapsed());
+    Ok(runs)
+}
@@ -7,10 +7,12 @@ use tokio::time::Instant;
 use tracing::error;
 use tracing::info;
 
-use crate::platforms::github::get_github_user;
+use crate::platforms::bitbucket::models::BitbucketUserData;
+use crate::platforms::gitea::models::GiteaUserData;
 use crate::platforms::github::models::GitHubUserData;
+use crate::platforms::gitlab::models::GitLabUserData;
 use crate::platforms::Platform;
-use crate::{error::AppResult, state::AppState};
+use crate::{error::AppResult, state::AppState, user::delete_user};
 
 pub enum PlatformUser {
     GitHub(),
@@ -19,6 +21,7 @@ pub enum PlatformUser {
 #[derive(FromRow)]
 pub struct User {
     platform: String,
+    instance_id: Option<i64>,
     created_at: String,
     updated_at: String,
 }
@@ -27,10 +30,10 @@ pub struct User {
 #[serde(tag = "type", content = "data")]
 #[allow(clippy::large_enum_variant)]
 pub enum PlatformUserData {
-    Bitbucket,
+    Bitbucket(BitbucketUserData),
     GitHub(GitHubUserData),
-    GitLab,
-    Gitea,
+    GitLab(GitLabUserData),
+    Gitea(GiteaUserData),
 }
 
 #[derive(Serialize)]
@@ -66,15 +69,11 @@ pub async fn get_user(id: i64, state: State<'_, AppState>) -> AppResult<FullUser
             "Error getting user avatar id from database"
         })?;
 
-    let platform_user = match Platform::from_str(&user.platform)? {
-        Platform::Bitbucket => PlatformUserData::Bitbucket,
-        Platform::GitHub => {
-            let github_user = get_github_user(id, &state.pool).await?;
-            PlatformUserData::GitHub(github_user)
-        }
-        Platform::GitLab => PlatformUserData::GitLab,
-        Platform::Gitea => PlatformUserData::Gitea,
-    };
+    let platform_user = state
+        .apis
+        .get(Platform::from_str(&user.platform)?, user.instance_id)?
+        .get_user(id, &state.pool)
+        .await?;
 
     info!("fetched full user \"{}\" in {:?}", id, start.elapsed());
 
@@ -91,15 +90,7 @@ pub async fn remove_user(id: i64, state: State<'_, AppState>) -> AppResult<()> {
     let start = Instant::now();
     let state = state.lock().await;
 
-    let query = "DELETE FROM user WHERE id = ?";
-    sqlx::query(query)
-        .bind(id)
-        .execute(&state.pool)
-        .await
-        .map_err(|e| {
-            error!("{:?}", e);
-            "Error deleting user from database"
-        })?;
+    delete_user(id, &state.pool).await?;
 
     info!("deleted user \"{id}\" in {:?}", start.elapsed());
 
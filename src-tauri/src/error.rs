@@ -1,5 +1,3 @@
-use tracing::error;
-
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
     #[error("Anyhow: {0}")]
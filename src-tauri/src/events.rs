@@ -1,13 +1,27 @@
+use std::{collections::HashSet, str::FromStr, time::Duration};
+
+use chrono::Utc;
 use serde::Serialize;
-use sqlx::SqlitePool;
+use sqlx::{prelude::FromRow, SqlitePool};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::time::Instant;
 use tracing::{error, info};
 
-use crate::error::{AppError, AppResult};
+use crate::{
+    error::{AppError, AppResult},
+    platforms::{
+        provider::{PlatformProvider, RepoContext, RepoRef},
+        Platform,
+    },
+    state::AppState,
+};
+
+/// How often the watched events of every repo are polled.
+const WATCH_INTERVAL: Duration = Duration::from_secs(15 * 60);
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
-enum RepoEvent {
+pub enum RepoEvent {
     Branch,
     Contributor,
     Commit,
@@ -52,6 +66,58 @@ impl RepoEvent {
     }
 }
 
+impl FromStr for RepoEvent {
+    type Err = AppError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::LIST
+            .into_iter()
+            .find(|e| e.to_string() == s)
+            .ok_or_else(|| AppError::Custom(format!("Invalid repo event \"{s}\"")))
+    }
+}
+
+/// The current state of one occurrence of a watched event, e.g. a branch and its head.
+pub struct WatchedItem {
+    /// Identifies the occurrence within the event kind, e.g. the branch name.
+    pub key: String,
+    /// Changes whenever the occurrence does, e.g. the head commit of the branch.
+    pub version: String,
+    pub title: String,
+    pub url: Option<String>,
+    pub occurred_at: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct RepoEventLogEntry {
+    pub id: i64,
+    pub repo_id: i64,
+    pub platform: String,
+    pub user: String,
+    pub repo: String,
+    pub event: String,
+    /// 'created' or 'updated'
+    pub action: String,
+    pub key: String,
+    pub title: String,
+    pub url: Option<String>,
+    pub occurred_at: Option<String>,
+    pub created_at: String,
+    pub read_at: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+struct RepoEventsData {
+    repo_id: i64,
+    count: usize,
+}
+
+#[derive(FromRow)]
+struct WatchRepoEvent {
+    id: i64,
+    event: String,
+    polled_at: Option<String>,
+}
+
 async fn watch_repo_event(repo_id: i64, event: &str, pool: &SqlitePool) -> AppResult<()> {
     if !RepoEvent::is_valid(event) {
         return AppError::new(&format!("Invalid repo event \"{event}\""));
@@ -96,3 +162,317 @@ pub async fn watch_repo_events(
 
     Ok(())
 }
+
+async fn add_repo_event_log(
+    repo_id: i64,
+    event: RepoEvent,
+    action: &str,
+    item: &WatchedItem,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let query = "
+        INSERT INTO repo_event_log (repo_id, event, action, key, title, url, occurred_at, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+    ";
+    sqlx::query(query)
+        .bind(repo_id)
+        .bind(event.to_string())
+        .bind(action)
+        .bind(&item.key)
+        .bind(&item.title)
+        .bind(&item.url)
+        .bind(&item.occurred_at)
+        .bind(Utc::now().to_rfc3339())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding repo event to database"
+        })?;
+    Ok(())
+}
+
+/// Diff the current occurrences of a watched event against the last known state, logging
+/// new and changed ones. The first poll only records the state, so existing occurrences
+/// aren't reported as new.
+async fn update_watched_event(
+    repo_id: i64,
+    watch: &WatchRepoEvent,
+    event: RepoEvent,
+    items: Vec<WatchedItem>,
+    pool: &SqlitePool,
+) -> AppResult<usize> {
+    let query = "SELECT key, version FROM repo_event_state WHERE watch_id = ?";
+    let known = sqlx::query_as::<_, (String, String)>(query)
+        .bind(watch.id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repo event state from database"
+        })?;
+    let known: HashSet<(String, String)> = known.into_iter().collect();
+    let known_keys: HashSet<&str> = known.iter().map(|(key, _)| key.as_str()).collect();
+
+    let mut count = 0;
+    for item in items {
+        if known.contains(&(item.key.clone(), item.version.clone())) {
+            continue;
+        }
+
+        if watch.polled_at.is_some() {
+            let action = if known_keys.contains(item.key.as_str()) {
+                "updated"
+            } else {
+                "created"
+            };
+            add_repo_event_log(repo_id, event, action, &item, pool).await?;
+            count += 1;
+        }
+
+        let query = "
+            INSERT INTO repo_event_state (watch_id, key, version) VALUES (?, ?, ?)
+            ON CONFLICT (watch_id, key) DO UPDATE SET version = excluded.version
+        ";
+        sqlx::query(query)
+            .bind(watch.id)
+            .bind(&item.key)
+            .bind(&item.version)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error updating repo event state in database"
+            })?;
+    }
+
+    let query = "UPDATE watch_repo_event SET polled_at = ? WHERE id = ?";
+    sqlx::query(query)
+        .bind(Utc::now().to_rfc3339())
+        .bind(watch.id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating watch repo event in database"
+        })?;
+
+    Ok(count)
+}
+
+/// Poll every watched event of a repo the platform supports, returning the number of
+/// new log entries.
+pub async fn poll_repo_events(
+    provider: &dyn PlatformProvider,
+    ctx: &RepoContext,
+    pool: &SqlitePool,
+) -> AppResult<usize> {
+    let start = Instant::now();
+
+    let query = "SELECT id, event, polled_at FROM watch_repo_event WHERE repo_id = ?";
+    let watches = sqlx::query_as::<_, WatchRepoEvent>(query)
+        .bind(ctx.repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting watch repo events from database"
+        })?;
+
+    let mut count = 0;
+    for watch in &watches {
+        let event = RepoEvent::from_str(&watch.event)?;
+        let Some(items) = provider.fetch_watched_items(ctx, event, pool).await? else {
+            continue;
+        };
+        count += update_watched_event(ctx.repo_id, watch, event, items, pool).await?;
+    }
+
+    info!(
+        "polled events of {}/{}, {} new in {:?}",
+        ctx.user,
+        ctx.repo,
+        count,
+        start.elapsed()
+    );
+
+    Ok(count)
+}
+
+/// Feed freshly fetched refs into the branch and tag watches of a repo, so ref changes
+/// found on sync are logged like polled ones.
+pub async fn add_repo_ref_events(
+    repo_id: i64,
+    refs: &[RepoRef],
+    pool: &SqlitePool,
+    app: &AppHandle,
+) -> AppResult<()> {
+    let query = "
+        SELECT id, event, polled_at FROM watch_repo_event
+        WHERE repo_id = ? AND event IN ('branch', 'tag')
+    ";
+    let watches = sqlx::query_as::<_, WatchRepoEvent>(query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting watch repo events from database"
+        })?;
+
+    let mut count = 0;
+    for watch in &watches {
+        let event = RepoEvent::from_str(&watch.event)?;
+        let items = refs
+            .iter()
+            .filter(|r| r.kind == watch.event)
+            .map(|r| WatchedItem {
+                key: r.name.clone(),
+                version: r.sha.clone(),
+                title: r.name.clone(),
+                url: None,
+                occurred_at: r.tagged_at.clone(),
+            })
+            .collect();
+        count += update_watched_event(repo_id, watch, event, items, pool).await?;
+    }
+
+    if count > 0 {
+        app.emit("repo-events", RepoEventsData { repo_id, count })
+            .unwrap();
+    }
+
+    Ok(())
+}
+
+async fn run_event_watcher(app: &AppHandle) -> AppResult<()> {
+    let state = app.state::<AppState>();
+    let pool = state.lock().await.pool.clone();
+
+    let query = "
+        SELECT DISTINCT r.id, r.platform, r.instance_id, r.user, r.repo
+        FROM repo r
+        JOIN watch_repo_event w ON w.repo_id = r.id
+    ";
+    let repos = sqlx::query_as::<_, (i64, String, Option<i64>, String, String)>(query)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting watched repositories from database"
+        })?;
+
+    // Hosts whose rate limit ran out are skipped until the next poll.
+    let mut rate_limited: HashSet<(Platform, Option<i64>)> = HashSet::new();
+
+    for (repo_id, platform, instance_id, user, repo) in repos {
+        let platform = Platform::from_str(&platform)?;
+        if rate_limited.contains(&(platform, instance_id)) {
+            continue;
+        }
+
+        // The state is only locked to get the provider, so commands aren't blocked while
+        // the repo is polled.
+        let provider = state.lock().await.apis.get(platform, instance_id)?;
+        if provider.check_core_rate_limit(&pool).await.is_err() {
+            rate_limited.insert((platform, instance_id));
+            continue;
+        }
+
+        let ctx = RepoContext {
+            repo_id,
+            instance_id,
+            user,
+            repo,
+        };
+        match poll_repo_events(provider.as_ref(), &ctx, &pool).await {
+            Ok(0) => {}
+            Ok(count) => app
+                .emit("repo-events", RepoEventsData { repo_id, count })
+                .unwrap(),
+            Err(e) => error!("{:?}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Periodically poll the watched events of every repo in the background.
+pub fn start_event_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = run_event_watcher(&app).await {
+                error!("{:?}", e);
+            }
+        }
+    });
+}
+
+/// The event log of a repo, or of every repo, newest first.
+pub async fn get_repo_event_log(
+    repo_id: Option<i64>,
+    unread_only: bool,
+    limit: i64,
+    pool: &SqlitePool,
+) -> AppResult<Vec<RepoEventLogEntry>> {
+    let query = "
+        SELECT l.*, r.platform, r.user, r.repo
+        FROM repo_event_log l
+        JOIN repo r ON r.id = l.repo_id
+        WHERE (? IS NULL OR l.repo_id = ?)
+        AND (NOT ? OR l.read_at IS NULL)
+        ORDER BY l.created_at DESC, l.id DESC
+        LIMIT ?
+    ";
+    let entries = sqlx::query_as::<_, RepoEventLogEntry>(query)
+        .bind(repo_id)
+        .bind(repo_id)
+        .bind(unread_only)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repo events from database"
+        })?;
+    Ok(entries)
+}
+
+/// Mark log entries as read, or as unread again.
+pub async fn set_repo_events_read(ids: &[i64], read: bool, pool: &SqlitePool) -> AppResult<()> {
+    let read_at = read.then(|| Utc::now().to_rfc3339());
+    for id in ids {
+        let query = "UPDATE repo_event_log SET read_at = ? WHERE id = ?";
+        sqlx::query(query)
+            .bind(&read_at)
+            .bind(id)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error updating repo event in database"
+            })?;
+    }
+    Ok(())
+}
+
+/// Mark every unread log entry of a repo, or of every repo, as read.
+pub async fn set_all_repo_events_read(repo_id: Option<i64>, pool: &SqlitePool) -> AppResult<()> {
+    let query = "
+        UPDATE repo_event_log SET read_at = ?
+        WHERE read_at IS NULL AND (? IS NULL OR repo_id = ?)
+    ";
+    sqlx::query(query)
+        .bind(Utc::now().to_rfc3339())
+        .bind(repo_id)
+        .bind(repo_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating repo events in database"
+        })?;
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use serde::Serialize;
+use sqlx::{prelude::FromRow, SqlitePool};
+use tracing::error;
+
+use crate::error::AppResult;
+
+/// A self-hosted (or alternative) host of a platform, registered by its base URL.
+#[derive(Serialize, FromRow, Clone)]
+pub struct Instance {
+    pub id: i64,
+    pub platform: String,
+    pub base_url: String,
+    #[serde(skip_serializing)]
+    pub token: Option<String>,
+    pub created_at: String,
+    /// Overrides the API URL derived from the base URL.
+    pub api_url: Option<String>,
+    /// Overrides the raw file contents URL derived from the base URL.
+    pub content_url: Option<String>,
+}
+
+pub async fn get_instance(id: i64, pool: &SqlitePool) -> AppResult<Instance> {
+    let query = "SELECT * FROM platform_instance WHERE id = ?";
+    let instance = sqlx::query_as::<_, Instance>(query)
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting platform instance from database"
+        })?;
+    Ok(instance)
+}
+
+pub async fn get_instances(pool: &SqlitePool) -> AppResult<Vec<Instance>> {
+    let query = "SELECT * FROM platform_instance ORDER BY platform, base_url";
+    let instances = sqlx::query_as::<_, Instance>(query)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting platform instances from database"
+        })?;
+    Ok(instances)
+}
+
+/// Trim trailing slashes and make sure the URL has a scheme.
+pub fn normalize_base_url(base_url: &str) -> Option<String> {
+    let base_url = base_url.trim().trim_end_matches('/');
+    if base_url.starts_with("https://") || base_url.starts_with("http://") {
+        Some(base_url.to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_base_url_trims_trailing_slashes() {
+        assert_eq!(
+            normalize_base_url(" https://gitlab.example.com// ").as_deref(),
+            Some("https://gitlab.example.com")
+        );
+        assert_eq!(
+            normalize_base_url("http://localhost:3000/gitea/").as_deref(),
+            Some("http://localhost:3000/gitea")
+        );
+    }
+
+    #[test]
+    fn normalize_base_url_requires_scheme() {
+        assert_eq!(normalize_base_url("gitlab.example.com"), None);
+        assert_eq!(normalize_base_url("ftp://gitlab.example.com"), None);
+        assert_eq!(normalize_base_url(""), None);
+    }
+}
@@ -9,14 +9,18 @@ use tracing_error::ErrorLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 use utils::dirs::get_cache_dir;
 
+pub mod blob;
+pub mod clone;
 pub mod commands;
 pub mod error;
 pub mod events;
+pub mod instance;
 pub mod platforms;
 pub mod repo;
 pub mod settings;
 pub mod state;
 pub mod submodule;
+pub mod sync;
 pub mod user;
 pub mod utils;
 
@@ -31,21 +35,66 @@ async fn main() -> anyhow::Result<()> {
         .await
         .expect("failed to run sqlx migrations");
 
+    {
+        let mut state = app_state.lock().await;
+        state.register_instances().await?;
+        repo::reconcile_repos(&state.pool).await?;
+        sync::fail_interrupted_sync_runs(&state.pool).await?;
+    }
+
     tauri::Builder::default()
         .setup(|app| {
             app.manage(app_state);
+            utils::rate_limit::set_app_handle(app.handle().clone());
+            sync::start_sync_scheduler(app.handle().clone());
+            events::start_event_watcher(app.handle().clone());
             Ok(())
         })
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .invoke_handler(tauri::generate_handler![
             get_version,
+            commands::auth::validate_github_token,
+            commands::auth::get_github_token_scopes,
+            commands::instance::add_instance,
+            commands::instance::get_instances,
+            commands::instance::remove_instance,
+            commands::discussion::get_discussion_categories,
+            commands::discussion::get_discussions,
+            commands::discussion::get_discussion,
+            commands::discussion::set_repo_archive_discussions,
+            commands::issue::get_issues,
+            commands::issue::get_issue,
+            commands::issue::set_repo_archive_issues,
+            commands::pull::get_pulls,
+            commands::pull::get_pull,
+            commands::pull::set_repo_archive_pulls,
+            commands::rate_limit::get_rate_limits,
+            commands::rate_limit::get_rate_limit_usage,
+            commands::release::get_releases,
+            commands::release::get_repo_release_asset_globs,
+            commands::release::set_repo_release_options,
             commands::repo::add_repo,
             commands::repo::get_repo_list,
             commands::repo::get_repo,
             commands::repo::remove_repo,
+            commands::repo::fetch_repo_clone,
+            commands::repo::get_repo_history,
+            commands::repo::get_repo_refs,
+            commands::repo::add_repo_ref_tree,
+            commands::repo::get_repo_ref_trees,
+            commands::repo::get_blob,
+            commands::repo::set_repo_prefetch,
             commands::settings::get_settings,
             commands::settings::set_theme,
+            commands::settings::set_github_token,
+            commands::settings::set_gitlab_token,
+            commands::settings::set_bitbucket_token,
+            commands::sync::get_sync_runs,
+            commands::sync::sync_repo,
+            commands::events::get_event_feed,
+            commands::events::set_events_read,
+            commands::events::set_all_events_read,
             commands::user::get_user,
             commands::user::remove_user,
         ])
@@ -4,17 +4,33 @@ use serde::Serialize;
 
 use crate::error::AppError;
 
+pub mod bitbucket;
+pub mod gitea;
 pub mod github;
+pub mod gitlab;
+pub mod provider;
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum Platform {
     Bitbucket,
+    #[serde(rename = "github")]
     GitHub,
+    #[serde(rename = "gitlab")]
     GitLab,
     Gitea,
 }
 
+impl Platform {
+    /// Whether repos can be added from self-hosted instances of the platform.
+    pub fn supports_instances(&self) -> bool {
+        match self {
+            Platform::Bitbucket => false,
+            Platform::GitHub | Platform::GitLab | Platform::Gitea => true,
+        }
+    }
+}
+
 impl FromStr for Platform {
     type Err = AppError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
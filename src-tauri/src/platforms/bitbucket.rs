@@ -0,0 +1,702 @@
+use api::BitbucketAPI;
+use api_models::{
+    BitbucketApiBranch, BitbucketApiBranchRestriction, BitbucketApiBranchingModel,
+    BitbucketApiBranchingModelBranch, BitbucketApiRepo, BitbucketApiTag,
+};
+use async_trait::async_trait;
+use models::{BitbucketRepo, BitbucketRepoData, BitbucketUserData, BitbucketWorkspace};
+use sqlx::{prelude::FromRow, SqlitePool};
+use tauri::AppHandle;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    commands::{
+        repo::{AddRepoProgress, DbPlatformRepo, RepoPreviewOwner},
+        user::PlatformUserData,
+    },
+    error::AppResult,
+    platforms::{
+        provider::{CloneRemote, FetchedTree, PlatformProvider, RepoContext, RepoRef},
+        Platform,
+    },
+    repo::{get_repo_stats, NewRepoTreeItem, PlatformRepoData, RepoStats},
+    user::{add_user, add_user_avatar, delete_user, get_user_avatar_path, get_user_id},
+    utils::data::is_commit_sha,
+};
+
+pub mod api;
+pub mod api_models;
+pub mod models;
+
+async fn add_bitbucket_repo_row(
+    repo_id: i64,
+    bitbucket_repo: &BitbucketApiRepo,
+    mainbranch: &str,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let query = "
+        INSERT INTO bitbucket_repo (
+            repo_id, uuid, workspace_uuid, name, slug,
+            full_name, description, is_private, fork_policy, language,
+            size, has_issues, has_wiki, mainbranch, project_key,
+            project_name, html_url, created_on, updated_on
+        )
+        VALUES (
+            ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?,
+            ?, ?, ?, ?
+        )
+        ON CONFLICT (repo_id) DO UPDATE SET
+            uuid = excluded.uuid, workspace_uuid = excluded.workspace_uuid,
+            name = excluded.name, slug = excluded.slug, full_name = excluded.full_name,
+            description = excluded.description, is_private = excluded.is_private,
+            fork_policy = excluded.fork_policy, language = excluded.language,
+            size = excluded.size, has_issues = excluded.has_issues,
+            has_wiki = excluded.has_wiki, mainbranch = excluded.mainbranch,
+            project_key = excluded.project_key, project_name = excluded.project_name,
+            html_url = excluded.html_url, created_on = excluded.created_on,
+            updated_on = excluded.updated_on
+    ";
+    sqlx::query(query)
+        .bind(repo_id)
+        .bind(&bitbucket_repo.uuid)
+        .bind(&bitbucket_repo.workspace.uuid)
+        .bind(&bitbucket_repo.name)
+        .bind(&bitbucket_repo.slug)
+        .bind(&bitbucket_repo.full_name)
+        .bind(&bitbucket_repo.description)
+        .bind(bitbucket_repo.is_private)
+        .bind(&bitbucket_repo.fork_policy)
+        .bind(&bitbucket_repo.language)
+        .bind(bitbucket_repo.size)
+        .bind(bitbucket_repo.has_issues)
+        .bind(bitbucket_repo.has_wiki)
+        .bind(mainbranch)
+        .bind(bitbucket_repo.project.as_ref().map(|p| &p.key))
+        .bind(bitbucket_repo.project.as_ref().map(|p| &p.name))
+        .bind(
+            bitbucket_repo
+                .links
+                .html
+                .as_ref()
+                .map(|l| l.href.as_str())
+                .unwrap_or_default(),
+        )
+        .bind(&bitbucket_repo.created_on)
+        .bind(&bitbucket_repo.updated_on)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding Bitbucket repository to database"
+        })?;
+
+    Ok(())
+}
+
+async fn add_bitbucket_workspace_user(
+    user: &str,
+    repo: &str,
+    api: &BitbucketAPI,
+    pool: &SqlitePool,
+    app: &AppHandle,
+) -> AppResult<()> {
+    AddRepoProgress::Owner.send("bitbucket", user, repo, 0, 0, 4, app);
+
+    if get_user_id("bitbucket", None, user, pool).await?.is_some() {
+        AddRepoProgress::Owner.send("bitbucket", user, repo, 100, 1, 1, app);
+        return Ok(());
+    }
+
+    let workspace = api.fetch_workspace(user, pool).await?;
+    AddRepoProgress::Owner.send("bitbucket", user, repo, 25, 1, 4, app);
+
+    let user_id = add_user("bitbucket", None, user, pool).await?;
+    // The user is removed again if anything fails, so the next add doesn't skip the owner.
+    let added: AppResult<()> = async {
+        AddRepoProgress::Owner.send("bitbucket", user, repo, 50, 2, 4, app);
+
+        let avatar_url = workspace.links.avatar.map(|l| l.href).unwrap_or_default();
+
+        let query = "
+            INSERT INTO bitbucket_workspace (
+                user_id, uuid, slug, name, is_private,
+                avatar_url, html_url, created_on
+            )
+            VALUES (
+                ?, ?, ?, ?, ?,
+                ?, ?, ?
+            )
+        ";
+        sqlx::query(query)
+            .bind(user_id)
+            .bind(&workspace.uuid)
+            .bind(&workspace.slug)
+            .bind(&workspace.name)
+            .bind(workspace.is_private)
+            .bind(&avatar_url)
+            .bind(workspace.links.html.map(|l| l.href).unwrap_or_default())
+            .bind(&workspace.created_on)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding Bitbucket workspace to database"
+            })?;
+
+        AddRepoProgress::Owner.send("bitbucket", user, repo, 75, 3, 4, app);
+
+        if !avatar_url.is_empty() {
+            add_user_avatar(user_id, "bitbucket", &avatar_url, pool).await?;
+        }
+        Ok(())
+    }
+    .await;
+    if let Err(e) = added {
+        if let Err(e) = delete_user(user_id, pool).await {
+            error!("{:?}", e);
+        }
+        return Err(e);
+    }
+
+    AddRepoProgress::Owner.send("bitbucket", user, repo, 100, 4, 4, app);
+
+    Ok(())
+}
+
+/// Add the repo metadata, returning the main branch.
+pub async fn add_bitbucket_repo(
+    repo_id: i64,
+    user: &str,
+    repo: &str,
+    api: &BitbucketAPI,
+    pool: &SqlitePool,
+    app: &AppHandle,
+) -> AppResult<String> {
+    AddRepoProgress::Metadata.send("bitbucket", user, repo, 0, 0, 2, app);
+    let bitbucket_repo = api.fetch_repo(user, repo, pool).await?;
+    AddRepoProgress::Metadata.send("bitbucket", user, repo, 50, 1, 2, app);
+
+    let Some(mainbranch) = bitbucket_repo.mainbranch.as_ref().map(|b| b.name.clone()) else {
+        return Err("Bitbucket repository is empty".into());
+    };
+
+    add_bitbucket_repo_row(repo_id, &bitbucket_repo, &mainbranch, pool).await?;
+    AddRepoProgress::Metadata.send("bitbucket", user, repo, 100, 2, 2, app);
+
+    Ok(mainbranch)
+}
+
+#[derive(FromRow)]
+struct BitbucketRepoPreview {
+    description: String,
+    is_private: bool,
+}
+
+#[derive(FromRow)]
+struct DbRepoOwner {
+    id: i64,
+    user: String,
+}
+
+pub async fn get_bitbucket_repo_preview(
+    repo_id: i64,
+    pool: &SqlitePool,
+) -> AppResult<(DbPlatformRepo, RepoPreviewOwner)> {
+    let start = Instant::now();
+
+    let query = "SELECT description, is_private FROM bitbucket_repo WHERE repo_id = ?";
+    let bitbucket_repo = sqlx::query_as::<_, BitbucketRepoPreview>(query)
+        .bind(repo_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting Bitbucket repository from database"
+        })?;
+
+    let owner_query = "
+        SELECT u.id, u.user
+        FROM repo r
+        JOIN user u ON u.platform = r.platform AND u.instance_id IS r.instance_id AND u.user = r.user
+        WHERE r.id = ?
+    ";
+    let owner = sqlx::query_as::<_, DbRepoOwner>(owner_query)
+        .bind(repo_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting Bitbucket repository owner from database"
+        })?;
+
+    let avatar = get_user_avatar_path(owner.id, pool)
+        .await?
+        .unwrap_or_default();
+
+    info!(
+        "got bitbucket repo preview from database in {:?}",
+        start.elapsed()
+    );
+
+    // Bitbucket has no stars and only reports counts through separate paginated requests.
+    Ok((
+        DbPlatformRepo {
+            description: bitbucket_repo.description,
+            stars: 0,
+            forks: 0,
+            issues: 0,
+            pull_requests: 0,
+            visibility: if bitbucket_repo.is_private {
+                "private".to_string()
+            } else {
+                "public".to_string()
+            },
+        },
+        RepoPreviewOwner {
+            id: owner.id,
+            user: owner.user,
+            avatar,
+        },
+    ))
+}
+
+pub async fn get_bitbucket_repo(repo_id: i64, pool: &SqlitePool) -> AppResult<BitbucketRepoData> {
+    let query = "SELECT * FROM bitbucket_repo WHERE repo_id = ?";
+    let bitbucket_repo = sqlx::query_as::<_, BitbucketRepo>(query)
+        .bind(repo_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting bitbucket repository from database"
+        })?;
+
+    let workspace_query = "SELECT * FROM bitbucket_workspace WHERE uuid = ?";
+    let workspace = sqlx::query_as::<_, BitbucketWorkspace>(workspace_query)
+        .bind(&bitbucket_repo.workspace_uuid)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting bitbucket workspace from database"
+        })?;
+
+    Ok(BitbucketRepoData {
+        repo: bitbucket_repo,
+        workspace,
+    })
+}
+
+pub async fn get_bitbucket_user(user_id: i64, pool: &SqlitePool) -> AppResult<BitbucketUserData> {
+    let query = "SELECT * FROM bitbucket_workspace WHERE user_id = ?";
+    let workspace = sqlx::query_as::<_, BitbucketWorkspace>(query)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting bitbucket workspace from database"
+        })?;
+
+    Ok(BitbucketUserData { workspace })
+}
+
+/// Whether a branch has any restriction, matched by glob pattern or by its type in the
+/// branching model.
+fn is_branch_protected(
+    name: &str,
+    restrictions: &[BitbucketApiBranchRestriction],
+    branching_model: Option<&BitbucketApiBranchingModel>,
+) -> bool {
+    let branch_type = branching_model.and_then(|model| {
+        let is_branch = |branch: &Option<BitbucketApiBranchingModelBranch>| {
+            branch
+                .as_ref()
+                .and_then(|b| b.branch.as_ref())
+                .is_some_and(|b| b.name == name)
+        };
+        if is_branch(&model.production) {
+            Some("production")
+        } else if is_branch(&model.development) {
+            Some("development")
+        } else {
+            model
+                .branch_types
+                .iter()
+                .find(|t| name.starts_with(&t.prefix))
+                .map(|t| t.kind.as_str())
+        }
+    });
+
+    restrictions
+        .iter()
+        .any(|r| match r.branch_match_kind.as_str() {
+            "glob" => r
+                .pattern
+                .as_deref()
+                .and_then(|pattern| glob::Pattern::new(pattern).ok())
+                .is_some_and(|pattern| pattern.matches(name)),
+            "branching_model" => branch_type.is_some() && r.branch_type.as_deref() == branch_type,
+            _ => false,
+        })
+}
+
+#[async_trait]
+impl PlatformProvider for BitbucketAPI {
+    fn platform(&self) -> Platform {
+        Platform::Bitbucket
+    }
+
+    fn set_token(&self, token: Option<String>) {
+        BitbucketAPI::set_token(self, token);
+    }
+
+    async fn add_repo(
+        &self,
+        ctx: &RepoContext,
+        pool: &SqlitePool,
+        app: &AppHandle,
+    ) -> AppResult<String> {
+        add_bitbucket_repo(ctx.repo_id, &ctx.user, &ctx.repo, self, pool, app).await
+    }
+
+    async fn fetch_tree(
+        &self,
+        ctx: &RepoContext,
+        rev: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<FetchedTree> {
+        let commit = if is_commit_sha(rev) {
+            rev.to_string()
+        } else {
+            self.fetch_branch(&ctx.user, &ctx.repo, rev, pool)
+                .await?
+                .target
+                .hash
+        };
+        let tree = self
+            .fetch_repo_tree(&ctx.user, &ctx.repo, &commit, pool)
+            .await?;
+
+        Ok(FetchedTree {
+            sha: commit.clone(),
+            rev: commit,
+            truncated: false,
+            items: tree.into_iter().map(NewRepoTreeItem::from).collect(),
+        })
+    }
+
+    async fn fetch_refs(&self, ctx: &RepoContext, pool: &SqlitePool) -> AppResult<Vec<RepoRef>> {
+        let branches: Vec<BitbucketApiBranch> = self
+            .fetch_repo_list(&ctx.user, &ctx.repo, "refs/branches", pool)
+            .await?;
+        let tags: Vec<BitbucketApiTag> = self
+            .fetch_repo_list(&ctx.user, &ctx.repo, "refs/tags", pool)
+            .await?;
+
+        // Branches aren't flagged as protected, restrictions are listed separately. Without
+        // permission to list them, no branch is marked protected.
+        let restrictions = self
+            .fetch_branch_restrictions(&ctx.user, &ctx.repo, pool)
+            .await?
+            .unwrap_or_default();
+        let branching_model = if restrictions
+            .iter()
+            .any(|r| r.branch_match_kind == "branching_model")
+        {
+            Some(
+                self.fetch_branching_model(&ctx.user, &ctx.repo, pool)
+                    .await?,
+            )
+        } else {
+            None
+        };
+        let branches = branches.into_iter().map(|branch| {
+            let protected =
+                is_branch_protected(&branch.name, &restrictions, branching_model.as_ref());
+            RepoRef::branch(branch.name, branch.target.hash, protected)
+        });
+        let tags = tags.into_iter().map(|tag| RepoRef {
+            annotated: Some(tag.tagger.is_some()),
+            tagger: tag.tagger.map(|t| t.raw),
+            tagged_at: tag.date,
+            message: tag.message,
+            ..RepoRef::tag(tag.name, tag.target.hash)
+        });
+        Ok(branches.chain(tags).collect())
+    }
+
+    async fn fetch_file(
+        &self,
+        ctx: &RepoContext,
+        rev: &str,
+        path: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<String> {
+        self.fetch_repo_readme(&ctx.user, &ctx.repo, rev, path, pool)
+            .await
+    }
+
+    async fn fetch_blob(
+        &self,
+        ctx: &RepoContext,
+        rev: &str,
+        path: &str,
+        _sha: Option<&str>,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<u8>> {
+        self.fetch_raw_file(&ctx.user, &ctx.repo, rev, path, pool)
+            .await
+    }
+
+    async fn add_owner(
+        &self,
+        ctx: &RepoContext,
+        pool: &SqlitePool,
+        app: &AppHandle,
+    ) -> AppResult<()> {
+        add_bitbucket_workspace_user(&ctx.user, &ctx.repo, self, pool, app).await
+    }
+
+    fn clone_remote(&self, ctx: &RepoContext) -> CloneRemote {
+        CloneRemote {
+            url: format!("https://bitbucket.org/{}/{}.git", ctx.user, ctx.repo),
+            credentials: self.token().map(|t| ("x-token-auth".to_string(), t)),
+        }
+    }
+
+    async fn get_repo(&self, repo_id: i64, pool: &SqlitePool) -> AppResult<PlatformRepoData> {
+        Ok(PlatformRepoData::Bitbucket(
+            get_bitbucket_repo(repo_id, pool).await?,
+        ))
+    }
+
+    async fn get_repo_stats(
+        &self,
+        repo_id: i64,
+        pool: &SqlitePool,
+    ) -> AppResult<Option<RepoStats>> {
+        let query = "
+            SELECT
+                NULL AS stars, NULL AS forks, NULL AS watchers, NULL AS open_issues,
+                mainbranch AS default_branch, NULL AS archived,
+                CASE WHEN is_private THEN 'private' ELSE 'public' END AS visibility,
+                NULL AS license, NULL AS topics
+            FROM bitbucket_repo
+            WHERE repo_id = ?
+        ";
+        get_repo_stats(query, repo_id, pool).await
+    }
+
+    async fn get_repo_preview(
+        &self,
+        repo_id: i64,
+        pool: &SqlitePool,
+    ) -> AppResult<(DbPlatformRepo, RepoPreviewOwner)> {
+        get_bitbucket_repo_preview(repo_id, pool).await
+    }
+
+    async fn get_user(&self, user_id: i64, pool: &SqlitePool) -> AppResult<PlatformUserData> {
+        Ok(PlatformUserData::Bitbucket(
+            get_bitbucket_user(user_id, pool).await?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::{
+        blob::{get_blob, git_blob_sha, BlobContent},
+        platforms::bitbucket::api_models::{BitbucketApiBranchName, BitbucketApiBranchType},
+        repo::add_repo_tree,
+        utils::testing::memory_pool,
+    };
+
+    const COMMIT: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    fn restriction(match_kind: &str, pattern_or_type: &str) -> BitbucketApiBranchRestriction {
+        let is_glob = match_kind == "glob";
+        BitbucketApiBranchRestriction {
+            kind: "push".to_string(),
+            branch_match_kind: match_kind.to_string(),
+            pattern: is_glob.then(|| pattern_or_type.to_string()),
+            branch_type: (!is_glob).then(|| pattern_or_type.to_string()),
+        }
+    }
+
+    #[test]
+    fn branches_are_protected_by_glob_or_branching_model() {
+        let restrictions = [
+            restriction("glob", "release/*"),
+            restriction("branching_model", "production"),
+            restriction("branching_model", "hotfix"),
+        ];
+        let model = BitbucketApiBranchingModel {
+            development: Some(BitbucketApiBranchingModelBranch {
+                branch: Some(BitbucketApiBranchName {
+                    name: "develop".to_string(),
+                }),
+            }),
+            production: Some(BitbucketApiBranchingModelBranch {
+                branch: Some(BitbucketApiBranchName {
+                    name: "main".to_string(),
+                }),
+            }),
+            branch_types: vec![BitbucketApiBranchType {
+                kind: "hotfix".to_string(),
+                prefix: "hotfix/".to_string(),
+            }],
+        };
+
+        let protected: Vec<&str> = [
+            "main",
+            "develop",
+            "release/1.0",
+            "hotfix/crash",
+            "feature/x",
+        ]
+        .into_iter()
+        .filter(|name| is_branch_protected(name, &restrictions, Some(&model)))
+        .collect();
+        assert_eq!(protected, ["main", "release/1.0", "hotfix/crash"]);
+        assert!(!is_branch_protected("main", &restrictions, None));
+    }
+
+    async fn mount_file(server: &MockServer, file_path: &str, content: &str) {
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/repositories/ws/repo/src/{COMMIT}/{file_path}"
+            )))
+            .respond_with(ResponseTemplate::new(200).set_body_string(content))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    async fn get_item_id(repo_id: i64, item_path: &str, pool: &SqlitePool) -> i64 {
+        sqlx::query_scalar("SELECT id FROM repo_tree_item WHERE repo_id = ? AND path = ?")
+            .bind(repo_id)
+            .bind(item_path)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn get_text_blob(
+        ctx: &RepoContext,
+        item_id: i64,
+        api: &BitbucketAPI,
+        pool: &SqlitePool,
+    ) -> (String, String) {
+        let blob = get_blob(ctx, item_id, api, pool).await.unwrap();
+        match blob.content {
+            BlobContent::Text { content, .. } => (blob.sha, content),
+            BlobContent::Binary { .. } => panic!("blob is binary"),
+        }
+    }
+
+    #[tokio::test]
+    async fn files_are_cached_by_the_sha_of_their_content() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(format!("/repositories/ws/repo/src/{COMMIT}/")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "values": [
+                    { "path": "a.txt", "type": "commit_file", "size": 6 },
+                    { "path": "b.txt", "type": "commit_file", "size": 7 },
+                ],
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+        mount_file(&server, "a.txt", "first\n").await;
+        mount_file(&server, "b.txt", "second\n").await;
+
+        let pool = memory_pool().await;
+        let query = "
+            INSERT INTO repo (platform, user, repo, created_at, updated_at)
+            VALUES ('bitbucket', 'ws', 'repo', '', '')
+        ";
+        let repo_id = sqlx::query(query)
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        let ctx = RepoContext {
+            repo_id,
+            instance_id: None,
+            user: "ws".to_string(),
+            repo: "repo".to_string(),
+        };
+        let api = BitbucketAPI::new(&server.uri(), None);
+        let app = tauri::test::mock_app();
+        let tree = api.fetch_tree(&ctx, COMMIT, &pool).await.unwrap();
+        add_repo_tree(
+            repo_id,
+            "main",
+            true,
+            &tree,
+            "bitbucket",
+            "ws",
+            "repo",
+            &pool,
+            app.handle(),
+        )
+        .await
+        .unwrap();
+
+        let a_id = get_item_id(repo_id, "a.txt", &pool).await;
+        let b_id = get_item_id(repo_id, "b.txt", &pool).await;
+        let (a_sha, a_content) = get_text_blob(&ctx, a_id, &api, &pool).await;
+        let (b_sha, b_content) = get_text_blob(&ctx, b_id, &api, &pool).await;
+        assert_eq!(a_content, "first\n");
+        assert_eq!(b_content, "second\n");
+        // The SHAs git gives these blobs.
+        assert_eq!(a_sha, git_blob_sha(b"first\n"));
+        assert_eq!(
+            git_blob_sha(b""),
+            "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"
+        );
+        assert_ne!(a_sha, b_sha);
+
+        // The fetched SHAs are kept when the same commit is stored again, the files are
+        // read from the cache.
+        let tree = api.fetch_tree(&ctx, COMMIT, &pool).await.unwrap();
+        add_repo_tree(
+            repo_id,
+            "main",
+            true,
+            &tree,
+            "bitbucket",
+            "ws",
+            "repo",
+            &pool,
+            app.handle(),
+        )
+        .await
+        .unwrap();
+        let a_id = get_item_id(repo_id, "a.txt", &pool).await;
+        assert_eq!(
+            get_text_blob(&ctx, a_id, &api, &pool).await,
+            (a_sha.clone(), a_content)
+        );
+
+        let blobs: Vec<(String, i64)> =
+            sqlx::query_as("SELECT sha, size FROM repo_blob WHERE repo_id = ? ORDER BY size")
+                .bind(repo_id)
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(blobs, [(a_sha, 6), (b_sha, 7)]);
+    }
+}
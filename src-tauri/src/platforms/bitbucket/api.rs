@@ -0,0 +1,446 @@
+use std::sync::RwLock;
+
+use reqwest::{RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use sqlx::SqlitePool;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    error::{AppError, AppResult},
+    platforms::{
+        bitbucket::api_models::{
+            BitbucketApiBranch, BitbucketApiBranchRestriction, BitbucketApiBranchingModel,
+            BitbucketApiPage, BitbucketApiRepo, BitbucketApiTreeItem, BitbucketApiWorkspace,
+        },
+        Platform,
+    },
+    utils::{
+        data::{parse_body, url_encode},
+        rate_limit::{send_with_retry, RateLimitKey},
+    },
+};
+
+/// Client for the Bitbucket Cloud REST API (2.0).
+pub struct BitbucketAPI {
+    client: reqwest::Client,
+    base_url: String,
+    /// Repository, project or workspace access token.
+    token: RwLock<Option<String>>,
+}
+
+impl BitbucketAPI {
+    pub const DEFAULT_BASE_URL: &'static str = "https://api.bitbucket.org/2.0";
+
+    pub fn init(token: Option<String>) -> Self {
+        Self::new(Self::DEFAULT_BASE_URL, token)
+    }
+
+    pub fn new(base_url: &str, token: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: RwLock::new(token.filter(|t| !t.is_empty())),
+        }
+    }
+
+    /// Requests are counted per host and token, the API sends no rate limit headers.
+    pub fn rate_limit_key(&self) -> RateLimitKey {
+        RateLimitKey::new(
+            Platform::Bitbucket,
+            &self.base_url,
+            self.token().as_deref(),
+            "core",
+        )
+    }
+
+    /// Replace the token used for subsequent requests.
+    pub fn set_token(&self, token: Option<String>) {
+        *self.token.write().unwrap() = token.filter(|t| !t.is_empty());
+    }
+
+    pub fn token(&self) -> Option<String> {
+        self.token.read().unwrap().clone()
+    }
+
+    /// Paginated responses link to the next page with an absolute URL.
+    fn get(&self, url: &str) -> RequestBuilder {
+        let req = self.client.get(url).header("User-Agent", "Git Chest");
+        match self.token.read().unwrap().as_deref() {
+            Some(token) => req.header("Authorization", format!("Bearer {token}")),
+            None => req,
+        }
+    }
+
+    pub async fn fetch_repo(
+        &self,
+        workspace: &str,
+        repo: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<BitbucketApiRepo> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(&format!(
+                "{}/repositories/{workspace}/{repo}",
+                self.base_url
+            ))
+            .header("Accept", "application/json")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key(), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching repository from Bitbucket API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        let body = res.text().await?;
+        let json_body: BitbucketApiRepo =
+            parse_body(&body, "Error parsing repository from Bitbucket API")?;
+
+        info!("fetching bitbucket repo took {:?}", start.elapsed());
+
+        Ok(json_body)
+    }
+
+    pub async fn fetch_branch(
+        &self,
+        workspace: &str,
+        repo: &str,
+        branch: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<BitbucketApiBranch> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(&format!(
+                "{}/repositories/{workspace}/{repo}/refs/branches/{}",
+                self.base_url,
+                url_encode(branch)
+            ))
+            .header("Accept", "application/json")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key(), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching repository branch from Bitbucket API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        let body = res.text().await?;
+        let json_body: BitbucketApiBranch =
+            parse_body(&body, "Error parsing repository branch from Bitbucket API")?;
+
+        info!("fetching bitbucket repo branch took {:?}", start.elapsed());
+
+        Ok(json_body)
+    }
+
+    /// Fetch every page of a list endpoint of a repo, e.g. `refs/branches`.
+    pub async fn fetch_repo_list<T: DeserializeOwned>(
+        &self,
+        workspace: &str,
+        repo: &str,
+        path: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<T>> {
+        let start = Instant::now();
+        let mut items: Vec<T> = Vec::new();
+        let mut next = Some(format!(
+            "{}/repositories/{workspace}/{repo}/{path}?pagelen=100",
+            self.base_url
+        ));
+
+        while let Some(url) = next {
+            let req = || self.get(&url).header("Accept", "application/json");
+            let res = send_with_retry(req, &self.rate_limit_key(), pool)
+                .await
+                .map_err(|e| {
+                    error!("{:?}", e);
+                    "Error fetching repository list from Bitbucket API"
+                })?;
+
+            if let Err(error) = res.error_for_status_ref() {
+                error!("{:?}", res.text().await?);
+                return AppError::new(&error.to_string());
+            }
+
+            let body = res.text().await?;
+            let mut page: BitbucketApiPage<T> =
+                parse_body(&body, "Error parsing repository list from Bitbucket API")?;
+            items.append(&mut page.values);
+            next = page.next;
+        }
+
+        info!(
+            "fetching bitbucket repo {} took {:?}",
+            path,
+            start.elapsed()
+        );
+
+        Ok(items)
+    }
+
+    /// Fetch the branch restrictions of a repo, None if the token may not list them. Only
+    /// repository admins can.
+    pub async fn fetch_branch_restrictions(
+        &self,
+        workspace: &str,
+        repo: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<Option<Vec<BitbucketApiBranchRestriction>>> {
+        let start = Instant::now();
+        let mut restrictions: Vec<BitbucketApiBranchRestriction> = Vec::new();
+        let mut next = Some(format!(
+            "{}/repositories/{workspace}/{repo}/branch-restrictions?pagelen=100",
+            self.base_url
+        ));
+
+        while let Some(url) = next {
+            let req = || self.get(&url).header("Accept", "application/json");
+            let res = send_with_retry(req, &self.rate_limit_key(), pool)
+                .await
+                .map_err(|e| {
+                    error!("{:?}", e);
+                    "Error fetching repository branch restrictions from Bitbucket API"
+                })?;
+
+            if matches!(
+                res.status(),
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+            ) {
+                info!("not allowed to list bitbucket branch restrictions of {workspace}/{repo}");
+                return Ok(None);
+            }
+            if let Err(error) = res.error_for_status_ref() {
+                error!("{:?}", res.text().await?);
+                return AppError::new(&error.to_string());
+            }
+
+            let body = res.text().await?;
+            let mut page: BitbucketApiPage<BitbucketApiBranchRestriction> = parse_body(
+                &body,
+                "Error parsing repository branch restrictions from Bitbucket API",
+            )?;
+            restrictions.append(&mut page.values);
+            next = page.next;
+        }
+
+        info!(
+            "fetching bitbucket repo branch restrictions took {:?}",
+            start.elapsed()
+        );
+
+        Ok(Some(restrictions))
+    }
+
+    pub async fn fetch_branching_model(
+        &self,
+        workspace: &str,
+        repo: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<BitbucketApiBranchingModel> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(&format!(
+                "{}/repositories/{workspace}/{repo}/branching-model",
+                self.base_url
+            ))
+            .header("Accept", "application/json")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key(), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching repository branching model from Bitbucket API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        let body = res.text().await?;
+        let json_body: BitbucketApiBranchingModel = parse_body(
+            &body,
+            "Error parsing repository branching model from Bitbucket API",
+        )?;
+
+        info!(
+            "fetching bitbucket repo branching model took {:?}",
+            start.elapsed()
+        );
+
+        Ok(json_body)
+    }
+
+    /// Fetch every file and directory of a commit. Directories are expanded breadth-first,
+    /// so parents always come before their children.
+    pub async fn fetch_repo_tree(
+        &self,
+        workspace: &str,
+        repo: &str,
+        commit: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<BitbucketApiTreeItem>> {
+        let start = Instant::now();
+        let mut items: Vec<BitbucketApiTreeItem> = Vec::new();
+        let mut next = Some(format!(
+            "{}/repositories/{workspace}/{repo}/src/{commit}/?max_depth=1000&pagelen=100",
+            self.base_url
+        ));
+
+        while let Some(url) = next {
+            let req = || self.get(&url).header("Accept", "application/json");
+            let res = send_with_retry(req, &self.rate_limit_key(), pool)
+                .await
+                .map_err(|e| {
+                    error!("{:?}", e);
+                    "Error fetching repository tree from Bitbucket API"
+                })?;
+
+            if let Err(error) = res.error_for_status_ref() {
+                error!("{:?}", res.text().await?);
+                return AppError::new(&error.to_string());
+            }
+
+            let body = res.text().await?;
+            let mut page: BitbucketApiPage<BitbucketApiTreeItem> =
+                parse_body(&body, "Error parsing repository tree from Bitbucket API")?;
+            items.append(&mut page.values);
+            next = page.next;
+        }
+
+        info!("fetching bitbucket repo tree took {:?}", start.elapsed());
+
+        Ok(items)
+    }
+
+    /// Fetch the raw content of a file at a commit, blobs can't be fetched by SHA.
+    pub async fn fetch_raw_file(
+        &self,
+        workspace: &str,
+        repo: &str,
+        commit: &str,
+        path: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<u8>> {
+        let start = Instant::now();
+
+        let path = path
+            .split('/')
+            .map(url_encode)
+            .collect::<Vec<_>>()
+            .join("/");
+        let req = || {
+            self.get(&format!(
+                "{}/repositories/{workspace}/{repo}/src/{commit}/{path}",
+                self.base_url,
+            ))
+            .header("Accept", "*/*")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key(), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching repository file from Bitbucket API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        let data = res.bytes().await.map_err(|e| {
+            error!("{:?}", e);
+            "Error parsing repository file from Bitbucket API"
+        })?;
+
+        info!("fetching bitbucket repo file took {:?}", start.elapsed());
+
+        Ok(data.to_vec())
+    }
+
+    pub async fn fetch_repo_readme(
+        &self,
+        workspace: &str,
+        repo: &str,
+        commit: &str,
+        filename: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<String> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(&format!(
+                "{}/repositories/{workspace}/{repo}/src/{commit}/{}",
+                self.base_url,
+                url_encode(filename)
+            ))
+            .header("Accept", "*/*")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key(), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching repository README from Bitbucket API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        let data = res.text().await.map_err(|e| {
+            error!("{:?}", e);
+            "Error parsing repository README from Bitbucket API"
+        })?;
+
+        info!("fetching bitbucket repo readme took {:?}", start.elapsed());
+
+        Ok(data)
+    }
+
+    pub async fn fetch_workspace(
+        &self,
+        workspace: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<BitbucketApiWorkspace> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(&format!("{}/workspaces/{workspace}", self.base_url))
+                .header("Accept", "application/json")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key(), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching workspace from Bitbucket API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        let body = res.text().await?;
+        let json_body: BitbucketApiWorkspace =
+            parse_body(&body, "Error parsing workspace from Bitbucket API")?;
+
+        info!("fetching bitbucket workspace took {:?}", start.elapsed());
+
+        Ok(json_body)
+    }
+}
@@ -0,0 +1,166 @@
+use serde::Deserialize;
+
+use crate::repo::NewRepoTreeItem;
+
+#[derive(Deserialize)]
+pub struct BitbucketApiLink {
+    pub href: String,
+}
+
+#[derive(Deserialize)]
+pub struct BitbucketApiLinks {
+    pub html: Option<BitbucketApiLink>,
+    pub avatar: Option<BitbucketApiLink>,
+}
+
+#[derive(Deserialize)]
+pub struct BitbucketApiWorkspace {
+    pub uuid: String,
+    pub slug: String,
+    pub name: String,
+    #[serde(default)]
+    pub is_private: bool,
+    pub links: BitbucketApiLinks,
+    pub created_on: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BitbucketApiProject {
+    pub key: String,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct BitbucketApiBranchName {
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct BitbucketApiRepo {
+    pub uuid: String,
+    pub name: String,
+    pub slug: String,
+    pub full_name: String,
+    pub description: String,
+    pub is_private: bool,
+    pub fork_policy: String,
+    pub language: String,
+    pub size: i64,
+    pub has_issues: bool,
+    pub has_wiki: bool,
+    /// Missing for empty repositories.
+    pub mainbranch: Option<BitbucketApiBranchName>,
+    pub project: Option<BitbucketApiProject>,
+    pub workspace: BitbucketApiWorkspace,
+    pub links: BitbucketApiLinks,
+    pub created_on: String,
+    pub updated_on: String,
+}
+
+#[derive(Deserialize)]
+pub struct BitbucketApiCommit {
+    pub hash: String,
+}
+
+#[derive(Deserialize)]
+pub struct BitbucketApiBranch {
+    pub name: String,
+    pub target: BitbucketApiCommit,
+}
+
+/// A restriction on the branches matching a glob pattern or a branch type of the
+/// branching model, e.g. who can push to them.
+#[derive(Deserialize)]
+pub struct BitbucketApiBranchRestriction {
+    /// E.g. 'push', 'force', 'delete' or 'require_approvals_to_merge'.
+    pub kind: String,
+    /// 'glob' or 'branching_model'.
+    pub branch_match_kind: String,
+    /// Set for the 'glob' match kind, e.g. 'release/*'.
+    pub pattern: Option<String>,
+    /// Set for the 'branching_model' match kind, e.g. 'production' or 'feature'.
+    pub branch_type: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BitbucketApiBranchingModelBranch {
+    /// Missing if the branch doesn't exist.
+    pub branch: Option<BitbucketApiBranchName>,
+}
+
+#[derive(Deserialize)]
+pub struct BitbucketApiBranchType {
+    /// E.g. 'feature', 'bugfix', 'release' or 'hotfix'.
+    pub kind: String,
+    pub prefix: String,
+}
+
+#[derive(Deserialize)]
+pub struct BitbucketApiBranchingModel {
+    pub development: Option<BitbucketApiBranchingModelBranch>,
+    /// Missing if the repository has no production branch.
+    pub production: Option<BitbucketApiBranchingModelBranch>,
+    #[serde(default)]
+    pub branch_types: Vec<BitbucketApiBranchType>,
+}
+
+#[derive(Deserialize)]
+pub struct BitbucketApiTagger {
+    /// E.g. 'Jane Doe <jane@example.com>'.
+    pub raw: String,
+}
+
+/// Lightweight tags have no tagger, message or date.
+#[derive(Deserialize)]
+pub struct BitbucketApiTag {
+    pub name: String,
+    pub target: BitbucketApiCommit,
+    pub tagger: Option<BitbucketApiTagger>,
+    pub message: Option<String>,
+    pub date: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BitbucketApiTreeItem {
+    pub path: String,
+    /// 'commit_directory' or 'commit_file'.
+    pub r#type: String,
+    pub size: Option<i32>,
+    /// e.g. 'executable', 'link', 'subrepository', 'lfs' or 'binary'.
+    #[serde(default)]
+    pub attributes: Vec<String>,
+}
+
+/// Bitbucket doesn't expose git object IDs, so the SHA of a file is left unknown until
+/// its content is fetched. The git mode is derived from the attributes.
+impl From<BitbucketApiTreeItem> for NewRepoTreeItem {
+    fn from(item: BitbucketApiTreeItem) -> Self {
+        let has = |attr: &str| item.attributes.iter().any(|a| a == attr);
+        let (mode, r#type) = if item.r#type == "commit_directory" {
+            ("040000", "tree")
+        } else if has("subrepository") {
+            ("160000", "commit")
+        } else if has("link") {
+            ("120000", "blob")
+        } else if has("executable") {
+            ("100755", "blob")
+        } else {
+            ("100644", "blob")
+        };
+
+        Self {
+            path: item.path,
+            mode: mode.to_string(),
+            r#type: r#type.to_string(),
+            sha: None,
+            size: item.size,
+        }
+    }
+}
+
+/// A page of any paginated collection.
+#[derive(Deserialize)]
+pub struct BitbucketApiPage<T> {
+    pub values: Vec<T>,
+    pub next: Option<String>,
+}
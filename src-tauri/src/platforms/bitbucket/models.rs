@@ -0,0 +1,48 @@
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+
+/// Excluding `repo_id`.
+#[derive(Serialize, FromRow)]
+pub struct BitbucketRepo {
+    uuid: String,
+    pub workspace_uuid: String,
+    name: String,
+    slug: String,
+    full_name: String,
+    description: String,
+    is_private: bool,
+    fork_policy: String,
+    language: String,
+    size: i64,
+    has_issues: bool,
+    has_wiki: bool,
+    mainbranch: String,
+    project_key: Option<String>,
+    project_name: Option<String>,
+    html_url: String,
+    created_on: String,
+    updated_on: String,
+}
+
+/// Excluding `user_id`.
+#[derive(Serialize, FromRow)]
+pub struct BitbucketWorkspace {
+    uuid: String,
+    slug: String,
+    name: String,
+    is_private: bool,
+    avatar_url: String,
+    html_url: String,
+    created_on: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct BitbucketRepoData {
+    pub repo: BitbucketRepo,
+    pub workspace: Option<BitbucketWorkspace>,
+}
+
+#[derive(Serialize)]
+pub struct BitbucketUserData {
+    pub workspace: BitbucketWorkspace,
+}
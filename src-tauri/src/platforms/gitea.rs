@@ -0,0 +1,532 @@
+use api::GiteaAPI;
+use api_models::{GiteaApiBranch, GiteaApiRepo, GiteaApiTag};
+use async_trait::async_trait;
+use models::{GiteaRepo, GiteaRepoData, GiteaUser, GiteaUserData};
+use sqlx::{prelude::FromRow, SqlitePool};
+use tauri::AppHandle;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    commands::{
+        repo::{AddRepoProgress, DbPlatformRepo, RepoPreviewOwner},
+        user::PlatformUserData,
+    },
+    error::AppResult,
+    platforms::{
+        provider::{CloneRemote, FetchedTree, PlatformProvider, RepoContext, RepoRef},
+        Platform,
+    },
+    repo::{get_repo_stats, NewRepoTreeItem, PlatformRepoData, RepoStats},
+    user::{add_user, add_user_avatar, delete_user, get_user_avatar_path, get_user_id},
+    utils::data::is_commit_sha,
+};
+
+pub mod api;
+pub mod api_models;
+pub mod models;
+
+async fn add_gitea_repo_topics(
+    repo_id: i64,
+    topics: Vec<String>,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let delete_query = "DELETE FROM gitea_repo_topic WHERE repo_id = ?";
+    sqlx::query(delete_query)
+        .bind(repo_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing Gitea repository topics from database"
+        })?;
+
+    for topic in topics {
+        let topic_query = "
+            INSERT INTO gitea_repo_topic (
+                repo_id, topic
+            )
+            VALUES (?, ?)
+        ";
+        sqlx::query(topic_query)
+            .bind(repo_id)
+            .bind(topic)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding Gitea repository topic to database"
+            })?;
+    }
+
+    Ok(())
+}
+
+async fn add_gitea_repo_row(
+    repo_id: i64,
+    gitea_repo: &GiteaApiRepo,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let query = "
+        INSERT INTO gitea_repo (
+            repo_id, id, owner_id, name, full_name,
+            description, empty, private, fork, template,
+            mirror, size, language, html_url, website,
+            stars_count, forks_count, watchers_count, open_issues_count, open_pr_counter,
+            release_counter, default_branch, archived, has_issues, has_wiki,
+            has_pull_requests, created_at, updated_at
+        )
+        VALUES (
+            ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?,
+            ?, ?, ?
+        )
+        ON CONFLICT (repo_id) DO UPDATE SET
+            id = excluded.id, owner_id = excluded.owner_id, name = excluded.name,
+            full_name = excluded.full_name, description = excluded.description,
+            empty = excluded.empty, private = excluded.private, fork = excluded.fork,
+            template = excluded.template, mirror = excluded.mirror, size = excluded.size,
+            language = excluded.language, html_url = excluded.html_url,
+            website = excluded.website, stars_count = excluded.stars_count,
+            forks_count = excluded.forks_count, watchers_count = excluded.watchers_count,
+            open_issues_count = excluded.open_issues_count,
+            open_pr_counter = excluded.open_pr_counter,
+            release_counter = excluded.release_counter,
+            default_branch = excluded.default_branch, archived = excluded.archived,
+            has_issues = excluded.has_issues, has_wiki = excluded.has_wiki,
+            has_pull_requests = excluded.has_pull_requests, created_at = excluded.created_at,
+            updated_at = excluded.updated_at
+    ";
+    sqlx::query(query)
+        .bind(repo_id)
+        .bind(gitea_repo.id)
+        .bind(gitea_repo.owner.id)
+        .bind(&gitea_repo.name)
+        .bind(&gitea_repo.full_name)
+        .bind(&gitea_repo.description)
+        .bind(gitea_repo.empty)
+        .bind(gitea_repo.private)
+        .bind(gitea_repo.fork)
+        .bind(gitea_repo.template)
+        .bind(gitea_repo.mirror)
+        .bind(gitea_repo.size)
+        .bind(&gitea_repo.language)
+        .bind(&gitea_repo.html_url)
+        .bind(&gitea_repo.website)
+        .bind(gitea_repo.stars_count)
+        .bind(gitea_repo.forks_count)
+        .bind(gitea_repo.watchers_count)
+        .bind(gitea_repo.open_issues_count)
+        .bind(gitea_repo.open_pr_counter)
+        .bind(gitea_repo.release_counter)
+        .bind(&gitea_repo.default_branch)
+        .bind(gitea_repo.archived)
+        .bind(gitea_repo.has_issues)
+        .bind(gitea_repo.has_wiki)
+        .bind(gitea_repo.has_pull_requests)
+        .bind(&gitea_repo.created_at)
+        .bind(&gitea_repo.updated_at)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding Gitea repository to database"
+        })?;
+
+    Ok(())
+}
+
+/// The repository response includes the full owner, so no user request is needed.
+async fn add_gitea_repo_owner_user(
+    instance_id: Option<i64>,
+    user: &str,
+    repo: &str,
+    api: &GiteaAPI,
+    pool: &SqlitePool,
+    app: &AppHandle,
+) -> AppResult<()> {
+    AddRepoProgress::Owner.send("gitea", user, repo, 0, 0, 4, app);
+
+    if get_user_id("gitea", instance_id, user, pool)
+        .await?
+        .is_some()
+    {
+        AddRepoProgress::Owner.send("gitea", user, repo, 100, 1, 1, app);
+        return Ok(());
+    }
+
+    let owner = api.fetch_repo(user, repo, pool).await?.owner;
+    AddRepoProgress::Owner.send("gitea", user, repo, 25, 1, 4, app);
+
+    let user_id = add_user("gitea", instance_id, user, pool).await?;
+    // The user is removed again if anything fails, so the next add doesn't skip the owner.
+    let added: AppResult<()> = async {
+        AddRepoProgress::Owner.send("gitea", user, repo, 50, 2, 4, app);
+
+        let query = "
+            INSERT INTO gitea_user (
+                user_id, id, login, full_name, avatar_url,
+                html_url, description, website, location, followers_count,
+                following_count, starred_repos_count, created
+            )
+            VALUES (
+                ?, ?, ?, ?, ?,
+                ?, ?, ?, ?, ?,
+                ?, ?, ?
+            )
+        ";
+        sqlx::query(query)
+            .bind(user_id)
+            .bind(owner.id)
+            .bind(&owner.login)
+            .bind(&owner.full_name)
+            .bind(&owner.avatar_url)
+            .bind(&owner.html_url)
+            .bind(&owner.description)
+            .bind(&owner.website)
+            .bind(&owner.location)
+            .bind(owner.followers_count)
+            .bind(owner.following_count)
+            .bind(owner.starred_repos_count)
+            .bind(&owner.created)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding Gitea user to database"
+            })?;
+
+        AddRepoProgress::Owner.send("gitea", user, repo, 75, 3, 4, app);
+
+        add_user_avatar(user_id, "gitea", &owner.avatar_url, pool).await?;
+        Ok(())
+    }
+    .await;
+    if let Err(e) = added {
+        if let Err(e) = delete_user(user_id, pool).await {
+            error!("{:?}", e);
+        }
+        return Err(e);
+    }
+
+    AddRepoProgress::Owner.send("gitea", user, repo, 100, 4, 4, app);
+
+    Ok(())
+}
+
+/// Add the repo metadata, returning the default branch.
+pub async fn add_gitea_repo(
+    repo_id: i64,
+    user: &str,
+    repo: &str,
+    api: &GiteaAPI,
+    pool: &SqlitePool,
+    app: &AppHandle,
+) -> AppResult<String> {
+    AddRepoProgress::Metadata.send("gitea", user, repo, 0, 0, 2, app);
+    let gitea_repo = api.fetch_repo(user, repo, pool).await?;
+    AddRepoProgress::Metadata.send("gitea", user, repo, 50, 1, 2, app);
+
+    if gitea_repo.empty {
+        return Err("Gitea repository is empty".into());
+    }
+
+    add_gitea_repo_row(repo_id, &gitea_repo, pool).await?;
+    add_gitea_repo_topics(repo_id, gitea_repo.topics, pool).await?;
+    AddRepoProgress::Metadata.send("gitea", user, repo, 100, 2, 2, app);
+
+    Ok(gitea_repo.default_branch)
+}
+
+#[derive(FromRow)]
+struct GiteaRepoPreview {
+    description: String,
+    stars_count: i32,
+    forks_count: i32,
+    open_issues_count: i32,
+    open_pr_counter: i32,
+    private: bool,
+}
+
+#[derive(FromRow)]
+struct DbRepoOwner {
+    id: i64,
+    user: String,
+}
+
+pub async fn get_gitea_repo_preview(
+    repo_id: i64,
+    pool: &SqlitePool,
+) -> AppResult<(DbPlatformRepo, RepoPreviewOwner)> {
+    let start = Instant::now();
+
+    let query = "SELECT description, stars_count, forks_count, open_issues_count, open_pr_counter, private FROM gitea_repo WHERE repo_id = ?";
+    let gitea_repo = sqlx::query_as::<_, GiteaRepoPreview>(query)
+        .bind(repo_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting Gitea repository from database"
+        })?;
+
+    let owner_query = "
+        SELECT u.id, u.user
+        FROM repo r
+        JOIN user u ON u.platform = r.platform AND u.instance_id IS r.instance_id AND u.user = r.user
+        WHERE r.id = ?
+    ";
+    let owner = sqlx::query_as::<_, DbRepoOwner>(owner_query)
+        .bind(repo_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting Gitea repository owner from database"
+        })?;
+
+    let avatar = get_user_avatar_path(owner.id, pool)
+        .await?
+        .unwrap_or_default();
+
+    info!(
+        "got gitea repo preview from database in {:?}",
+        start.elapsed()
+    );
+
+    Ok((
+        DbPlatformRepo {
+            description: gitea_repo.description,
+            stars: gitea_repo.stars_count,
+            forks: gitea_repo.forks_count,
+            issues: gitea_repo.open_issues_count,
+            pull_requests: gitea_repo.open_pr_counter,
+            visibility: if gitea_repo.private {
+                "private".to_string()
+            } else {
+                "public".to_string()
+            },
+        },
+        RepoPreviewOwner {
+            id: owner.id,
+            user: owner.user,
+            avatar,
+        },
+    ))
+}
+
+pub async fn get_gitea_repo(repo_id: i64, pool: &SqlitePool) -> AppResult<GiteaRepoData> {
+    let query = "SELECT * FROM gitea_repo WHERE repo_id = ?";
+    let gitea_repo = sqlx::query_as::<_, GiteaRepo>(query)
+        .bind(repo_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting gitea repository from database"
+        })?;
+
+    let owner_query = "
+        SELECT g.*
+        FROM gitea_user g
+        JOIN user u ON u.id = g.user_id
+        JOIN repo r ON r.instance_id IS u.instance_id
+        WHERE r.id = ? AND g.id = ?
+    ";
+    let owner = sqlx::query_as::<_, GiteaUser>(owner_query)
+        .bind(repo_id)
+        .bind(gitea_repo.owner_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting gitea owner from database"
+        })?;
+
+    let topics_query = "SELECT topic FROM gitea_repo_topic WHERE repo_id = ?";
+    let topics = sqlx::query_scalar::<_, String>(topics_query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting gitea topics from database"
+        })?;
+
+    Ok(GiteaRepoData {
+        repo: gitea_repo,
+        owner,
+        topics,
+    })
+}
+
+pub async fn get_gitea_user(user_id: i64, pool: &SqlitePool) -> AppResult<GiteaUserData> {
+    let query = "SELECT * FROM gitea_user WHERE user_id = ?";
+    let gitea_user = sqlx::query_as::<_, GiteaUser>(query)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting gitea user from database"
+        })?;
+
+    Ok(GiteaUserData { user: gitea_user })
+}
+
+#[async_trait]
+impl PlatformProvider for GiteaAPI {
+    fn platform(&self) -> Platform {
+        Platform::Gitea
+    }
+
+    fn set_token(&self, token: Option<String>) {
+        GiteaAPI::set_token(self, token);
+    }
+
+    async fn add_repo(
+        &self,
+        ctx: &RepoContext,
+        pool: &SqlitePool,
+        app: &AppHandle,
+    ) -> AppResult<String> {
+        add_gitea_repo(ctx.repo_id, &ctx.user, &ctx.repo, self, pool, app).await
+    }
+
+    async fn fetch_tree(
+        &self,
+        ctx: &RepoContext,
+        rev: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<FetchedTree> {
+        let commit = if is_commit_sha(rev) {
+            rev.to_string()
+        } else {
+            self.fetch_branch(&ctx.user, &ctx.repo, rev, pool)
+                .await?
+                .commit
+                .id
+        };
+        let tree = self
+            .fetch_repo_tree(&ctx.user, &ctx.repo, &commit, pool)
+            .await?;
+
+        Ok(FetchedTree {
+            sha: tree.sha,
+            rev: commit,
+            truncated: tree.truncated,
+            items: tree
+                .tree
+                .unwrap_or_default()
+                .into_iter()
+                .map(NewRepoTreeItem::from)
+                .collect(),
+        })
+    }
+
+    async fn fetch_refs(&self, ctx: &RepoContext, pool: &SqlitePool) -> AppResult<Vec<RepoRef>> {
+        let branches: Vec<GiteaApiBranch> = self
+            .fetch_repo_list(&ctx.user, &ctx.repo, "branches", pool)
+            .await?;
+        let tags: Vec<GiteaApiTag> = self
+            .fetch_repo_list(&ctx.user, &ctx.repo, "tags", pool)
+            .await?;
+
+        let branches = branches
+            .into_iter()
+            .map(|branch| RepoRef::branch(branch.name, branch.commit.id, branch.protected));
+        let tags = tags.into_iter().map(|tag| {
+            let annotated = tag.id != tag.commit.sha;
+            RepoRef {
+                annotated: Some(annotated),
+                message: tag.message.filter(|_| annotated),
+                ..RepoRef::tag(tag.name, tag.commit.sha)
+            }
+        });
+        Ok(branches.chain(tags).collect())
+    }
+
+    async fn fetch_file(
+        &self,
+        ctx: &RepoContext,
+        rev: &str,
+        path: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<String> {
+        self.fetch_repo_readme(&ctx.user, &ctx.repo, rev, path, pool)
+            .await
+    }
+
+    async fn fetch_blob(
+        &self,
+        ctx: &RepoContext,
+        rev: &str,
+        path: &str,
+        _sha: Option<&str>,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<u8>> {
+        self.fetch_raw_file(&ctx.user, &ctx.repo, rev, path, pool)
+            .await
+    }
+
+    async fn add_owner(
+        &self,
+        ctx: &RepoContext,
+        pool: &SqlitePool,
+        app: &AppHandle,
+    ) -> AppResult<()> {
+        add_gitea_repo_owner_user(ctx.instance_id, &ctx.user, &ctx.repo, self, pool, app).await
+    }
+
+    fn clone_remote(&self, ctx: &RepoContext) -> CloneRemote {
+        // Gitea accepts a token as the password for any username.
+        CloneRemote {
+            url: format!("{}/{}/{}.git", self.base_url(), ctx.user, ctx.repo),
+            credentials: self.token().map(|t| ("oauth2".to_string(), t)),
+        }
+    }
+
+    async fn get_repo(&self, repo_id: i64, pool: &SqlitePool) -> AppResult<PlatformRepoData> {
+        Ok(PlatformRepoData::Gitea(
+            get_gitea_repo(repo_id, pool).await?,
+        ))
+    }
+
+    async fn get_repo_stats(
+        &self,
+        repo_id: i64,
+        pool: &SqlitePool,
+    ) -> AppResult<Option<RepoStats>> {
+        let query = "
+            SELECT
+                stars_count AS stars, forks_count AS forks, watchers_count AS watchers,
+                open_issues_count AS open_issues, default_branch, archived,
+                CASE WHEN private THEN 'private' ELSE 'public' END AS visibility,
+                NULL AS license,
+                (
+                    SELECT group_concat(topic, ',') FROM gitea_repo_topic t
+                    WHERE t.repo_id = r.repo_id
+                ) AS topics
+            FROM gitea_repo r
+            WHERE r.repo_id = ?
+        ";
+        get_repo_stats(query, repo_id, pool).await
+    }
+
+    async fn get_repo_preview(
+        &self,
+        repo_id: i64,
+        pool: &SqlitePool,
+    ) -> AppResult<(DbPlatformRepo, RepoPreviewOwner)> {
+        get_gitea_repo_preview(repo_id, pool).await
+    }
+
+    async fn get_user(&self, user_id: i64, pool: &SqlitePool) -> AppResult<PlatformUserData> {
+        Ok(PlatformUserData::Gitea(
+            get_gitea_user(user_id, pool).await?,
+        ))
+    }
+}
@@ -0,0 +1,328 @@
+use std::sync::RwLock;
+
+use reqwest::RequestBuilder;
+use serde::de::DeserializeOwned;
+use sqlx::SqlitePool;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    error::{AppError, AppResult},
+    platforms::{
+        gitea::api_models::{GiteaApiBranch, GiteaApiRepo, GiteaApiRepoTree},
+        Platform,
+    },
+    utils::{
+        data::{parse_body, url_encode},
+        rate_limit::{send_with_retry, RateLimitKey},
+    },
+};
+
+/// Client for the API (v1) of a Gitea or Forgejo instance, e.g. `https://codeberg.org`.
+pub struct GiteaAPI {
+    client: reqwest::Client,
+    base_url: String,
+    token: RwLock<Option<String>>,
+}
+
+impl GiteaAPI {
+    pub fn new(base_url: &str, token: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: RwLock::new(token.filter(|t| !t.is_empty())),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Requests are counted per host and token, the API sends no rate limit headers.
+    pub fn rate_limit_key(&self) -> RateLimitKey {
+        RateLimitKey::new(
+            Platform::Gitea,
+            &self.base_url,
+            self.token().as_deref(),
+            "core",
+        )
+    }
+
+    /// Replace the token used for subsequent requests.
+    pub fn set_token(&self, token: Option<String>) {
+        *self.token.write().unwrap() = token.filter(|t| !t.is_empty());
+    }
+
+    pub fn token(&self) -> Option<String> {
+        self.token.read().unwrap().clone()
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        let req = self
+            .client
+            .get(format!("{}/api/v1{path}", self.base_url))
+            .header("User-Agent", "Git Chest");
+        match self.token.read().unwrap().as_deref() {
+            Some(token) => req.header("Authorization", format!("token {token}")),
+            None => req,
+        }
+    }
+
+    pub async fn fetch_repo(
+        &self,
+        user: &str,
+        repo: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<GiteaApiRepo> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(&format!("/repos/{user}/{repo}"))
+                .header("Accept", "application/json")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key(), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching repository from Gitea API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        let body = res.text().await?;
+        let json_body: GiteaApiRepo = parse_body(&body, "Error parsing repository from Gitea API")?;
+
+        info!("fetching gitea repo took {:?}", start.elapsed());
+
+        Ok(json_body)
+    }
+
+    pub async fn fetch_branch(
+        &self,
+        user: &str,
+        repo: &str,
+        branch: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<GiteaApiBranch> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(&format!(
+                "/repos/{user}/{repo}/branches/{}",
+                url_encode(branch)
+            ))
+            .header("Accept", "application/json")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key(), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching repository branch from Gitea API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        let body = res.text().await?;
+        let json_body: GiteaApiBranch =
+            parse_body(&body, "Error parsing repository branch from Gitea API")?;
+
+        info!("fetching gitea repo branch took {:?}", start.elapsed());
+
+        Ok(json_body)
+    }
+
+    /// Fetch every page of a list endpoint of a repo, e.g. `branches` or `tags`.
+    pub async fn fetch_repo_list<T: DeserializeOwned>(
+        &self,
+        user: &str,
+        repo: &str,
+        path: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<T>> {
+        let start = Instant::now();
+        let mut items: Vec<T> = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let req = || {
+                self.get(&format!("/repos/{user}/{repo}/{path}?limit=50&page={page}"))
+                    .header("Accept", "application/json")
+            };
+            let res = send_with_retry(req, &self.rate_limit_key(), pool)
+                .await
+                .map_err(|e| {
+                    error!("{:?}", e);
+                    "Error fetching repository list from Gitea API"
+                })?;
+
+            if let Err(error) = res.error_for_status_ref() {
+                error!("{:?}", res.text().await?);
+                return AppError::new(&error.to_string());
+            }
+
+            let body = res.text().await?;
+            let mut page_items: Vec<T> =
+                parse_body(&body, "Error parsing repository list from Gitea API")?;
+            // The instance may cap the page size below the requested limit.
+            if page_items.is_empty() {
+                break;
+            }
+            items.append(&mut page_items);
+            page += 1;
+        }
+
+        info!("fetching gitea repo {} took {:?}", path, start.elapsed());
+
+        Ok(items)
+    }
+
+    /// Fetch the recursive tree of a commit, the items are split over pages of
+    /// the instance's maximum page size.
+    pub async fn fetch_repo_tree(
+        &self,
+        user: &str,
+        repo: &str,
+        sha: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<GiteaApiRepoTree> {
+        let start = Instant::now();
+        let mut page = 1;
+        let mut tree: Option<GiteaApiRepoTree> = None;
+
+        loop {
+            let req = || {
+                self.get(&format!(
+                    "/repos/{user}/{repo}/git/trees/{sha}?recursive=true&page={page}"
+                ))
+                .header("Accept", "application/json")
+            };
+            let res = send_with_retry(req, &self.rate_limit_key(), pool)
+                .await
+                .map_err(|e| {
+                    error!("{:?}", e);
+                    "Error fetching repository tree from Gitea API"
+                })?;
+
+            if let Err(error) = res.error_for_status_ref() {
+                error!("{:?}", res.text().await?);
+                return AppError::new(&error.to_string());
+            }
+
+            let body = res.text().await?;
+            let page_tree: GiteaApiRepoTree =
+                parse_body(&body, "Error parsing repository tree from Gitea API")?;
+            let page_items = page_tree.tree.unwrap_or_default();
+            let is_empty = page_items.is_empty();
+
+            let tree = tree.get_or_insert(GiteaApiRepoTree {
+                sha: page_tree.sha,
+                tree: Some(Vec::new()),
+                truncated: false,
+                page: page_tree.page,
+                total_count: page_tree.total_count,
+            });
+            let items = tree.tree.get_or_insert_with(Vec::new);
+            items.extend(page_items);
+            tree.truncated = page_tree.truncated;
+
+            if is_empty || items.len() as i64 >= tree.total_count {
+                break;
+            }
+            page += 1;
+        }
+
+        info!("fetching gitea repo tree took {:?}", start.elapsed());
+
+        tree.ok_or_else(|| "Error fetching repository tree from Gitea API".into())
+    }
+
+    /// Fetch the raw content of a file at a revision, blobs can't be fetched raw by SHA.
+    pub async fn fetch_raw_file(
+        &self,
+        user: &str,
+        repo: &str,
+        rev: &str,
+        path: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<u8>> {
+        let start = Instant::now();
+
+        let path = path
+            .split('/')
+            .map(url_encode)
+            .collect::<Vec<_>>()
+            .join("/");
+        let req = || {
+            self.get(&format!(
+                "/repos/{user}/{repo}/raw/{path}?ref={}",
+                url_encode(rev)
+            ))
+            .header("Accept", "*/*")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key(), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching repository file from Gitea API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        let data = res.bytes().await.map_err(|e| {
+            error!("{:?}", e);
+            "Error parsing repository file from Gitea API"
+        })?;
+
+        info!("fetching gitea repo file took {:?}", start.elapsed());
+
+        Ok(data.to_vec())
+    }
+
+    pub async fn fetch_repo_readme(
+        &self,
+        user: &str,
+        repo: &str,
+        sha: &str,
+        filename: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<String> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(&format!(
+                "/repos/{user}/{repo}/raw/{}?ref={sha}",
+                url_encode(filename)
+            ))
+            .header("Accept", "*/*")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key(), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching repository README from Gitea API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        let data = res.text().await.map_err(|e| {
+            error!("{:?}", e);
+            "Error parsing repository README from Gitea API"
+        })?;
+
+        info!("fetching gitea repo readme took {:?}", start.elapsed());
+
+        Ok(data)
+    }
+}
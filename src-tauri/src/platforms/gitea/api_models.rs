@@ -0,0 +1,120 @@
+use serde::Deserialize;
+
+use crate::repo::NewRepoTreeItem;
+
+/// Used for both users and organizations.
+#[derive(Deserialize)]
+pub struct GiteaApiUser {
+    pub id: i64,
+    pub login: String,
+    #[serde(default)]
+    pub full_name: String,
+    pub avatar_url: String,
+    #[serde(default)]
+    pub html_url: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub website: String,
+    #[serde(default)]
+    pub location: String,
+    #[serde(default)]
+    pub followers_count: i32,
+    #[serde(default)]
+    pub following_count: i32,
+    #[serde(default)]
+    pub starred_repos_count: i32,
+    pub created: String,
+}
+
+/// URL-related properties are not included, except for `html_url`.
+#[derive(Deserialize)]
+pub struct GiteaApiRepo {
+    pub id: i64,
+    pub owner: GiteaApiUser,
+    pub name: String,
+    pub full_name: String,
+    pub description: String,
+    pub empty: bool,
+    pub private: bool,
+    pub fork: bool,
+    pub template: bool,
+    pub mirror: bool,
+    pub size: i32,
+    pub language: String,
+    pub html_url: String,
+    pub website: String,
+    pub stars_count: i32,
+    pub forks_count: i32,
+    pub watchers_count: i32,
+    pub open_issues_count: i32,
+    pub open_pr_counter: i32,
+    pub release_counter: i32,
+    pub default_branch: String,
+    pub archived: bool,
+    pub has_issues: bool,
+    pub has_wiki: bool,
+    pub has_pull_requests: bool,
+    /// Only returned by newer versions.
+    #[serde(default)]
+    pub topics: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct GiteaApiCommit {
+    pub id: String,
+}
+
+#[derive(Deserialize)]
+pub struct GiteaApiBranch {
+    pub name: String,
+    pub commit: GiteaApiCommit,
+    pub protected: bool,
+}
+
+#[derive(Deserialize)]
+pub struct GiteaApiTagCommit {
+    pub sha: String,
+}
+
+#[derive(Deserialize)]
+pub struct GiteaApiTag {
+    pub name: String,
+    /// The tag object SHA of annotated tags, the commit SHA of lightweight ones.
+    pub id: String,
+    pub message: Option<String>,
+    pub commit: GiteaApiTagCommit,
+}
+
+#[derive(Deserialize)]
+pub struct GiteaApiRepoTreeItem {
+    pub path: String,
+    pub mode: String,
+    pub r#type: String,
+    pub size: Option<i32>,
+    pub sha: String,
+}
+
+impl From<GiteaApiRepoTreeItem> for NewRepoTreeItem {
+    fn from(item: GiteaApiRepoTreeItem) -> Self {
+        Self {
+            path: item.path,
+            mode: item.mode,
+            r#type: item.r#type,
+            sha: Some(item.sha),
+            size: item.size,
+        }
+    }
+}
+
+/// A single page of a recursive tree.
+#[derive(Deserialize)]
+pub struct GiteaApiRepoTree {
+    pub sha: String,
+    pub tree: Option<Vec<GiteaApiRepoTreeItem>>,
+    pub truncated: bool,
+    pub page: i64,
+    pub total_count: i64,
+}
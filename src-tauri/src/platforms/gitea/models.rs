@@ -0,0 +1,63 @@
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+
+/// Excluding `repo_id`.
+#[derive(Serialize, FromRow)]
+pub struct GiteaRepo {
+    pub id: i64,
+    pub owner_id: i64,
+    name: String,
+    full_name: String,
+    description: String,
+    empty: bool,
+    private: bool,
+    fork: bool,
+    template: bool,
+    mirror: bool,
+    size: i32,
+    language: String,
+    html_url: String,
+    website: String,
+    stars_count: i32,
+    forks_count: i32,
+    watchers_count: i32,
+    open_issues_count: i32,
+    open_pr_counter: i32,
+    release_counter: i32,
+    default_branch: String,
+    archived: bool,
+    has_issues: bool,
+    has_wiki: bool,
+    has_pull_requests: bool,
+    created_at: String,
+    updated_at: String,
+}
+
+/// Excluding `user_id`.
+#[derive(Serialize, FromRow)]
+pub struct GiteaUser {
+    pub id: i64,
+    pub login: String,
+    full_name: String,
+    avatar_url: String,
+    html_url: String,
+    description: String,
+    website: String,
+    location: String,
+    followers_count: i32,
+    following_count: i32,
+    starred_repos_count: i32,
+    created: String,
+}
+
+#[derive(Serialize)]
+pub struct GiteaRepoData {
+    pub repo: GiteaRepo,
+    pub owner: Option<GiteaUser>,
+    pub topics: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct GiteaUserData {
+    pub user: GiteaUser,
+}
@@ -1,42 +1,59 @@
 use std::collections::HashMap;
 
-use crate::utils::dirs::ensure_dir;
 use api::GitHubAPI;
-use api_models::{GitHubApiRepoLicense, GitHubApiRepoOrg, GitHubApiRepoOwner, GitHubApiRepoTree};
+use api_models::{
+    GitHubApiBranch, GitHubApiCommit, GitHubApiContributor, GitHubApiFork,
+    GitHubApiGraphQLRepository, GitHubApiIssue, GitHubApiPull, GitHubApiRefs, GitHubApiRelease,
+    GitHubApiRepoLicense, GitHubApiRepoOrg, GitHubApiRepoOwner, GitHubApiTag, GitHubApiTagRef,
+};
+use async_trait::async_trait;
 use chrono::Utc;
 use models::{
     GitHubRepo, GitHubRepoCustomProperty, GitHubRepoData, GitHubRepoLicense, GitHubRepoOrg,
-    GitHubRepoOwner, GitHubUser, GitHubUserData,
+    GitHubRepoOwner, GitHubTreeWalkItem, GitHubUser, GitHubUserData,
 };
+use serde_json::{json, Map};
 use sqlx::{prelude::FromRow, SqlitePool};
 use tauri::AppHandle;
-use tokio::{fs, io::AsyncWriteExt, time::Instant};
-use tracing::{error, info};
+use tokio::time::Instant;
+use tracing::{error, info, warn};
 
 use crate::{
-    commands::repo::{AddRepoProgress, DbPlatformRepo, RepoPreviewOwner},
+    commands::{
+        repo::{AddRepoProgress, DbPlatformRepo, RepoPreviewOwner},
+        user::PlatformUserData,
+    },
     error::AppResult,
-    repo::download_readme_assets,
-    utils::{data::progress_percentage, dirs::get_data_dir, image::download_image},
+    events::{RepoEvent, WatchedItem},
+    platforms::{
+        provider::{CloneRemote, FetchedTree, PlatformProvider, RepoContext, RepoRef},
+        Platform,
+    },
+    repo::{get_repo_stats, NewRepoTreeItem, PlatformRepoData, RepoStats},
+    user::{add_user, add_user_avatar, delete_user, get_user_avatar_path, get_user_id},
 };
 
 pub mod api;
 pub mod api_models;
+pub mod discussion;
+pub mod issue;
 pub mod models;
+pub mod pull;
+pub mod release;
 
 async fn add_github_repo_owner(
-    github_repo_id: i64,
+    repo_id: i64,
     owner: GitHubApiRepoOwner,
     pool: &SqlitePool,
 ) -> AppResult<()> {
     let org_query = "
         INSERT OR REPLACE INTO github_repo_owner (
-            github_repo_id, login, id, node_id, gravatar_id, type, site_admin
+            repo_id, login, id, node_id, gravatar_id, type, site_admin
         )
         VALUES (?, ?, ?, ?, ?, ?, ?)
     ";
     sqlx::query(org_query)
-        .bind(github_repo_id)
+        .bind(repo_id)
         .bind(owner.login)
         .bind(owner.id)
         .bind(owner.node_id)
@@ -54,19 +71,19 @@ async fn add_github_repo_owner(
 }
 
 async fn add_github_repo_org(
-    github_repo_id: i64,
+    repo_id: i64,
     org: Option<GitHubApiRepoOrg>,
     pool: &SqlitePool,
 ) -> AppResult<()> {
     if let Some(org) = org {
         let org_query = "
             INSERT OR REPLACE INTO github_repo_org (
-                github_repo_id, login, id, node_id, gravatar_id, type, site_admin
+                repo_id, login, id, node_id, gravatar_id, type, site_admin
             )
             VALUES (?, ?, ?, ?, ?, ?, ?)
         ";
         sqlx::query(org_query)
-            .bind(github_repo_id)
+            .bind(repo_id)
             .bind(org.login)
             .bind(org.id)
             .bind(org.node_id)
@@ -85,18 +102,18 @@ async fn add_github_repo_org(
 }
 
 async fn add_github_repo_license(
-    github_repo_id: i64,
+    repo_id: i64,
     license: GitHubApiRepoLicense,
     pool: &SqlitePool,
 ) -> AppResult<()> {
     let license_query = "
         INSERT OR REPLACE INTO github_repo_license (
-            github_repo_id, key, name, spdx_id, node_id
+            repo_id, key, name, spdx_id, node_id
         )
         VALUES (?, ?, ?, ?, ?)
     ";
     sqlx::query(license_query)
-        .bind(github_repo_id)
+        .bind(repo_id)
         .bind(license.key)
         .bind(license.name)
         .bind(license.spdx_id)
@@ -112,19 +129,29 @@ async fn add_github_repo_license(
 }
 
 async fn add_github_repo_topics(
-    github_repo_id: i64,
+    repo_id: i64,
     topics: Vec<String>,
     pool: &SqlitePool,
 ) -> AppResult<()> {
+    let delete_query = "DELETE FROM github_repo_topic WHERE repo_id = ?";
+    sqlx::query(delete_query)
+        .bind(repo_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing GitHub repository topics from database"
+        })?;
+
     for topic in topics {
         let topic_query = "
             INSERT INTO github_repo_topic (
-                github_repo_id, topic
+                repo_id, topic
             )
             VALUES (?, ?)
         ";
         sqlx::query(topic_query)
-            .bind(github_repo_id)
+            .bind(repo_id)
             .bind(topic)
             .execute(pool)
             .await
@@ -138,20 +165,30 @@ async fn add_github_repo_topics(
 }
 
 async fn add_github_repo_custom_properties(
-    github_repo_id: i64,
+    repo_id: i64,
     custom_properties: Option<HashMap<String, String>>,
     pool: &SqlitePool,
 ) -> AppResult<()> {
+    let delete_query = "DELETE FROM github_repo_custom_property WHERE repo_id = ?";
+    sqlx::query(delete_query)
+        .bind(repo_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing GitHub repository custom properties from database"
+        })?;
+
     if let Some(custom_properties) = custom_properties {
         for custom_property in custom_properties {
             let custom_property_query = "
             INSERT INTO github_repo_custom_property (
-                github_repo_id, key, value
+                repo_id, key, value
             )
             VALUES (?, ?, ?)
         ";
             sqlx::query(custom_property_query)
-                .bind(github_repo_id)
+                .bind(repo_id)
                 .bind(custom_property.0)
                 .bind(custom_property.1)
                 .execute(pool)
@@ -166,205 +203,93 @@ async fn add_github_repo_custom_properties(
     Ok(())
 }
 
-async fn add_github_repo_tree(
-    repo_id: i64,
+async fn add_github_repo_owner_user(
+    instance_id: Option<i64>,
     user: &str,
     repo: &str,
-    tree: GitHubApiRepoTree,
+    api: &GitHubAPI,
     pool: &SqlitePool,
     app: &AppHandle,
 ) -> AppResult<()> {
-    let query = "
-        INSERT INTO repo_tree (
-            repo_id, sha, truncated
-        )
-        VALUES (?, ?, ?)
-    ";
-    sqlx::query(query)
-        .bind(repo_id)
-        .bind(tree.sha)
-        .bind(tree.truncated)
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            error!("{:?}", e);
-            "Error adding GitHub repository tree to database"
-        })?;
+    AddRepoProgress::Owner.send("github", user, repo, 0, 0, 5, app);
 
-    let mut tree_ids: HashMap<&str, i64> = HashMap::new();
-    let total_tree_items = tree.tree.len();
+    if get_user_id("github", instance_id, user, pool)
+        .await?
+        .is_some()
+    {
+        AddRepoProgress::Owner.send("github", user, repo, 100, 1, 1, app);
+        return Ok(());
+    }
 
-    for (i, item) in tree.tree.iter().enumerate() {
-        let mut parent_id: Option<i64> = None;
-        let path_parts: Vec<&str> = item.path.split("/").collect();
-        // If a parent is found in the path.
-        if path_parts.len() >= 2 {
-            let parent_name = &path_parts[path_parts.len() - 2];
-            parent_id = Some(*tree_ids.get(*parent_name).unwrap());
-        }
+    let github_user = api.fetch_user(user, pool).await?;
+    AddRepoProgress::Owner.send("github", user, repo, 20, 1, 5, app);
 
-        let item_query = "
-            INSERT INTO repo_tree_item (
-                repo_id, parent_id, path, mode, type, sha, size
+    let user_id = add_user("github", instance_id, user, pool).await?;
+    // The user is removed again if anything fails, so the next add doesn't skip the owner.
+    let added: AppResult<()> = async {
+        AddRepoProgress::Owner.send("github", user, repo, 40, 2, 5, app);
+
+        let query = "
+            INSERT INTO github_user (
+                user_id, login, id, node_id, gravatar_id,
+                type, site_admin, company, blog, location,
+                hireable, bio, twitter_username, public_repos, public_gists,
+                followers, following, created_at, updated_at
+            )
+            VALUES (
+                ?, ?, ?, ?, ?,
+                ?, ?, ?, ?, ?,
+                ?, ?, ?, ?, ?,
+                ?, ?, ?, ?
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?)
         ";
-
-        let item_id = sqlx::query(item_query)
-            .bind(repo_id)
-            .bind(parent_id)
-            .bind(&item.path)
-            .bind(&item.mode)
-            .bind(&item.r#type)
-            .bind(&item.sha)
-            .bind(item.size)
+        sqlx::query(query)
+            .bind(user_id)
+            .bind(github_user.login)
+            .bind(github_user.id)
+            .bind(github_user.node_id)
+            .bind(github_user.gravatar_id)
+            .bind(github_user.r#type)
+            .bind(github_user.site_admin)
+            .bind(github_user.company)
+            .bind(github_user.blog)
+            .bind(github_user.location)
+            .bind(github_user.hireable)
+            .bind(github_user.bio)
+            .bind(github_user.twitter_username)
+            .bind(github_user.public_repos)
+            .bind(github_user.public_gists)
+            .bind(github_user.followers)
+            .bind(github_user.following)
+            .bind(github_user.created_at)
+            .bind(github_user.updated_at)
             .execute(pool)
             .await
             .map_err(|e| {
                 error!("{:?}", e);
-                "Error adding GitHub repository tree item to database"
+                "Error adding GitHub user from database"
             })?
             .last_insert_rowid();
 
-        if item.r#type == "tree" {
-            let item_name = path_parts.last().unwrap();
-            tree_ids.insert(*item_name, item_id);
-        }
+        AddRepoProgress::Owner.send("github", user, repo, 60, 3, 5, app);
 
-        let progress = progress_percentage(i, total_tree_items);
-        AddRepoProgress::InsertTree.send(
-            "github",
-            user,
-            repo,
-            progress,
-            (i + 1) as u64,
-            total_tree_items as u64,
-            app,
-        );
+        add_user_avatar(user_id, "github", &github_user.avatar_url, pool).await?;
+        Ok(())
     }
-
-    Ok(())
-}
-
-async fn check_github_user_exists(user: &str, pool: &SqlitePool) -> AppResult<bool> {
-    let exists_query = "SELECT id FROM github_user WHERE login = ?";
-    let exists = sqlx::query(exists_query)
-        .bind(user)
-        .fetch_optional(pool)
-        .await
-        .map_err(|e| {
+    .await;
+    if let Err(e) = added {
+        if let Err(e) = delete_user(user_id, pool).await {
             error!("{:?}", e);
-            "Error checking if user exists in database"
-        })?;
-    Ok(exists.is_some())
-}
-
-async fn add_github_repo_owner_user(
-    user: &str,
-    repo: &str,
-    api: &GitHubAPI,
-    pool: &SqlitePool,
-    app: &AppHandle,
-) -> AppResult<()> {
-    AddRepoProgress::Owner.send("github", user, repo, 0, 0, 5, app);
-
-    let user_exists = check_github_user_exists(user, pool).await?;
-    if user_exists {
-        AddRepoProgress::Owner.send("github", user, repo, 100, 1, 1, app);
-        return Ok(());
+        }
+        return Err(e);
     }
 
-    let github_user = api.fetch_user(user, pool).await?;
-    AddRepoProgress::Owner.send("github", user, repo, 20, 1, 5, app);
-
-    let user_query =
-        "INSERT INTO user (platform, user, created_at, updated_at) VALUES (?, ?, ?, ?)";
-    let user_id = sqlx::query(user_query)
-        .bind("github")
-        .bind(user)
-        .bind(Utc::now().to_rfc3339())
-        .bind(Utc::now().to_rfc3339())
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            error!("{:?}", e);
-            "Error adding repository data into database"
-        })?
-        .last_insert_rowid();
-
-    AddRepoProgress::Owner.send("github", user, repo, 40, 2, 5, app);
-
-    let query = "
-        INSERT INTO github_user (
-            user_id, login, id, node_id, gravatar_id,
-            type, site_admin, company, blog, location,
-            hireable, bio, twitter_username, public_repos, public_gists,
-            followers, following, created_at, updated_at
-        )
-        VALUES (
-            ?, ?, ?, ?, ?,
-            ?, ?, ?, ?, ?,
-            ?, ?, ?, ?, ?,
-            ?, ?, ?, ?
-        )
-    ";
-    sqlx::query(query)
-        .bind(user_id)
-        .bind(github_user.login)
-        .bind(github_user.id)
-        .bind(github_user.node_id)
-        .bind(github_user.gravatar_id)
-        .bind(github_user.r#type)
-        .bind(github_user.site_admin)
-        .bind(github_user.company)
-        .bind(github_user.blog)
-        .bind(github_user.location)
-        .bind(github_user.hireable)
-        .bind(github_user.bio)
-        .bind(github_user.twitter_username)
-        .bind(github_user.public_repos)
-        .bind(github_user.public_gists)
-        .bind(github_user.followers)
-        .bind(github_user.following)
-        .bind(github_user.created_at)
-        .bind(github_user.updated_at)
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            error!("{:?}", e);
-            "Error adding GitHub user from database"
-        })?
-        .last_insert_rowid();
-
-    AddRepoProgress::Owner.send("github", user, repo, 60, 3, 5, app);
-
-    let (bytes, ext) = download_image(&github_user.avatar_url).await?;
-    let avatar_query = "INSERT INTO user_avatar (user_id, platform, ext, url) VALUES (?, ?, ?, ?)";
-    let avatar_id = sqlx::query(avatar_query)
-        .bind(user_id)
-        .bind("github")
-        .bind(ext)
-        .bind(&github_user.avatar_url)
-        .execute(pool)
-        .await?
-        .last_insert_rowid();
-
-    AddRepoProgress::Owner.send("github", user, repo, 80, 4, 5, app);
-
-    let dir = get_data_dir().join("assets/avatars");
-    ensure_dir(&dir).await?;
-    let path = dir.join(avatar_id.to_string());
-    let mut file = fs::File::create(&path).await.map_err(|e| {
-        error!("{:?}", e);
-        "Error creating avatar image file"
-    })?;
-    file.write_all(&bytes).await?;
-
     AddRepoProgress::Owner.send("github", user, repo, 100, 5, 5, app);
 
     Ok(())
 }
 
+/// Add the repo metadata, returning the default branch.
 pub async fn add_github_repo(
     repo_id: i64,
     user: &str,
@@ -372,7 +297,7 @@ pub async fn add_github_repo(
     api: &GitHubAPI,
     pool: &SqlitePool,
     app: &AppHandle,
-) -> AppResult<()> {
+) -> AppResult<String> {
     AddRepoProgress::Metadata.send("github", user, repo, 0, 0, 2, app);
     let github_repo = api.fetch_repo(user, repo, pool).await?;
     AddRepoProgress::Metadata.send("github", user, repo, 50, 1, 2, app);
@@ -398,8 +323,27 @@ pub async fn add_github_repo(
             ?, ?, ?, ?, ?,
             ?
         )
+        ON CONFLICT (repo_id) DO UPDATE SET
+            id = excluded.id, node_id = excluded.node_id, name = excluded.name,
+            full_name = excluded.full_name, private = excluded.private,
+            description = excluded.description, fork = excluded.fork,
+            created_at = excluded.created_at, updated_at = excluded.updated_at,
+            pushed_at = excluded.pushed_at, homepage = excluded.homepage, size = excluded.size,
+            stargazers_count = excluded.stargazers_count,
+            watchers_count = excluded.watchers_count, language = excluded.language,
+            has_issues = excluded.has_issues, has_projects = excluded.has_projects,
+            has_downloads = excluded.has_downloads, has_wiki = excluded.has_wiki,
+            has_pages = excluded.has_pages, has_discussions = excluded.has_discussions,
+            forks_count = excluded.forks_count, archived = excluded.archived,
+            disabled = excluded.disabled, open_issues_count = excluded.open_issues_count,
+            allow_forking = excluded.allow_forking, is_template = excluded.is_template,
+            web_commit_signoff_required = excluded.web_commit_signoff_required,
+            visibility = excluded.visibility, forks = excluded.forks,
+            open_issues = excluded.open_issues, watchers = excluded.watchers,
+            default_branch = excluded.default_branch, network_count = excluded.network_count,
+            subscribers_count = excluded.subscribers_count
     ";
-    let github_repo_id = sqlx::query(query)
+    sqlx::query(query)
         .bind(repo_id)
         .bind(github_repo.id)
         .bind(github_repo.node_id)
@@ -441,69 +385,20 @@ pub async fn add_github_repo(
         .map_err(|e| {
             error!("{:?}", e);
             "Error adding GitHub repository from database"
-        })?
-        .last_insert_rowid();
+        })?;
 
-    add_github_repo_owner(github_repo_id, github_repo.owner, pool).await?;
-    add_github_repo_org(github_repo_id, github_repo.org, pool).await?;
-    add_github_repo_license(github_repo_id, github_repo.license, pool).await?;
-    add_github_repo_topics(github_repo_id, github_repo.topics, pool).await?;
-    add_github_repo_custom_properties(github_repo_id, github_repo.custom_properties, pool).await?;
+    add_github_repo_owner(repo_id, github_repo.owner, pool).await?;
+    add_github_repo_org(repo_id, github_repo.org, pool).await?;
+    add_github_repo_license(repo_id, github_repo.license, pool).await?;
+    add_github_repo_topics(repo_id, github_repo.topics, pool).await?;
+    add_github_repo_custom_properties(repo_id, github_repo.custom_properties, pool).await?;
     AddRepoProgress::Metadata.send("github", user, repo, 100, 2, 2, app);
 
-    AddRepoProgress::FetchTree.send("github", user, repo, 0, 0, 1, app);
-    let github_repo_tree = api
-        .fetch_repo_tree(user, repo, &github_repo.default_branch, pool)
-        .await?;
-    AddRepoProgress::FetchTree.send("github", user, repo, 100, 1, 1, app);
-
-    add_github_repo_tree(repo_id, user, repo, github_repo_tree, pool, app).await?;
-
-    AddRepoProgress::Readme.send("github", user, repo, 0, 0, 1, app);
-    let filename = sqlx::query_scalar::<_, String>(
-        "SELECT path
-        FROM repo_tree_item
-        WHERE parent_id IS NULL
-        AND (LOWER(path) LIKE '%readme%' OR LOWER(path) LIKE '%readme.md%')",
-    )
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| {
-        error!("{:?}", e);
-        "Error looking for optional repository tree item from database"
-    })?;
-
-    if let Some(filename) = filename {
-        let readme_content = api
-            .fetch_repo_readme(user, repo, &github_repo.default_branch, &filename)
-            .await?;
-
-        let parsed_readme_content =
-            download_readme_assets(&readme_content, repo_id, "github", user, repo, pool, app)
-                .await?;
-
-        let readme_query = "INSERT INTO repo_readme (repo_id, content) VALUES (?, ?)";
-        sqlx::query(readme_query)
-            .bind(repo_id)
-            .bind(parsed_readme_content)
-            .execute(pool)
-            .await
-            .map_err(|e| {
-                error!("{:?}", e);
-                "Error adding repository README to database"
-            })?;
-    } else {
-        AddRepoProgress::Readme.send("github", user, repo, 100, 2, 2, app);
-    }
-
-    add_github_repo_owner_user(user, repo, api, pool, app).await?;
-
-    Ok(())
+    Ok(github_repo.default_branch)
 }
 
 #[derive(FromRow)]
 struct GitHubRepoPreview {
-    id: i64,
     description: String,
     stargazers_count: i32,
     forks: i32,
@@ -523,7 +418,7 @@ pub async fn get_github_repo_preview(
 ) -> AppResult<(DbPlatformRepo, RepoPreviewOwner)> {
     let start = Instant::now();
 
-    let query = "SELECT id, description, stargazers_count, forks, open_issues, visibility FROM github_repo WHERE repo_id = ?";
+    let query = "SELECT description, stargazers_count, forks, open_issues, visibility FROM github_repo WHERE repo_id = ?";
     let github_repo = sqlx::query_as::<_, GitHubRepoPreview>(query)
         .bind(repo_id)
         .fetch_one(pool)
@@ -533,9 +428,9 @@ pub async fn get_github_repo_preview(
             "Error getting GitHub repository from database"
         })?;
 
-    let owner_query = "SELECT id, login FROM github_repo_owner WHERE github_repo_id = ?";
+    let owner_query = "SELECT id, login FROM github_repo_owner WHERE repo_id = ?";
     let owner = sqlx::query_as::<_, DbRepoPreviewOwner>(owner_query)
-        .bind(github_repo.id)
+        .bind(repo_id)
         .fetch_one(pool)
         .await
         .map_err(|e| {
@@ -543,25 +438,24 @@ pub async fn get_github_repo_preview(
             "Error getting GitHub repository owner from database"
         })?;
 
-    let user_id_query = "SELECT user_id FROM github_user WHERE id = ?";
+    let user_id_query = "
+        SELECT g.user_id
+        FROM github_user g
+        JOIN user u ON u.id = g.user_id
+        JOIN repo r ON r.instance_id IS u.instance_id
+        WHERE r.id = ? AND g.id = ?
+    ";
     let user_id = sqlx::query_scalar::<_, i64>(user_id_query)
+        .bind(repo_id)
         .bind(owner.id)
         .fetch_one(pool)
         .await?;
 
-    let avatar_id_query = "SELECT id FROM user_avatar WHERE user_id = ?";
-    let avatar_id = sqlx::query_scalar::<_, i64>(avatar_id_query)
-        .bind(user_id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| {
-            error!("{:?}", e);
-            "Error getting user avatar id from database"
-        })?;
+    let avatar = get_user_avatar_path(user_id, pool)
+        .await?
+        .unwrap_or_default();
 
-    let dir = get_data_dir().join("assets/avatars");
-    let path = dir.join(avatar_id.to_string());
-    let avatar = path.to_str().unwrap().to_string();
+    let pull_requests = pull::count_open_github_pulls(repo_id, pool).await?;
 
     info!(
         "got github repo preview from database in {:?}",
@@ -574,7 +468,7 @@ pub async fn get_github_repo_preview(
             stars: github_repo.stargazers_count,
             forks: github_repo.forks,
             issues: github_repo.open_issues,
-            pull_requests: 0,
+            pull_requests: pull_requests as i32,
             visibility: github_repo.visibility,
         },
         RepoPreviewOwner {
@@ -596,9 +490,9 @@ pub async fn get_github_repo(repo_id: i64, pool: &SqlitePool) -> AppResult<GitHu
             "Error getting github repository from database"
         })?;
 
-    let owner_query = "SELECT * FROM github_repo_owner WHERE github_repo_id = ?";
+    let owner_query = "SELECT * FROM github_repo_owner WHERE repo_id = ?";
     let owner = sqlx::query_as::<_, GitHubRepoOwner>(owner_query)
-        .bind(github_repo.id)
+        .bind(repo_id)
         .fetch_one(pool)
         .await
         .map_err(|e| {
@@ -606,9 +500,9 @@ pub async fn get_github_repo(repo_id: i64, pool: &SqlitePool) -> AppResult<GitHu
             "Error getting github owner from database"
         })?;
 
-    let org_query = "SELECT * FROM github_repo_org WHERE github_repo_id = ?";
+    let org_query = "SELECT * FROM github_repo_org WHERE repo_id = ?";
     let org = sqlx::query_as::<_, GitHubRepoOrg>(org_query)
-        .bind(github_repo.id)
+        .bind(repo_id)
         .fetch_optional(pool)
         .await
         .map_err(|e| {
@@ -616,9 +510,9 @@ pub async fn get_github_repo(repo_id: i64, pool: &SqlitePool) -> AppResult<GitHu
             "Error getting github organization from database"
         })?;
 
-    let topics_query = "SELECT topic FROM github_repo_topic WHERE github_repo_id = ?";
+    let topics_query = "SELECT topic FROM github_repo_topic WHERE repo_id = ?";
     let topics = sqlx::query_scalar::<_, String>(topics_query)
-        .bind(github_repo.id)
+        .bind(repo_id)
         .fetch_all(pool)
         .await
         .map_err(|e| {
@@ -626,9 +520,9 @@ pub async fn get_github_repo(repo_id: i64, pool: &SqlitePool) -> AppResult<GitHu
             "Error getting github topics from database"
         })?;
 
-    let license_query = "SELECT * FROM github_repo_license WHERE github_repo_id = ?";
+    let license_query = "SELECT * FROM github_repo_license WHERE repo_id = ?";
     let license = sqlx::query_as::<_, GitHubRepoLicense>(license_query)
-        .bind(github_repo.id)
+        .bind(repo_id)
         .fetch_one(pool)
         .await
         .map_err(|e| {
@@ -636,10 +530,9 @@ pub async fn get_github_repo(repo_id: i64, pool: &SqlitePool) -> AppResult<GitHu
             "Error getting github license from database"
         })?;
 
-    let custom_properties_query =
-        "SELECT * FROM github_repo_custom_property WHERE github_repo_id = ?";
+    let custom_properties_query = "SELECT * FROM github_repo_custom_property WHERE repo_id = ?";
     let custom_properties = sqlx::query_as::<_, GitHubRepoCustomProperty>(custom_properties_query)
-        .bind(github_repo.id)
+        .bind(repo_id)
         .fetch_all(pool)
         .await
         .map_err(|e| {
@@ -670,3 +563,755 @@ pub async fn get_github_user(user_id: i64, pool: &SqlitePool) -> AppResult<GitHu
 
     Ok(GitHubUserData { user: github_user })
 }
+
+/// Fetch the current occurrences of a watched event. Lists the endpoints sort newest (or
+/// most recently updated) first only include their first page. Branches and tags are
+/// sorted by name and contributors by their number of contributions, so new ones can be
+/// on any page and every page is fetched.
+async fn fetch_github_watched_items(
+    api: &GitHubAPI,
+    user: &str,
+    repo: &str,
+    event: RepoEvent,
+    pool: &SqlitePool,
+) -> AppResult<Option<Vec<WatchedItem>>> {
+    let web_url = format!("{}/{user}/{repo}", api.web_url());
+    let items = match event {
+        RepoEvent::Branch => api
+            .fetch_repo_list_all::<GitHubApiBranch>(user, repo, "branches", pool)
+            .await?
+            .into_iter()
+            .map(|branch| WatchedItem {
+                url: Some(format!("{web_url}/tree/{}", branch.name)),
+                title: branch.name.clone(),
+                key: branch.name,
+                version: branch.commit.sha,
+                occurred_at: None,
+            })
+            .collect(),
+        RepoEvent::Commit => api
+            .fetch_repo_list::<GitHubApiCommit>(user, repo, "commits", pool)
+            .await?
+            .into_iter()
+            .map(|commit| WatchedItem {
+                title: commit
+                    .commit
+                    .message
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                version: commit.sha.clone(),
+                key: commit.sha,
+                url: Some(commit.html_url),
+                occurred_at: commit.commit.author.map(|author| author.date),
+            })
+            .collect(),
+        RepoEvent::Contributor => api
+            .fetch_repo_list_all::<GitHubApiContributor>(user, repo, "contributors", pool)
+            .await?
+            .into_iter()
+            .filter_map(|contributor| {
+                let login = contributor.login?;
+                Some(WatchedItem {
+                    title: login.clone(),
+                    key: login,
+                    // Only new contributors are of interest, not their contribution count.
+                    version: String::new(),
+                    url: contributor.html_url,
+                    occurred_at: None,
+                })
+            })
+            .collect(),
+        RepoEvent::Fork => api
+            .fetch_repo_list::<GitHubApiFork>(user, repo, "forks?sort=newest", pool)
+            .await?
+            .into_iter()
+            .map(|fork| WatchedItem {
+                key: fork.id.to_string(),
+                version: String::new(),
+                title: fork.full_name,
+                url: Some(fork.html_url),
+                occurred_at: Some(fork.created_at),
+            })
+            .collect(),
+        RepoEvent::Issue => api
+            .fetch_repo_list::<GitHubApiIssue>(
+                user,
+                repo,
+                "issues?state=all&sort=updated&direction=desc",
+                pool,
+            )
+            .await?
+            .into_iter()
+            .filter(|issue| issue.pull_request.is_none())
+            .map(|issue| WatchedItem {
+                key: issue.number.to_string(),
+                title: format!("#{} {}", issue.number, issue.title),
+                version: issue.state,
+                url: Some(issue.html_url),
+                occurred_at: Some(issue.updated_at),
+            })
+            .collect(),
+        RepoEvent::PullRequest => api
+            .fetch_repo_list::<GitHubApiPull>(
+                user,
+                repo,
+                "pulls?state=all&sort=updated&direction=desc",
+                pool,
+            )
+            .await?
+            .into_iter()
+            .map(|pull| WatchedItem {
+                key: pull.number.to_string(),
+                title: format!("#{} {}", pull.number, pull.title),
+                version: match pull.merged_at {
+                    Some(_) => "merged".to_string(),
+                    None => pull.state,
+                },
+                url: Some(pull.html_url),
+                occurred_at: Some(pull.updated_at),
+            })
+            .collect(),
+        RepoEvent::Release => api
+            .fetch_repo_list::<GitHubApiRelease>(user, repo, "releases", pool)
+            .await?
+            .into_iter()
+            .filter(|release| !release.draft)
+            .map(|release| WatchedItem {
+                key: release.id.to_string(),
+                title: release.name.unwrap_or(release.tag_name.clone()),
+                version: release.tag_name,
+                url: Some(release.html_url),
+                occurred_at: release.published_at,
+            })
+            .collect(),
+        RepoEvent::Star => {
+            // Stargazers are listed oldest first, the count is diffed instead.
+            let github_repo = api.fetch_repo(user, repo, pool).await?;
+            vec![WatchedItem {
+                key: "stargazers".to_string(),
+                version: github_repo.stargazers_count.to_string(),
+                title: format!("{} stars", github_repo.stargazers_count),
+                url: Some(format!("{web_url}/stargazers")),
+                occurred_at: None,
+            }]
+        }
+        RepoEvent::Tag => api
+            .fetch_repo_list_all::<GitHubApiTag>(user, repo, "tags", pool)
+            .await?
+            .into_iter()
+            .map(|tag| WatchedItem {
+                url: Some(format!("{web_url}/releases/tag/{}", tag.name)),
+                title: tag.name.clone(),
+                key: tag.name,
+                version: tag.commit.sha,
+                occurred_at: None,
+            })
+            .collect(),
+        // Discussions are only available through the GraphQL API.
+        RepoEvent::Discussion => {
+            discussion::fetch_github_discussion_items(api, user, repo, pool).await?
+        }
+    };
+    Ok(Some(items))
+}
+
+const TAG_REFS_QUERY: &str = "
+    query($owner: String!, $name: String!, $after: String) {
+        repository(owner: $owner, name: $name) {
+            refs(refPrefix: \"refs/tags/\", first: 100, after: $after) {
+                nodes {
+                    name
+                    target {
+                        __typename oid
+                        ... on Tag { message tagger { name date } target { oid } }
+                    }
+                }
+                pageInfo { hasNextPage endCursor }
+            }
+        }
+    }
+";
+
+/// Every tag of a repo with the tagger and message of annotated ones.
+async fn fetch_github_tag_refs(
+    ctx: &RepoContext,
+    api: &GitHubAPI,
+    pool: &SqlitePool,
+) -> AppResult<Vec<RepoRef>> {
+    let mut variables = Map::new();
+    variables.insert("owner".to_string(), json!(ctx.user));
+    variables.insert("name".to_string(), json!(ctx.repo));
+
+    let tags = api
+        .fetch_graphql_all(
+            TAG_REFS_QUERY,
+            variables,
+            |data: GitHubApiGraphQLRepository<GitHubApiRefs<GitHubApiTagRef>>| {
+                data.repository.map(|r| r.refs)
+            },
+            pool,
+        )
+        .await?;
+
+    let tags = tags
+        .into_iter()
+        .map(|tag| {
+            let target = tag.target;
+            if target.typename != "Tag" {
+                return RepoRef {
+                    annotated: Some(false),
+                    ..RepoRef::tag(tag.name, target.oid)
+                };
+            }
+
+            let sha = target.target.map(|t| t.oid).unwrap_or(target.oid);
+            let (tagger, tagged_at) = target.tagger.map(|t| (t.name, t.date)).unwrap_or_default();
+            RepoRef {
+                annotated: Some(true),
+                tagger,
+                tagged_at,
+                message: target.message,
+                ..RepoRef::tag(tag.name, sha)
+            }
+        })
+        .collect();
+    Ok(tags)
+}
+
+/// How long a walk that isn't resumed is kept, before another walk of the repo removes it.
+const ABANDONED_TREE_WALK_AGE: chrono::Duration = chrono::Duration::days(1);
+
+/// Walk a tree one directory at a time, for trees GitHub truncates when fetched
+/// recursively. The directories left to fetch are stored as a cursor, so a walk that
+/// fails (e.g. on the rate limit) resumes where it stopped for the same tree.
+///
+/// Returns the items and whether a directory was itself truncated, in which case some of
+/// its entries are missing.
+async fn walk_github_repo_tree(
+    ctx: &RepoContext,
+    sha: &str,
+    api: &GitHubAPI,
+    pool: &SqlitePool,
+) -> AppResult<(Vec<NewRepoTreeItem>, bool)> {
+    let start = Instant::now();
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("{:?}", e);
+        "Error adding GitHub tree walk to database"
+    })?;
+    // Walks of other trees may still be resumed, only those left for long are removed.
+    sqlx::query("DELETE FROM github_tree_walk WHERE repo_id = ? AND sha != ? AND started_at < ?")
+        .bind(ctx.repo_id)
+        .bind(sha)
+        .bind((Utc::now() - ABANDONED_TREE_WALK_AGE).to_rfc3339())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing GitHub tree walk from database"
+        })?;
+    let walk_query =
+        "INSERT OR IGNORE INTO github_tree_walk (repo_id, sha, started_at) VALUES (?, ?, ?)";
+    let added = sqlx::query(walk_query)
+        .bind(ctx.repo_id)
+        .bind(sha)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding GitHub tree walk to database"
+        })?
+        .rows_affected()
+        > 0;
+    if added {
+        let dir_query = "
+            INSERT INTO github_tree_walk_dir (repo_id, root_sha, path, sha)
+            VALUES (?, ?, '', ?)
+        ";
+        sqlx::query(dir_query)
+            .bind(ctx.repo_id)
+            .bind(sha)
+            .bind(sha)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding GitHub tree walk to database"
+            })?;
+    } else {
+        info!("resuming github tree walk of {}/{}", ctx.user, ctx.repo);
+    }
+    tx.commit().await.map_err(|e| {
+        error!("{:?}", e);
+        "Error adding GitHub tree walk to database"
+    })?;
+
+    loop {
+        let dir_query = "
+            SELECT path, sha
+            FROM github_tree_walk_dir
+            WHERE repo_id = ? AND root_sha = ?
+            LIMIT 1
+        ";
+        let dir = sqlx::query_as::<_, (String, String)>(dir_query)
+            .bind(ctx.repo_id)
+            .bind(sha)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error getting GitHub tree walk directory from database"
+            })?;
+        let Some((dir_path, dir_sha)) = dir else {
+            break;
+        };
+
+        let tree = api
+            .fetch_repo_tree(&ctx.user, &ctx.repo, &dir_sha, false, pool)
+            .await?;
+
+        // The items of a directory and its removal from the cursor are stored together.
+        let mut tx = pool.begin().await.map_err(|e| {
+            error!("{:?}", e);
+            "Error adding GitHub tree walk items to database"
+        })?;
+        // A directory is already fetched on its own, the entries GitHub leaves out of it
+        // can't be fetched any other way.
+        if tree.truncated {
+            warn!(
+                "github tree of {}/{} at '{}' is truncated",
+                ctx.user, ctx.repo, dir_path
+            );
+            let truncated_query =
+                "UPDATE github_tree_walk SET truncated = 1 WHERE repo_id = ? AND sha = ?";
+            sqlx::query(truncated_query)
+                .bind(ctx.repo_id)
+                .bind(sha)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    error!("{:?}", e);
+                    "Error updating GitHub tree walk in database"
+                })?;
+        }
+        for item in tree.tree {
+            let path = if dir_path.is_empty() {
+                item.path
+            } else {
+                format!("{dir_path}/{}", item.path)
+            };
+
+            if item.r#type == "tree" {
+                let dir_query = "
+                    INSERT OR REPLACE INTO github_tree_walk_dir (repo_id, root_sha, path, sha)
+                    VALUES (?, ?, ?, ?)
+                ";
+                sqlx::query(dir_query)
+                    .bind(ctx.repo_id)
+                    .bind(sha)
+                    .bind(&path)
+                    .bind(&item.sha)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| {
+                        error!("{:?}", e);
+                        "Error adding GitHub tree walk directory to database"
+                    })?;
+            }
+
+            let item_query = "
+                INSERT OR REPLACE INTO github_tree_walk_item (
+                    repo_id, root_sha, path, mode, type, sha, size
+                )
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+            ";
+            sqlx::query(item_query)
+                .bind(ctx.repo_id)
+                .bind(sha)
+                .bind(&path)
+                .bind(item.mode)
+                .bind(item.r#type)
+                .bind(item.sha)
+                .bind(item.size)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| {
+                    error!("{:?}", e);
+                    "Error adding GitHub tree walk item to database"
+                })?;
+        }
+        let dir_query =
+            "DELETE FROM github_tree_walk_dir WHERE repo_id = ? AND root_sha = ? AND path = ?";
+        sqlx::query(dir_query)
+            .bind(ctx.repo_id)
+            .bind(sha)
+            .bind(&dir_path)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error removing GitHub tree walk directory from database"
+            })?;
+        tx.commit().await.map_err(|e| {
+            error!("{:?}", e);
+            "Error adding GitHub tree walk items to database"
+        })?;
+    }
+
+    let items_query = "
+        SELECT path, mode, type, sha, size
+        FROM github_tree_walk_item
+        WHERE repo_id = ? AND root_sha = ?
+        ORDER BY path
+    ";
+    let items = sqlx::query_as::<_, GitHubTreeWalkItem>(items_query)
+        .bind(ctx.repo_id)
+        .bind(sha)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting GitHub tree walk items from database"
+        })?;
+
+    let truncated_query = "
+        DELETE FROM github_tree_walk
+        WHERE repo_id = ? AND sha = ?
+        RETURNING truncated
+    ";
+    let truncated = sqlx::query_scalar::<_, bool>(truncated_query)
+        .bind(ctx.repo_id)
+        .bind(sha)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing GitHub tree walk from database"
+        })?
+        .unwrap_or_default();
+
+    info!(
+        "walking github tree of {}/{} ({} items) took {:?}",
+        ctx.user,
+        ctx.repo,
+        items.len(),
+        start.elapsed()
+    );
+
+    let items = items.into_iter().map(NewRepoTreeItem::from).collect();
+    Ok((items, truncated))
+}
+
+#[async_trait]
+impl PlatformProvider for GitHubAPI {
+    fn platform(&self) -> Platform {
+        Platform::GitHub
+    }
+
+    fn set_token(&self, token: Option<String>) {
+        GitHubAPI::set_token(self, token);
+    }
+
+    async fn check_core_rate_limit(&self, pool: &SqlitePool) -> AppResult<()> {
+        self.check_rate_limit("core", pool).await
+    }
+
+    async fn add_repo(
+        &self,
+        ctx: &RepoContext,
+        pool: &SqlitePool,
+        app: &AppHandle,
+    ) -> AppResult<String> {
+        add_github_repo(ctx.repo_id, &ctx.user, &ctx.repo, self, pool, app).await
+    }
+
+    async fn fetch_tree(
+        &self,
+        ctx: &RepoContext,
+        rev: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<FetchedTree> {
+        let tree = self
+            .fetch_repo_tree(&ctx.user, &ctx.repo, rev, true, pool)
+            .await?;
+
+        let (items, truncated) = if tree.truncated {
+            walk_github_repo_tree(ctx, &tree.sha, self, pool).await?
+        } else {
+            let items = tree.tree.into_iter().map(NewRepoTreeItem::from).collect();
+            (items, false)
+        };
+
+        Ok(FetchedTree {
+            sha: tree.sha,
+            rev: rev.to_string(),
+            truncated,
+            items,
+        })
+    }
+
+    async fn fetch_refs(&self, ctx: &RepoContext, pool: &SqlitePool) -> AppResult<Vec<RepoRef>> {
+        let branches: Vec<GitHubApiBranch> = self
+            .fetch_repo_list_all(&ctx.user, &ctx.repo, "branches", pool)
+            .await?;
+        let branches = branches
+            .into_iter()
+            .map(|branch| RepoRef::branch(branch.name, branch.commit.sha, branch.protected));
+
+        // The REST API doesn't tell annotated tags apart, GraphQL does but needs a token.
+        let tags = if self.token().is_some() {
+            fetch_github_tag_refs(ctx, self, pool).await?
+        } else {
+            let tags: Vec<GitHubApiTag> = self
+                .fetch_repo_list_all(&ctx.user, &ctx.repo, "tags", pool)
+                .await?;
+            tags.into_iter()
+                .map(|tag| RepoRef::tag(tag.name, tag.commit.sha))
+                .collect()
+        };
+
+        Ok(branches.chain(tags).collect())
+    }
+
+    async fn fetch_file(
+        &self,
+        ctx: &RepoContext,
+        rev: &str,
+        path: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<String> {
+        self.fetch_repo_readme(&ctx.user, &ctx.repo, rev, path, pool)
+            .await
+    }
+
+    async fn fetch_blob(
+        &self,
+        ctx: &RepoContext,
+        _rev: &str,
+        _path: &str,
+        sha: Option<&str>,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<u8>> {
+        let sha = sha.ok_or("Blob SHA is unknown")?;
+        self.fetch_repo_blob(&ctx.user, &ctx.repo, sha, pool).await
+    }
+
+    async fn add_owner(
+        &self,
+        ctx: &RepoContext,
+        pool: &SqlitePool,
+        app: &AppHandle,
+    ) -> AppResult<()> {
+        add_github_repo_owner_user(ctx.instance_id, &ctx.user, &ctx.repo, self, pool, app).await
+    }
+
+    fn clone_remote(&self, ctx: &RepoContext) -> CloneRemote {
+        CloneRemote {
+            url: format!("{}/{}/{}.git", self.web_url(), ctx.user, ctx.repo),
+            credentials: self.token().map(|t| ("x-access-token".to_string(), t)),
+        }
+    }
+
+    async fn fetch_watched_items(
+        &self,
+        ctx: &RepoContext,
+        event: RepoEvent,
+        pool: &SqlitePool,
+    ) -> AppResult<Option<Vec<WatchedItem>>> {
+        fetch_github_watched_items(self, &ctx.user, &ctx.repo, event, pool).await
+    }
+
+    async fn archive_issues(&self, ctx: &RepoContext, pool: &SqlitePool) -> AppResult<()> {
+        issue::archive_github_issues(ctx, self, pool).await
+    }
+
+    async fn archive_pulls(&self, ctx: &RepoContext, pool: &SqlitePool) -> AppResult<()> {
+        pull::archive_github_pulls(ctx, self, pool).await
+    }
+
+    async fn archive_discussions(&self, ctx: &RepoContext, pool: &SqlitePool) -> AppResult<()> {
+        discussion::archive_github_discussions(ctx, self, pool).await
+    }
+
+    async fn archive_releases(&self, ctx: &RepoContext, pool: &SqlitePool) -> AppResult<()> {
+        release::archive_github_releases(ctx, self, pool).await
+    }
+
+    async fn get_repo(&self, repo_id: i64, pool: &SqlitePool) -> AppResult<PlatformRepoData> {
+        Ok(PlatformRepoData::GitHub(
+            get_github_repo(repo_id, pool).await?,
+        ))
+    }
+
+    async fn get_repo_stats(
+        &self,
+        repo_id: i64,
+        pool: &SqlitePool,
+    ) -> AppResult<Option<RepoStats>> {
+        let query = "
+            SELECT
+                g.stargazers_count AS stars, g.forks_count AS forks,
+                g.subscribers_count AS watchers, g.open_issues_count AS open_issues,
+                g.default_branch, g.archived, g.visibility, l.spdx_id AS license,
+                (
+                    SELECT group_concat(topic, ',') FROM github_repo_topic
+                    WHERE repo_id = g.repo_id
+                ) AS topics
+            FROM github_repo g
+            LEFT JOIN github_repo_license l ON l.repo_id = g.repo_id
+            WHERE g.repo_id = ?
+        ";
+        get_repo_stats(query, repo_id, pool).await
+    }
+
+    async fn get_repo_preview(
+        &self,
+        repo_id: i64,
+        pool: &SqlitePool,
+    ) -> AppResult<(DbPlatformRepo, RepoPreviewOwner)> {
+        get_github_repo_preview(repo_id, pool).await
+    }
+
+    async fn get_user(&self, user_id: i64, pool: &SqlitePool) -> AppResult<PlatformUserData> {
+        Ok(PlatformUserData::GitHub(
+            get_github_user(user_id, pool).await?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::utils::testing::memory_pool;
+
+    async fn mount_tree(server: &MockServer, sha: &str, truncated: bool, items: serde_json::Value) {
+        Mock::given(method("GET"))
+            .and(path(format!("/repos/user/repo/git/trees/{sha}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "sha": sha,
+                "tree": items,
+                "truncated": truncated,
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    fn tree_item(path: &str, r#type: &str, sha: &str) -> serde_json::Value {
+        let mode = if r#type == "tree" { "040000" } else { "100644" };
+        json!({ "path": path, "mode": mode, "type": r#type, "sha": sha, "size": 1 })
+    }
+
+    async fn add_test_repo(pool: &SqlitePool) -> RepoContext {
+        let query = "
+            INSERT INTO repo (platform, user, repo, created_at, updated_at)
+            VALUES ('github', 'user', 'repo', '', '')
+        ";
+        let repo_id = sqlx::query(query)
+            .execute(pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        RepoContext {
+            repo_id,
+            instance_id: None,
+            user: "user".to_string(),
+            repo: "repo".to_string(),
+        }
+    }
+
+    fn paths(items: &[NewRepoTreeItem]) -> Vec<&str> {
+        items.iter().map(|i| i.path.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn truncated_directory_marks_the_walked_tree_truncated() {
+        let server = MockServer::start().await;
+        let root_items = json!([
+            tree_item("src", "tree", "t1"),
+            tree_item("a.txt", "blob", "b1")
+        ]);
+        mount_tree(&server, "root", false, root_items).await;
+        mount_tree(
+            &server,
+            "t1",
+            true,
+            json!([tree_item("main.rs", "blob", "b2")]),
+        )
+        .await;
+
+        let pool = memory_pool().await;
+        let ctx = add_test_repo(&pool).await;
+        let api = GitHubAPI::new(&server.uri(), &server.uri(), &server.uri(), None);
+        let (items, truncated) = walk_github_repo_tree(&ctx, "root", &api, &pool)
+            .await
+            .unwrap();
+
+        assert!(truncated);
+        assert_eq!(paths(&items), ["a.txt", "src", "src/main.rs"]);
+    }
+
+    #[tokio::test]
+    async fn walks_of_other_trees_are_resumed() {
+        let server = MockServer::start().await;
+        let first_items = json!([
+            tree_item("src", "tree", "t1"),
+            tree_item("a.txt", "blob", "b1")
+        ]);
+        mount_tree(&server, "first", false, first_items).await;
+        Mock::given(method("GET"))
+            .and(path("/repos/user/repo/git/trees/t1"))
+            .respond_with(ResponseTemplate::new(404))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        mount_tree(
+            &server,
+            "t1",
+            false,
+            json!([tree_item("main.rs", "blob", "b2")]),
+        )
+        .await;
+        mount_tree(
+            &server,
+            "second",
+            false,
+            json!([tree_item("b.txt", "blob", "b3")]),
+        )
+        .await;
+
+        let pool = memory_pool().await;
+        let ctx = add_test_repo(&pool).await;
+        let api = GitHubAPI::new(&server.uri(), &server.uri(), &server.uri(), None);
+        assert!(walk_github_repo_tree(&ctx, "first", &api, &pool)
+            .await
+            .is_err());
+
+        let (second_items, _) = walk_github_repo_tree(&ctx, "second", &api, &pool)
+            .await
+            .unwrap();
+        assert_eq!(paths(&second_items), ["b.txt"]);
+
+        // The root of the first tree isn't fetched again.
+        let (first_items, truncated) = walk_github_repo_tree(&ctx, "first", &api, &pool)
+            .await
+            .unwrap();
+        assert!(!truncated);
+        assert_eq!(paths(&first_items), ["a.txt", "src", "src/main.rs"]);
+
+        let walks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM github_tree_walk")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(walks, 0);
+    }
+}
@@ -1,3 +1,8 @@
+use std::sync::RwLock;
+
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
 use sqlx::SqlitePool;
 use tauri::http::HeaderMap;
 use tokio::time::Instant;
@@ -5,42 +10,138 @@ use tracing::{error, info};
 
 use crate::{
     error::{AppError, AppResult},
-    platforms::github::api_models::{GitHubApiRepoTree, GitHubApiUser},
+    platforms::{
+        github::{
+            api_models::{
+                GitHubApiConnection, GitHubApiGraphQLResponse, GitHubApiRepoTree, GitHubApiUser,
+            },
+            models::GitHubTokenInfo,
+        },
+        Platform,
+    },
     utils::{
         data::{parse_body, parse_header, parse_header_num},
-        rate_limit::{check_rate_limit, update_rate_limit},
+        rate_limit::{check_rate_limit, send_with_retry, update_rate_limit, RateLimitKey},
     },
 };
 
 use super::api_models::GitHubApiRepo;
 
+/// Client for the GitHub REST and GraphQL APIs of github.com or a GitHub Enterprise
+/// Server host.
 pub struct GitHubAPI {
     client: reqwest::Client,
-    base_url: &'static str,
-    base_content_url: &'static str,
+    /// The website URL, repos are cloned from it.
+    web_url: String,
+    base_url: String,
+    base_content_url: String,
+    /// Personal access token sent with every request, if configured.
+    token: RwLock<Option<String>>,
 }
 
 impl GitHubAPI {
-    pub fn init() -> Self {
+    pub const DEFAULT_WEB_URL: &'static str = "https://github.com";
+    pub const DEFAULT_BASE_URL: &'static str = "https://api.github.com";
+    pub const DEFAULT_CONTENT_URL: &'static str = "https://raw.githubusercontent.com";
+
+    pub fn init(token: Option<String>) -> Self {
+        Self::new(
+            Self::DEFAULT_WEB_URL,
+            Self::DEFAULT_BASE_URL,
+            Self::DEFAULT_CONTENT_URL,
+            token,
+        )
+    }
+
+    /// A GitHub Enterprise Server host serves the API at `{host}/api/v3` and raw
+    /// contents at `{host}/raw`.
+    pub fn new(
+        web_url: &str,
+        base_url: &str,
+        base_content_url: &str,
+        token: Option<String>,
+    ) -> Self {
+        let base_url = base_url.trim_end_matches('/').to_string();
         Self {
             client: reqwest::Client::new(),
-            base_url: "https://api.github.com",
-            base_content_url: "https://raw.githubusercontent.com",
+            web_url: web_url.trim_end_matches('/').to_string(),
+            base_url,
+            base_content_url: base_content_url.trim_end_matches('/').to_string(),
+            token: RwLock::new(token.filter(|t| !t.is_empty())),
         }
     }
 
+    pub fn web_url(&self) -> &str {
+        &self.web_url
+    }
+
+    /// Replace the token used for subsequent requests.
+    pub fn set_token(&self, token: Option<String>) {
+        *self.token.write().unwrap() = token.filter(|t| !t.is_empty());
+    }
+
+    pub fn token(&self) -> Option<String> {
+        self.token.read().unwrap().clone()
+    }
+
+    /// Build a GET request with the common headers and the authorization token applied.
+    fn get(&self, url: &str, token: Option<&str>) -> RequestBuilder {
+        let req = self.client.get(url).header("User-Agent", "Git Chest");
+        match token.map(|t| t.to_string()).or_else(|| self.token()) {
+            Some(token) => req.header("Authorization", format!("Bearer {token}")),
+            None => req,
+        }
+    }
+
+    /// Build a POST request with the common headers and the authorization token applied.
+    fn post(&self, url: &str) -> RequestBuilder {
+        let req = self.client.post(url).header("User-Agent", "Git Chest");
+        match self.token() {
+            Some(token) => req.header("Authorization", format!("Bearer {token}")),
+            None => req,
+        }
+    }
+
+    /// The GraphQL endpoint, `{host}/api/graphql` on GitHub Enterprise Server.
+    fn graphql_url(&self) -> String {
+        match self.base_url.strip_suffix("/v3") {
+            Some(api_url) => format!("{api_url}/graphql"),
+            None => format!("{}/graphql", self.base_url),
+        }
+    }
+
+    /// Rate limiting is optional on GitHub Enterprise Server, without it no headers are sent.
     pub async fn update_rate_limit(&self, headers: &HeaderMap, pool: &SqlitePool) -> AppResult<()> {
-        let max = parse_header_num(headers, "X-RateLimit-Limit").unwrap_or(0);
+        let Ok(max) = parse_header_num(headers, "X-RateLimit-Limit") else {
+            return Ok(());
+        };
         let remaining = parse_header_num(headers, "X-RateLimit-Remaining").unwrap_or(0);
         let used = parse_header_num(headers, "X-RateLimit-Used").unwrap_or(0);
         let reset_at = parse_header_num(headers, "X-RateLimit-Reset").unwrap_or(0);
-        let resource = parse_header(headers, "X-RateLimit-Resource").unwrap();
-        update_rate_limit("github", max, remaining, used, reset_at, resource, pool).await?;
+        let resource = parse_header(headers, "X-RateLimit-Resource").unwrap_or("core");
+        update_rate_limit(
+            &self.rate_limit_key(resource),
+            max,
+            remaining,
+            used,
+            reset_at,
+            pool,
+        )
+        .await?;
         Ok(())
     }
 
-    async fn check_rate_limit(&self, resource: &str, pool: &SqlitePool) -> AppResult<()> {
-        check_rate_limit("github", resource, pool).await
+    pub fn rate_limit_key(&self, resource: &str) -> RateLimitKey {
+        RateLimitKey::new(
+            Platform::GitHub,
+            &self.base_url,
+            self.token().as_deref(),
+            resource,
+        )
+    }
+
+    pub async fn check_rate_limit(&self, resource: &str, pool: &SqlitePool) -> AppResult<()> {
+        check_rate_limit(&self.rate_limit_key(resource), pool).await
     }
 
     pub async fn fetch_repo(
@@ -49,15 +150,13 @@ impl GitHubAPI {
         repo: &str,
         pool: &SqlitePool,
     ) -> AppResult<GitHubApiRepo> {
-        self.check_rate_limit("core", pool).await?;
         let start = Instant::now();
 
-        let res = self
-            .client
-            .get(format!("{}/repos/{user}/{repo}", self.base_url))
-            .header("Accept", "application/vnd.github+json")
-            .header("User-Agent", "Git Chest")
-            .send()
+        let req = || {
+            self.get(&format!("{}/repos/{user}/{repo}", self.base_url), None)
+                .header("Accept", "application/vnd.github+json")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key("core"), pool)
             .await
             .map_err(|e| {
                 error!("{:?}", e);
@@ -80,25 +179,30 @@ impl GitHubAPI {
         Ok(json_body)
     }
 
+    /// Fetch the tree of a branch, commit or tree SHA. Recursive trees are truncated by
+    /// GitHub past 100,000 items or 7 MB.
     pub async fn fetch_repo_tree(
         &self,
         user: &str,
         repo: &str,
-        branch: &str,
+        tree: &str,
+        recursive: bool,
         pool: &SqlitePool,
     ) -> AppResult<GitHubApiRepoTree> {
-        self.check_rate_limit("core", pool).await?;
         let start = Instant::now();
 
-        let res = self
-            .client
-            .get(format!(
-                "{}/repos/{user}/{repo}/git/trees/{branch}?recursive=true",
-                self.base_url,
-            ))
+        let query = if recursive { "?recursive=true" } else { "" };
+        let req = || {
+            self.get(
+                &format!(
+                    "{}/repos/{user}/{repo}/git/trees/{tree}{query}",
+                    self.base_url,
+                ),
+                None,
+            )
             .header("Accept", "application/vnd.github+json")
-            .header("User-Agent", "Git Chest")
-            .send()
+        };
+        let res = send_with_retry(req, &self.rate_limit_key("core"), pool)
             .await
             .map_err(|e| {
                 error!("{:?}", e);
@@ -122,25 +226,146 @@ impl GitHubAPI {
         Ok(data)
     }
 
+    /// Fetch the raw content of a blob by its SHA.
+    pub async fn fetch_repo_blob(
+        &self,
+        user: &str,
+        repo: &str,
+        sha: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<u8>> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(
+                &format!("{}/repos/{user}/{repo}/git/blobs/{sha}", self.base_url),
+                None,
+            )
+            .header("Accept", "application/vnd.github.raw+json")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key("core"), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching repository blob from GitHub API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        self.update_rate_limit(res.headers(), pool).await?;
+
+        let data = res.bytes().await.map_err(|e| {
+            error!("{:?}", e);
+            "Error parsing repository blob from GitHub API"
+        })?;
+
+        info!("fetching github repo blob took {:?}", start.elapsed());
+
+        Ok(data.to_vec())
+    }
+
+    /// Fetch the `.diff` text of a pull request.
+    pub async fn fetch_pull_diff(
+        &self,
+        user: &str,
+        repo: &str,
+        number: i64,
+        pool: &SqlitePool,
+    ) -> AppResult<String> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(
+                &format!("{}/repos/{user}/{repo}/pulls/{number}", self.base_url),
+                None,
+            )
+            .header("Accept", "application/vnd.github.diff")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key("core"), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching pull request diff from GitHub API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        self.update_rate_limit(res.headers(), pool).await?;
+
+        let data = res.text().await.map_err(|e| {
+            error!("{:?}", e);
+            "Error parsing pull request diff from GitHub API"
+        })?;
+
+        info!(
+            "fetching github pull request diff took {:?}",
+            start.elapsed()
+        );
+
+        Ok(data)
+    }
+
+    /// Fetch the binary of a release asset. The response is returned so the body can be
+    /// streamed, assets can be large.
+    pub async fn fetch_release_asset(
+        &self,
+        user: &str,
+        repo: &str,
+        asset_id: i64,
+        pool: &SqlitePool,
+    ) -> AppResult<Response> {
+        let req = || {
+            self.get(
+                &format!(
+                    "{}/repos/{user}/{repo}/releases/assets/{asset_id}",
+                    self.base_url
+                ),
+                None,
+            )
+            .header("Accept", "application/octet-stream")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key("core"), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching release asset from GitHub API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        Ok(res)
+    }
+
     pub async fn fetch_repo_readme(
         &self,
         user: &str,
         repo: &str,
         branch: &str,
         filename: &str,
+        pool: &SqlitePool,
     ) -> AppResult<String> {
         let start = Instant::now();
 
-        let res = self
-            .client
-            .get(format!(
-                "{}/{user}/{repo}/{branch}/{filename}",
-                self.base_content_url,
-            ))
-            .header("Host", "raw.githubusercontent.com")
+        let req = || {
+            self.get(
+                &format!(
+                    "{}/{user}/{repo}/{branch}/{filename}",
+                    self.base_content_url,
+                ),
+                None,
+            )
             .header("Accept", "*/*")
-            .header("User-Agent", "Git Chest")
-            .send()
+        };
+        let res = send_with_retry(req, &self.rate_limit_key("raw"), pool)
             .await
             .map_err(|e| {
                 error!("{:?}", e);
@@ -163,16 +388,169 @@ impl GitHubAPI {
         Ok(data)
     }
 
-    pub async fn fetch_user(&self, user: &str, pool: &SqlitePool) -> AppResult<GitHubApiUser> {
-        self.check_rate_limit("core", pool).await?;
+    /// Fetch the first page (up to 100 items) of a list endpoint of a repo, e.g.
+    /// `branches` or `issues?state=all`.
+    pub async fn fetch_repo_list<T: DeserializeOwned>(
+        &self,
+        user: &str,
+        repo: &str,
+        path: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<T>> {
+        self.fetch_repo_list_page(user, repo, path, 1, pool).await
+    }
+
+    /// Fetch every page of a list endpoint of a repo.
+    pub async fn fetch_repo_list_all<T: DeserializeOwned>(
+        &self,
+        user: &str,
+        repo: &str,
+        path: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<T>> {
+        let mut items = Vec::new();
+        let mut page = 1;
+        loop {
+            let mut page_items = self
+                .fetch_repo_list_page(user, repo, path, page, pool)
+                .await?;
+            let is_last = page_items.len() < 100;
+            items.append(&mut page_items);
+            if is_last {
+                break;
+            }
+            page += 1;
+        }
+        Ok(items)
+    }
+
+    /// Fetch one page (up to 100 items) of a list endpoint of a repo, starting at 1.
+    pub async fn fetch_repo_list_page<T: DeserializeOwned>(
+        &self,
+        user: &str,
+        repo: &str,
+        path: &str,
+        page: u32,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<T>> {
         let start = Instant::now();
 
-        let res = self
-            .client
-            .get(format!("{}/users/{user}", self.base_url))
+        let separator = if path.contains('?') { '&' } else { '?' };
+        let req = || {
+            self.get(
+                &format!(
+                    "{}/repos/{user}/{repo}/{path}{separator}per_page=100&page={page}",
+                    self.base_url,
+                ),
+                None,
+            )
             .header("Accept", "application/vnd.github+json")
-            .header("User-Agent", "Git Chest")
-            .send()
+        };
+        let res = send_with_retry(req, &self.rate_limit_key("core"), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching repository list from GitHub API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        self.update_rate_limit(res.headers(), pool).await?;
+
+        let body = res.text().await?;
+        let json_body: Vec<T> = parse_body(&body, "Error parsing repository list from GitHub API")?;
+
+        info!("fetching github repo {} took {:?}", path, start.elapsed());
+
+        Ok(json_body)
+    }
+
+    /// Run a GraphQL query, which counts against the separate `graphql` rate limit.
+    /// The API requires a token.
+    pub async fn fetch_graphql<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: &Map<String, Value>,
+        pool: &SqlitePool,
+    ) -> AppResult<T> {
+        let start = Instant::now();
+
+        let req = || {
+            self.post(&self.graphql_url())
+                .json(&json!({ "query": query, "variables": variables }))
+        };
+        let res = send_with_retry(req, &self.rate_limit_key("graphql"), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching data from GitHub GraphQL API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        self.update_rate_limit(res.headers(), pool).await?;
+
+        let body = res.text().await?;
+        let json_body: GitHubApiGraphQLResponse<T> =
+            parse_body(&body, "Error parsing data from GitHub GraphQL API")?;
+
+        if !json_body.errors.is_empty() {
+            let messages: Vec<&str> = json_body
+                .errors
+                .iter()
+                .map(|e| e.message.as_str())
+                .collect();
+            error!("{:?}", messages);
+            return AppError::new(&messages.join("\n"));
+        }
+
+        info!("fetching github graphql query took {:?}", start.elapsed());
+
+        Ok(json_body
+            .data
+            .ok_or("Error parsing data from GitHub GraphQL API")?)
+    }
+
+    /// Fetch every page of a connection of a GraphQL query, which takes the cursor as
+    /// `$after`. `connection` picks the connection out of the response data.
+    pub async fn fetch_graphql_all<T: DeserializeOwned, N>(
+        &self,
+        query: &str,
+        mut variables: Map<String, Value>,
+        connection: impl Fn(T) -> Option<GitHubApiConnection<N>>,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<N>> {
+        let mut nodes = Vec::new();
+        loop {
+            let data = self.fetch_graphql::<T>(query, &variables, pool).await?;
+            let Some(page) = connection(data) else {
+                break;
+            };
+            nodes.extend(page.nodes.into_iter().flatten());
+            match page.page_info.end_cursor {
+                Some(cursor) if page.page_info.has_next_page => {
+                    variables.insert("after".to_string(), Value::String(cursor));
+                }
+                _ => break,
+            }
+        }
+        Ok(nodes)
+    }
+
+    pub async fn fetch_user(&self, user: &str, pool: &SqlitePool) -> AppResult<GitHubApiUser> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(&format!("{}/users/{user}", self.base_url), None)
+                .header("Accept", "application/vnd.github+json")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key("core"), pool)
             .await
             .map_err(|e| {
                 error!("{:?}", e);
@@ -193,4 +571,65 @@ impl GitHubAPI {
 
         Ok(json_body)
     }
+
+    /// Check a token against the authenticated user endpoint, falling back to the configured
+    /// token when none is given. Classic tokens report their scopes in `X-OAuth-Scopes`, while
+    /// fine-grained tokens do not send the header at all.
+    pub async fn fetch_token_info(
+        &self,
+        token: Option<&str>,
+        pool: &SqlitePool,
+    ) -> AppResult<GitHubTokenInfo> {
+        let start = Instant::now();
+
+        let Some(token) = token.map(|t| t.to_string()).or_else(|| self.token()) else {
+            return AppError::new("No GitHub token configured");
+        };
+
+        let req = || {
+            self.get(&format!("{}/user", self.base_url), Some(&token))
+                .header("Accept", "application/vnd.github+json")
+        };
+        let res = send_with_retry(
+            req,
+            &RateLimitKey::new(Platform::GitHub, &self.base_url, Some(&token), "core"),
+            pool,
+        )
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error validating token with GitHub API"
+        })?;
+
+        if res.status() == StatusCode::UNAUTHORIZED {
+            return AppError::new("GitHub token is invalid or expired");
+        }
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        let headers = res.headers();
+        let scopes = parse_header(headers, "X-OAuth-Scopes").ok().map(|scopes| {
+            scopes
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<String>>()
+        });
+        let expires_at = parse_header(headers, "GitHub-Authentication-Token-Expiration")
+            .ok()
+            .map(|s| s.to_string());
+        let rate_limit = parse_header_num(headers, "X-RateLimit-Limit").unwrap_or(0);
+
+        let body = res.text().await?;
+        let user: GitHubApiUser = parse_body(&body, "Error parsing user from GitHub API")?;
+
+        info!("validating github token took {:?}", start.elapsed());
+
+        Ok(GitHubTokenInfo::new(
+            user.login, scopes, expires_at, rate_limit,
+        ))
+    }
 }
@@ -2,6 +2,8 @@ use std::collections::HashMap;
 
 use serde::Deserialize;
 
+use crate::repo::NewRepoTreeItem;
+
 #[derive(Deserialize)]
 pub struct GitHubApiRepoOwner {
     pub login: String,
@@ -85,6 +87,18 @@ pub struct GitHubApiRepoTreeItem {
     pub size: Option<i32>,
 }
 
+impl From<GitHubApiRepoTreeItem> for NewRepoTreeItem {
+    fn from(item: GitHubApiRepoTreeItem) -> Self {
+        Self {
+            path: item.path,
+            mode: item.mode,
+            r#type: item.r#type,
+            sha: Some(item.sha),
+            size: item.size,
+        }
+    }
+}
+
 /// The hierarchy between files in a Git repository.
 #[derive(Deserialize)]
 pub struct GitHubApiRepoTree {
@@ -117,3 +131,393 @@ pub struct GitHubApiUser {
     pub created_at: String,
     pub updated_at: String,
 }
+
+#[derive(Deserialize)]
+pub struct GitHubApiCommitRef {
+    pub sha: String,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiBranch {
+    pub name: String,
+    pub commit: GitHubApiCommitRef,
+    pub protected: bool,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiTag {
+    pub name: String,
+    pub commit: GitHubApiCommitRef,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiGitActor {
+    pub name: String,
+    pub email: String,
+    pub date: String,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiCommitDetails {
+    pub message: String,
+    pub author: Option<GitHubApiGitActor>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiCommit {
+    pub sha: String,
+    pub html_url: String,
+    pub commit: GitHubApiCommitDetails,
+}
+
+/// The author of an issue or comment, `None` for deleted users.
+#[derive(Deserialize)]
+pub struct GitHubApiIssueUser {
+    pub login: String,
+    pub id: i64,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiLabel {
+    pub name: String,
+    pub color: String,
+    pub description: Option<String>,
+}
+
+/// Pull requests are issues too, those have the `pull_request` property set.
+#[derive(Deserialize)]
+pub struct GitHubApiIssue {
+    pub id: i64,
+    pub number: i64,
+    pub title: String,
+    pub body: Option<String>,
+    /// 'open' or 'closed'
+    pub state: String,
+    /// 'completed', 'not_planned' or 'reopened'
+    pub state_reason: Option<String>,
+    pub user: Option<GitHubApiIssueUser>,
+    pub labels: Vec<GitHubApiLabel>,
+    pub assignees: Vec<GitHubApiIssueUser>,
+    pub locked: bool,
+    pub comments: i64,
+    pub html_url: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub closed_at: Option<String>,
+    pub pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiIssueComment {
+    pub id: i64,
+    pub body: Option<String>,
+    pub user: Option<GitHubApiIssueUser>,
+    pub html_url: String,
+    /// The API URL of the issue, ending with its number.
+    pub issue_url: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiPullBranch {
+    pub r#ref: String,
+    pub sha: String,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiPull {
+    pub id: i64,
+    pub number: i64,
+    pub title: String,
+    pub body: Option<String>,
+    /// 'open' or 'closed'
+    pub state: String,
+    #[serde(default)]
+    pub draft: bool,
+    pub user: Option<GitHubApiIssueUser>,
+    pub head: GitHubApiPullBranch,
+    pub base: GitHubApiPullBranch,
+    pub html_url: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub closed_at: Option<String>,
+    pub merged_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiPullReview {
+    pub id: i64,
+    pub body: Option<String>,
+    /// 'APPROVED', 'CHANGES_REQUESTED', 'COMMENTED', 'DISMISSED' or 'PENDING'
+    pub state: String,
+    pub user: Option<GitHubApiIssueUser>,
+    pub commit_id: Option<String>,
+    pub html_url: String,
+    pub submitted_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiPullReviewComment {
+    pub id: i64,
+    pub pull_request_review_id: Option<i64>,
+    pub in_reply_to_id: Option<i64>,
+    pub body: String,
+    pub user: Option<GitHubApiIssueUser>,
+    pub path: String,
+    /// The line in the file the comment is anchored to, `None` if it's outdated.
+    pub line: Option<i64>,
+    pub start_line: Option<i64>,
+    pub original_line: Option<i64>,
+    /// 'LEFT' or 'RIGHT'
+    pub side: Option<String>,
+    pub commit_id: String,
+    pub diff_hunk: String,
+    pub html_url: String,
+    /// The API URL of the pull request, ending with its number.
+    pub pull_request_url: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiRelease {
+    pub id: i64,
+    pub tag_name: String,
+    pub name: Option<String>,
+    pub body: Option<String>,
+    pub author: Option<GitHubApiIssueUser>,
+    pub html_url: String,
+    pub draft: bool,
+    pub prerelease: bool,
+    pub created_at: String,
+    pub published_at: Option<String>,
+    pub assets: Vec<GitHubApiReleaseAsset>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiReleaseAsset {
+    pub id: i64,
+    pub name: String,
+    pub label: Option<String>,
+    pub content_type: String,
+    pub size: i64,
+    pub download_count: i64,
+    pub browser_download_url: String,
+    /// E.g. 'sha256:{hex}', only set for assets uploaded since GitHub computes it.
+    #[serde(default)]
+    pub digest: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiFork {
+    pub id: i64,
+    pub full_name: String,
+    pub html_url: String,
+    pub created_at: String,
+}
+
+/// Anonymous contributors (without a GitHub account) have no login.
+#[derive(Deserialize)]
+pub struct GitHubApiContributor {
+    pub login: Option<String>,
+    pub html_url: Option<String>,
+    pub contributions: i64,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiGraphQLError {
+    pub message: String,
+}
+
+/// GraphQL responds with 200 OK to failed queries too, with the reasons in `errors`.
+#[derive(Deserialize)]
+pub struct GitHubApiGraphQLResponse<T> {
+    pub data: Option<T>,
+    #[serde(default)]
+    pub errors: Vec<GitHubApiGraphQLError>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubApiPageInfo {
+    pub has_next_page: bool,
+    pub end_cursor: Option<String>,
+}
+
+/// A page of a GraphQL connection, queried with `nodes` and `pageInfo`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubApiConnection<T> {
+    /// GraphQL lists are nullable, e.g. for items that can't be accessed.
+    pub nodes: Vec<Option<T>>,
+    pub page_info: GitHubApiPageInfo,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiGraphQLActor {
+    pub login: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubApiReactors {
+    pub total_count: i64,
+}
+
+/// The reactions of one kind to a discussion or comment.
+#[derive(Deserialize)]
+pub struct GitHubApiReactionGroup {
+    /// E.g. 'THUMBS_UP', 'HEART' or 'ROCKET'.
+    pub content: String,
+    pub reactors: GitHubApiReactors,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubApiDiscussionCategory {
+    /// The GraphQL node ID.
+    pub id: String,
+    pub name: String,
+    pub emoji: String,
+    pub description: Option<String>,
+    /// Whether discussions of the category can have a marked answer.
+    pub is_answerable: bool,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiNodeId {
+    pub id: String,
+}
+
+/// A reply to a discussion comment, replies aren't nested any deeper.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubApiDiscussionReply {
+    pub id: String,
+    pub body: String,
+    pub url: String,
+    /// `None` for deleted users.
+    pub author: Option<GitHubApiGraphQLActor>,
+    pub upvote_count: i64,
+    pub reaction_groups: Option<Vec<GitHubApiReactionGroup>>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubApiDiscussionComment {
+    pub id: String,
+    pub body: String,
+    pub url: String,
+    pub author: Option<GitHubApiGraphQLActor>,
+    pub is_answer: bool,
+    pub upvote_count: i64,
+    pub reaction_groups: Option<Vec<GitHubApiReactionGroup>>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub replies: GitHubApiConnection<GitHubApiDiscussionReply>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubApiDiscussion {
+    pub id: String,
+    pub number: i64,
+    pub title: String,
+    pub body: String,
+    pub url: String,
+    pub author: Option<GitHubApiGraphQLActor>,
+    pub category: GitHubApiNodeId,
+    /// The comment marked as the answer.
+    pub answer: Option<GitHubApiNodeId>,
+    pub closed: bool,
+    pub locked: bool,
+    pub upvote_count: i64,
+    pub reaction_groups: Option<Vec<GitHubApiReactionGroup>>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub comments: GitHubApiConnection<GitHubApiDiscussionComment>,
+}
+
+/// A discussion in the list of a repo's discussions, for watching.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubApiDiscussionPreview {
+    pub number: i64,
+    pub title: String,
+    pub url: String,
+    pub closed: bool,
+    pub updated_at: String,
+}
+
+/// `repository` of a GraphQL query, `None` if the repo wasn't found.
+#[derive(Deserialize)]
+pub struct GitHubApiGraphQLRepository<T> {
+    pub repository: Option<T>,
+}
+
+/// `node` of a GraphQL query by node ID.
+#[derive(Deserialize)]
+pub struct GitHubApiGraphQLNode<T> {
+    pub node: Option<T>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubApiDiscussionCategories {
+    pub discussion_categories: GitHubApiConnection<GitHubApiDiscussionCategory>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiDiscussions<T> {
+    pub discussions: GitHubApiConnection<T>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiDiscussionComments {
+    pub comments: GitHubApiConnection<GitHubApiDiscussionComment>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiDiscussionReplies {
+    pub replies: GitHubApiConnection<GitHubApiDiscussionReply>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiRefs<T> {
+    pub refs: GitHubApiConnection<T>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiGitObject {
+    pub oid: String,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiGitTagger {
+    pub name: Option<String>,
+    pub date: Option<String>,
+}
+
+/// The object a tag ref points to, a 'Tag' for annotated tags and usually a 'Commit'
+/// for lightweight ones.
+#[derive(Deserialize)]
+pub struct GitHubApiTagTarget {
+    #[serde(rename = "__typename")]
+    pub typename: String,
+    pub oid: String,
+    pub message: Option<String>,
+    pub tagger: Option<GitHubApiGitTagger>,
+    /// The object an annotated tag points to.
+    pub target: Option<GitHubApiGitObject>,
+}
+
+#[derive(Deserialize)]
+pub struct GitHubApiTagRef {
+    pub name: String,
+    pub target: GitHubApiTagTarget,
+}
@@ -0,0 +1,792 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sqlx::{prelude::FromRow, Sqlite, SqlitePool, Transaction};
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    error::AppResult,
+    events::WatchedItem,
+    platforms::{
+        github::{
+            api::GitHubAPI,
+            api_models::{
+                GitHubApiDiscussion, GitHubApiDiscussionCategories, GitHubApiDiscussionComment,
+                GitHubApiDiscussionComments, GitHubApiDiscussionPreview,
+                GitHubApiDiscussionReplies, GitHubApiDiscussionReply, GitHubApiDiscussions,
+                GitHubApiGraphQLNode, GitHubApiGraphQLRepository, GitHubApiReactionGroup,
+            },
+        },
+        provider::RepoContext,
+    },
+};
+
+const REACTION_FRAGMENT: &str = "
+    fragment Reactions on Reactable {
+        reactionGroups { content reactors { totalCount } }
+    }
+";
+
+/// Replies are comments too, but can't be answers or have replies themselves.
+const REPLY_FRAGMENT: &str = "
+    fragment Reply on DiscussionComment {
+        id body url author { login } upvoteCount createdAt updatedAt ...Reactions
+    }
+";
+
+/// GraphQL rejects unused fragments, queries append the ones they use.
+const COMMENT_FRAGMENT: &str = "
+    fragment Comment on DiscussionComment {
+        ...Reply
+        isAnswer
+        replies(first: 30) { nodes { ...Reply } pageInfo { hasNextPage endCursor } }
+    }
+";
+
+const CATEGORIES_QUERY: &str = "
+    query($owner: String!, $name: String!, $after: String) {
+        repository(owner: $owner, name: $name) {
+            discussionCategories(first: 100, after: $after) {
+                nodes { id name emoji description isAnswerable }
+                pageInfo { hasNextPage endCursor }
+            }
+        }
+    }
+";
+
+/// Discussions are fetched 20 at a time with their first 30 comments and replies, which
+/// keeps the query cost low. The remaining ones are fetched separately.
+const DISCUSSIONS_QUERY: &str = "
+    query($owner: String!, $name: String!, $after: String) {
+        repository(owner: $owner, name: $name) {
+            discussions(first: 20, after: $after, orderBy: {field: UPDATED_AT, direction: DESC}) {
+                nodes {
+                    id number title body url author { login } category { id } answer { id }
+                    closed locked upvoteCount createdAt updatedAt ...Reactions
+                    comments(first: 30) { nodes { ...Comment } pageInfo { hasNextPage endCursor } }
+                }
+                pageInfo { hasNextPage endCursor }
+            }
+        }
+    }
+";
+
+const COMMENTS_QUERY: &str = "
+    query($id: ID!, $after: String) {
+        node(id: $id) {
+            ... on Discussion {
+                comments(first: 30, after: $after) {
+                    nodes { ...Comment }
+                    pageInfo { hasNextPage endCursor }
+                }
+            }
+        }
+    }
+";
+
+const REPLIES_QUERY: &str = "
+    query($id: ID!, $after: String) {
+        node(id: $id) {
+            ... on DiscussionComment {
+                replies(first: 30, after: $after) {
+                    nodes { ...Reply }
+                    pageInfo { hasNextPage endCursor }
+                }
+            }
+        }
+    }
+";
+
+const WATCH_QUERY: &str = "
+    query($owner: String!, $name: String!) {
+        repository(owner: $owner, name: $name) {
+            discussions(first: 100, orderBy: {field: UPDATED_AT, direction: DESC}) {
+                nodes { number title url closed updatedAt }
+                pageInfo { hasNextPage endCursor }
+            }
+        }
+    }
+";
+
+#[derive(Serialize, FromRow)]
+pub struct GitHubDiscussionCategory {
+    pub id: String,
+    pub name: String,
+    pub emoji: String,
+    pub description: Option<String>,
+    pub is_answerable: bool,
+}
+
+/// A discussion in the list of a repo's discussions.
+#[derive(Serialize, FromRow)]
+pub struct GitHubDiscussionPreview {
+    pub number: i64,
+    pub title: String,
+    pub category_name: Option<String>,
+    pub category_emoji: Option<String>,
+    pub author_login: Option<String>,
+    pub closed: bool,
+    pub answered: bool,
+    /// Top-level comments, without replies.
+    pub comments: i64,
+    pub upvote_count: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct GitHubDiscussionReaction {
+    pub content: String,
+    pub count: i64,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct GitHubDiscussion {
+    pub id: String,
+    pub number: i64,
+    pub category_id: Option<String>,
+    pub title: String,
+    pub body: String,
+    pub author_login: Option<String>,
+    pub url: String,
+    /// The comment marked as the answer.
+    pub answer_id: Option<String>,
+    pub closed: bool,
+    pub locked: bool,
+    pub upvote_count: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct GitHubDiscussionComment {
+    pub id: String,
+    #[serde(skip)]
+    pub reply_to_id: Option<String>,
+    pub body: String,
+    pub author_login: Option<String>,
+    pub url: String,
+    pub is_answer: bool,
+    pub upvote_count: i64,
+    pub created_at: String,
+    pub updated_at: String,
+    #[sqlx(skip)]
+    pub reactions: Vec<GitHubDiscussionReaction>,
+    /// Oldest first, always empty for replies.
+    #[sqlx(skip)]
+    pub replies: Vec<GitHubDiscussionComment>,
+}
+
+#[derive(Serialize)]
+pub struct GitHubFullDiscussion {
+    #[serde(flatten)]
+    pub discussion: GitHubDiscussion,
+    pub category: Option<GitHubDiscussionCategory>,
+    pub reactions: Vec<GitHubDiscussionReaction>,
+    /// Oldest first, with their replies.
+    pub comments: Vec<GitHubDiscussionComment>,
+}
+
+/// A comment with every one of its replies.
+struct FetchedComment {
+    comment: GitHubApiDiscussionComment,
+    replies: Vec<GitHubApiDiscussionReply>,
+}
+
+fn repo_variables(user: &str, repo: &str) -> Map<String, Value> {
+    let mut variables = Map::new();
+    variables.insert("owner".to_string(), json!(user));
+    variables.insert("name".to_string(), json!(repo));
+    variables
+}
+
+/// Variables to continue a connection of a node after a cursor.
+fn node_variables(id: &str, after: Option<String>) -> Map<String, Value> {
+    let mut variables = Map::new();
+    variables.insert("id".to_string(), json!(id));
+    variables.insert("after".to_string(), json!(after));
+    variables
+}
+
+async fn add_github_discussion_categories(
+    ctx: &RepoContext,
+    api: &GitHubAPI,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let categories = api
+        .fetch_graphql_all(
+            CATEGORIES_QUERY,
+            repo_variables(&ctx.user, &ctx.repo),
+            |data: GitHubApiGraphQLRepository<GitHubApiDiscussionCategories>| {
+                data.repository.map(|r| r.discussion_categories)
+            },
+            pool,
+        )
+        .await?;
+
+    for category in categories {
+        let query = "
+            INSERT INTO github_discussion_category (
+                id, repo_id, name, emoji, description, is_answerable
+            )
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT (id) DO UPDATE SET
+                name = excluded.name, emoji = excluded.emoji,
+                description = excluded.description, is_answerable = excluded.is_answerable
+        ";
+        sqlx::query(query)
+            .bind(&category.id)
+            .bind(ctx.repo_id)
+            .bind(&category.name)
+            .bind(&category.emoji)
+            .bind(&category.description)
+            .bind(category.is_answerable)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding discussion category to database"
+            })?;
+    }
+
+    Ok(())
+}
+
+/// Every comment of a discussion with every reply, continuing the first pages that came
+/// with the discussion.
+async fn fetch_github_discussion_comments(
+    discussion: &mut GitHubApiDiscussion,
+    api: &GitHubAPI,
+    pool: &SqlitePool,
+) -> AppResult<Vec<FetchedComment>> {
+    let first_page = std::mem::take(&mut discussion.comments.nodes);
+    let mut comments: Vec<GitHubApiDiscussionComment> = first_page.into_iter().flatten().collect();
+
+    let page_info = &discussion.comments.page_info;
+    if page_info.has_next_page {
+        let mut more = api
+            .fetch_graphql_all(
+                &format!("{COMMENTS_QUERY}{COMMENT_FRAGMENT}{REPLY_FRAGMENT}{REACTION_FRAGMENT}"),
+                node_variables(&discussion.id, page_info.end_cursor.clone()),
+                |data: GitHubApiGraphQLNode<GitHubApiDiscussionComments>| {
+                    data.node.map(|n| n.comments)
+                },
+                pool,
+            )
+            .await?;
+        comments.append(&mut more);
+    }
+
+    let mut fetched = Vec::with_capacity(comments.len());
+    for mut comment in comments {
+        let first_page = std::mem::take(&mut comment.replies.nodes);
+        let mut replies: Vec<GitHubApiDiscussionReply> = first_page.into_iter().flatten().collect();
+
+        let page_info = &comment.replies.page_info;
+        if page_info.has_next_page {
+            let mut more = api
+                .fetch_graphql_all(
+                    &format!("{REPLIES_QUERY}{REPLY_FRAGMENT}{REACTION_FRAGMENT}"),
+                    node_variables(&comment.id, page_info.end_cursor.clone()),
+                    |data: GitHubApiGraphQLNode<GitHubApiDiscussionReplies>| {
+                        data.node.map(|n| n.replies)
+                    },
+                    pool,
+                )
+                .await?;
+            replies.append(&mut more);
+        }
+
+        fetched.push(FetchedComment { comment, replies });
+    }
+
+    Ok(fetched)
+}
+
+async fn add_github_discussion_reactions(
+    subject_id: &str,
+    discussion_id: &str,
+    groups: &Option<Vec<GitHubApiReactionGroup>>,
+    tx: &mut Transaction<'_, Sqlite>,
+) -> AppResult<()> {
+    for group in groups.iter().flatten() {
+        if group.reactors.total_count == 0 {
+            continue;
+        }
+        let query = "
+            INSERT OR REPLACE INTO github_discussion_reaction (
+                subject_id, discussion_id, content, count
+            )
+            VALUES (?, ?, ?, ?)
+        ";
+        sqlx::query(query)
+            .bind(subject_id)
+            .bind(discussion_id)
+            .bind(&group.content)
+            .bind(group.reactors.total_count)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding discussion reaction to database"
+            })?;
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn add_github_discussion_comment(
+    id: &str,
+    discussion_id: &str,
+    reply_to_id: Option<&str>,
+    body: &str,
+    author_login: Option<&str>,
+    url: &str,
+    is_answer: bool,
+    upvote_count: i64,
+    created_at: &str,
+    updated_at: &str,
+    tx: &mut Transaction<'_, Sqlite>,
+) -> AppResult<()> {
+    let query = "
+        INSERT INTO github_discussion_comment (
+            id, discussion_id, reply_to_id, body, author_login, url, is_answer, upvote_count,
+            created_at, updated_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    ";
+    sqlx::query(query)
+        .bind(id)
+        .bind(discussion_id)
+        .bind(reply_to_id)
+        .bind(body)
+        .bind(author_login)
+        .bind(url)
+        .bind(is_answer)
+        .bind(upvote_count)
+        .bind(created_at)
+        .bind(updated_at)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding discussion comment to database"
+        })?;
+    Ok(())
+}
+
+/// Store a discussion, replacing its previously stored comments and reactions, so
+/// deleted ones are removed too.
+async fn add_github_discussion(
+    repo_id: i64,
+    discussion: &GitHubApiDiscussion,
+    comments: &[FetchedComment],
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("{:?}", e);
+        "Error adding discussion to database"
+    })?;
+
+    let query = "
+        INSERT INTO github_discussion (
+            id, repo_id, number, category_id, title, body, author_login, url, answer_id,
+            closed, locked, upvote_count, created_at, updated_at
+        )
+        VALUES (
+            ?, ?, ?, (SELECT id FROM github_discussion_category WHERE id = ?), ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?
+        )
+        ON CONFLICT (id) DO UPDATE SET
+            number = excluded.number, category_id = excluded.category_id,
+            title = excluded.title, body = excluded.body, author_login = excluded.author_login,
+            url = excluded.url, answer_id = excluded.answer_id, closed = excluded.closed,
+            locked = excluded.locked, upvote_count = excluded.upvote_count,
+            updated_at = excluded.updated_at
+    ";
+    sqlx::query(query)
+        .bind(&discussion.id)
+        .bind(repo_id)
+        .bind(discussion.number)
+        .bind(&discussion.category.id)
+        .bind(&discussion.title)
+        .bind(&discussion.body)
+        .bind(discussion.author.as_ref().map(|a| &a.login))
+        .bind(&discussion.url)
+        .bind(discussion.answer.as_ref().map(|a| &a.id))
+        .bind(discussion.closed)
+        .bind(discussion.locked)
+        .bind(discussion.upvote_count)
+        .bind(&discussion.created_at)
+        .bind(&discussion.updated_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding discussion to database"
+        })?;
+
+    for query in [
+        "DELETE FROM github_discussion_comment WHERE discussion_id = ?",
+        "DELETE FROM github_discussion_reaction WHERE discussion_id = ?",
+    ] {
+        sqlx::query(query)
+            .bind(&discussion.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error removing discussion comments from database"
+            })?;
+    }
+
+    add_github_discussion_reactions(
+        &discussion.id,
+        &discussion.id,
+        &discussion.reaction_groups,
+        &mut tx,
+    )
+    .await?;
+
+    for FetchedComment { comment, replies } in comments {
+        add_github_discussion_comment(
+            &comment.id,
+            &discussion.id,
+            None,
+            &comment.body,
+            comment.author.as_ref().map(|a| a.login.as_str()),
+            &comment.url,
+            comment.is_answer,
+            comment.upvote_count,
+            &comment.created_at,
+            &comment.updated_at,
+            &mut tx,
+        )
+        .await?;
+        add_github_discussion_reactions(
+            &comment.id,
+            &discussion.id,
+            &comment.reaction_groups,
+            &mut tx,
+        )
+        .await?;
+
+        for reply in replies {
+            add_github_discussion_comment(
+                &reply.id,
+                &discussion.id,
+                Some(&comment.id),
+                &reply.body,
+                reply.author.as_ref().map(|a| a.login.as_str()),
+                &reply.url,
+                false,
+                reply.upvote_count,
+                &reply.created_at,
+                &reply.updated_at,
+                &mut tx,
+            )
+            .await?;
+            add_github_discussion_reactions(
+                &reply.id,
+                &discussion.id,
+                &reply.reaction_groups,
+                &mut tx,
+            )
+            .await?;
+        }
+    }
+
+    tx.commit().await.map_err(|e| {
+        error!("{:?}", e);
+        "Error adding discussion to database"
+    })?;
+
+    Ok(())
+}
+
+/// Fetch and store the discussions of a repo with their categories, comments, replies
+/// and reactions, only those updated since the previous archive if there was one.
+pub async fn archive_github_discussions(
+    ctx: &RepoContext,
+    api: &GitHubAPI,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let start = Instant::now();
+
+    let query = "SELECT discussions_since FROM github_discussion_sync WHERE repo_id = ?";
+    let since = sqlx::query_scalar::<_, Option<String>>(query)
+        .bind(ctx.repo_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting discussion sync from database"
+        })?
+        .flatten();
+
+    add_github_discussion_categories(ctx, api, pool).await?;
+
+    // Discussions are fetched most recently updated first, until a page reaches
+    // discussions that were already archived.
+    let query = format!("{DISCUSSIONS_QUERY}{COMMENT_FRAGMENT}{REPLY_FRAGMENT}{REACTION_FRAGMENT}");
+    let mut variables = repo_variables(&ctx.user, &ctx.repo);
+    let mut latest_update: Option<String> = None;
+    let mut count = 0;
+    loop {
+        let data = api
+            .fetch_graphql::<GitHubApiGraphQLRepository<GitHubApiDiscussions<GitHubApiDiscussion>>>(
+                &query, &variables, pool,
+            )
+            .await?;
+        let page = data
+            .repository
+            .ok_or("Repository not found on GitHub")?
+            .discussions;
+
+        let mut reached_since = false;
+        for mut discussion in page.nodes.into_iter().flatten() {
+            if since.as_ref().is_some_and(|s| &discussion.updated_at < s) {
+                reached_since = true;
+                break;
+            }
+
+            let comments = fetch_github_discussion_comments(&mut discussion, api, pool).await?;
+            add_github_discussion(ctx.repo_id, &discussion, &comments, pool).await?;
+
+            if latest_update
+                .as_ref()
+                .map_or(true, |l| &discussion.updated_at > l)
+            {
+                latest_update = Some(discussion.updated_at.clone());
+            }
+            count += 1;
+        }
+
+        match page.page_info.end_cursor {
+            Some(cursor) if page.page_info.has_next_page && !reached_since => {
+                variables.insert("after".to_string(), Value::String(cursor));
+            }
+            _ => break,
+        }
+    }
+
+    let query = "
+        INSERT INTO github_discussion_sync (repo_id, discussions_since)
+        VALUES (?, ?)
+        ON CONFLICT (repo_id) DO UPDATE SET
+            discussions_since = COALESCE(excluded.discussions_since, discussions_since)
+    ";
+    sqlx::query(query)
+        .bind(ctx.repo_id)
+        .bind(latest_update)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating discussion sync in database"
+        })?;
+
+    info!(
+        "archived {} discussions of {}/{} in {:?}",
+        count,
+        ctx.user,
+        ctx.repo,
+        start.elapsed()
+    );
+
+    Ok(())
+}
+
+/// The 100 most recently updated discussions of a repo, to watch for changes.
+pub async fn fetch_github_discussion_items(
+    api: &GitHubAPI,
+    user: &str,
+    repo: &str,
+    pool: &SqlitePool,
+) -> AppResult<Vec<WatchedItem>> {
+    let data = api
+        .fetch_graphql::<GitHubApiGraphQLRepository<GitHubApiDiscussions<GitHubApiDiscussionPreview>>>(
+            WATCH_QUERY,
+            &repo_variables(user, repo),
+            pool,
+        )
+        .await?;
+    let discussions = data
+        .repository
+        .ok_or("Repository not found on GitHub")?
+        .discussions
+        .nodes;
+
+    Ok(discussions
+        .into_iter()
+        .flatten()
+        .map(|discussion| WatchedItem {
+            key: discussion.number.to_string(),
+            title: format!("#{} {}", discussion.number, discussion.title),
+            version: if discussion.closed { "closed" } else { "open" }.to_string(),
+            url: Some(discussion.url),
+            occurred_at: Some(discussion.updated_at),
+        })
+        .collect())
+}
+
+pub async fn get_github_discussion_categories(
+    repo_id: i64,
+    pool: &SqlitePool,
+) -> AppResult<Vec<GitHubDiscussionCategory>> {
+    let query = "
+        SELECT id, name, emoji, description, is_answerable
+        FROM github_discussion_category
+        WHERE repo_id = ?
+        ORDER BY name
+    ";
+    let categories = sqlx::query_as::<_, GitHubDiscussionCategory>(query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting discussion categories from database"
+        })?;
+    Ok(categories)
+}
+
+/// The archived discussions of a repo, most recently updated first.
+pub async fn get_github_discussions(
+    repo_id: i64,
+    category_id: Option<&str>,
+    offset: i64,
+    limit: i64,
+    pool: &SqlitePool,
+) -> AppResult<Vec<GitHubDiscussionPreview>> {
+    let query = "
+        SELECT
+            d.number, d.title, c.name AS category_name, c.emoji AS category_emoji,
+            d.author_login, d.closed, d.answer_id IS NOT NULL AS answered,
+            (
+                SELECT COUNT(*)
+                FROM github_discussion_comment
+                WHERE discussion_id = d.id AND reply_to_id IS NULL
+            ) AS comments,
+            d.upvote_count, d.created_at, d.updated_at
+        FROM github_discussion AS d
+        LEFT JOIN github_discussion_category AS c ON c.id = d.category_id
+        WHERE d.repo_id = ? AND (? IS NULL OR d.category_id = ?)
+        ORDER BY d.updated_at DESC
+        LIMIT ? OFFSET ?
+    ";
+    let discussions = sqlx::query_as::<_, GitHubDiscussionPreview>(query)
+        .bind(repo_id)
+        .bind(category_id)
+        .bind(category_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting discussions from database"
+        })?;
+    Ok(discussions)
+}
+
+/// An archived discussion with its category, reactions and threaded comments.
+pub async fn get_github_discussion(
+    repo_id: i64,
+    number: i64,
+    pool: &SqlitePool,
+) -> AppResult<GitHubFullDiscussion> {
+    let query = "
+        SELECT
+            id, number, category_id, title, body, author_login, url, answer_id, closed,
+            locked, upvote_count, created_at, updated_at
+        FROM github_discussion
+        WHERE repo_id = ? AND number = ?
+    ";
+    let discussion = sqlx::query_as::<_, GitHubDiscussion>(query)
+        .bind(repo_id)
+        .bind(number)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting discussion from database"
+        })?
+        .ok_or("Discussion not found")?;
+
+    let query = "
+        SELECT id, name, emoji, description, is_answerable
+        FROM github_discussion_category
+        WHERE id = ?
+    ";
+    let category = sqlx::query_as::<_, GitHubDiscussionCategory>(query)
+        .bind(&discussion.category_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting discussion category from database"
+        })?;
+
+    let query = "
+        SELECT subject_id, content, count
+        FROM github_discussion_reaction
+        WHERE discussion_id = ?
+        ORDER BY count DESC
+    ";
+    let reaction_rows = sqlx::query_as::<_, (String, String, i64)>(query)
+        .bind(&discussion.id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting discussion reactions from database"
+        })?;
+    let mut reactions: HashMap<String, Vec<GitHubDiscussionReaction>> = HashMap::new();
+    for (subject_id, content, count) in reaction_rows {
+        reactions
+            .entry(subject_id)
+            .or_default()
+            .push(GitHubDiscussionReaction { content, count });
+    }
+
+    let query = "
+        SELECT
+            id, reply_to_id, body, author_login, url, is_answer, upvote_count, created_at,
+            updated_at
+        FROM github_discussion_comment
+        WHERE discussion_id = ?
+        ORDER BY created_at
+    ";
+    let rows = sqlx::query_as::<_, GitHubDiscussionComment>(query)
+        .bind(&discussion.id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting discussion comments from database"
+        })?;
+
+    // Replies are attached to their comment, both stay ordered by creation.
+    let mut comments: Vec<GitHubDiscussionComment> = Vec::new();
+    let mut replies: HashMap<String, Vec<GitHubDiscussionComment>> = HashMap::new();
+    for mut comment in rows {
+        comment.reactions = reactions.remove(&comment.id).unwrap_or_default();
+        match comment.reply_to_id.clone() {
+            Some(reply_to_id) => replies.entry(reply_to_id).or_default().push(comment),
+            None => comments.push(comment),
+        }
+    }
+    for comment in &mut comments {
+        comment.replies = replies.remove(&comment.id).unwrap_or_default();
+    }
+
+    Ok(GitHubFullDiscussion {
+        reactions: reactions.remove(&discussion.id).unwrap_or_default(),
+        discussion,
+        category,
+        comments,
+    })
+}
@@ -0,0 +1,547 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use sqlx::{prelude::FromRow, SqlitePool};
+use tokio::{fs, io::AsyncWriteExt, time::Instant};
+use tracing::{error, info};
+
+use crate::{
+    error::AppResult,
+    platforms::{
+        github::{
+            api::GitHubAPI,
+            api_models::{GitHubApiIssue, GitHubApiIssueComment},
+        },
+        provider::RepoContext,
+    },
+    repo::parse_image_urls,
+    utils::{
+        dirs::{ensure_dir, get_data_dir},
+        image::download_image,
+    },
+};
+
+/// An issue in the list of a repo's issues.
+#[derive(Serialize, FromRow)]
+pub struct GitHubIssuePreview {
+    pub number: i64,
+    pub title: String,
+    pub state: String,
+    pub user_login: Option<String>,
+    pub comments: i64,
+    /// Comma-separated label names.
+    pub labels: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub closed_at: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct GitHubIssue {
+    #[serde(skip)]
+    pub id: i64,
+    pub number: i64,
+    pub title: String,
+    /// Markdown, with mirrored images pointing to their local files.
+    pub body: Option<String>,
+    pub state: String,
+    pub state_reason: Option<String>,
+    pub user_login: Option<String>,
+    pub locked: bool,
+    pub comments: i64,
+    pub html_url: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub closed_at: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct GitHubIssueLabel {
+    pub name: String,
+    pub color: String,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct GitHubIssueComment {
+    pub id: i64,
+    /// Markdown, with mirrored images pointing to their local files.
+    pub body: Option<String>,
+    pub user_login: Option<String>,
+    pub html_url: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Serialize)]
+pub struct GitHubFullIssue {
+    #[serde(flatten)]
+    pub issue: GitHubIssue,
+    pub labels: Vec<GitHubIssueLabel>,
+    pub assignees: Vec<String>,
+    pub comments: Vec<GitHubIssueComment>,
+}
+
+fn get_issue_asset_dir(user: &str, repo: &str) -> std::path::PathBuf {
+    get_data_dir().join(format!("assets/repos/{user}/{repo}/issues"))
+}
+
+/// Download the images of a markdown body that weren't mirrored yet. Images that fail
+/// to download keep pointing to their URL.
+async fn mirror_body_images(ctx: &RepoContext, body: &str, pool: &SqlitePool) -> AppResult<()> {
+    let dir = get_issue_asset_dir(&ctx.user, &ctx.repo);
+
+    for url in parse_image_urls(body) {
+        let query = "SELECT 1 FROM github_issue_asset WHERE repo_id = ? AND url = ?";
+        let exists = sqlx::query_scalar::<_, i64>(query)
+            .bind(ctx.repo_id)
+            .bind(&url)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error getting issue asset from database"
+            })?
+            .is_some();
+        if exists {
+            continue;
+        }
+
+        let (bytes, ext) = match download_image(&url).await {
+            Ok(image) => image,
+            Err(e) => {
+                error!("{:?}", e);
+                continue;
+            }
+        };
+
+        let query = "INSERT INTO github_issue_asset (repo_id, url, ext) VALUES (?, ?, ?)";
+        let id = sqlx::query(query)
+            .bind(ctx.repo_id)
+            .bind(&url)
+            .bind(&ext)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error inserting issue asset into database"
+            })?
+            .last_insert_rowid();
+
+        ensure_dir(&dir).await?;
+        let ext_str = ext.map(|e| format!(".{e}")).unwrap_or_default();
+        let mut file = fs::File::create(dir.join(format!("{id}{ext_str}")))
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error creating local issue asset file"
+            })?;
+        file.write_all(&bytes).await?;
+    }
+
+    Ok(())
+}
+
+/// Point the mirrored images of a markdown body to their local files.
+fn replace_mirrored_images(body: &str, assets: &HashMap<String, String>) -> String {
+    parse_image_urls(body)
+        .into_iter()
+        .fold(body.to_string(), |body, url| match assets.get(&url) {
+            Some(path) => body.replace(&url, path),
+            None => body,
+        })
+}
+
+/// The local paths of the mirrored issue images of a repo by their URL.
+async fn get_issue_assets(
+    repo_id: i64,
+    user: &str,
+    repo: &str,
+    pool: &SqlitePool,
+) -> AppResult<HashMap<String, String>> {
+    let query = "SELECT id, url, ext FROM github_issue_asset WHERE repo_id = ?";
+    let assets = sqlx::query_as::<_, (i64, String, Option<String>)>(query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting issue assets from database"
+        })?;
+
+    let dir = get_issue_asset_dir(user, repo);
+    Ok(assets
+        .into_iter()
+        .map(|(id, url, ext)| {
+            let ext_str = ext.map(|e| format!(".{e}")).unwrap_or_default();
+            let path = dir.join(format!("{id}{ext_str}"));
+            (url, path.to_string_lossy().into_owned())
+        })
+        .collect())
+}
+
+async fn add_github_issue(
+    repo_id: i64,
+    issue: &GitHubApiIssue,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("{:?}", e);
+        "Error adding issue to database"
+    })?;
+
+    let query = "
+        INSERT INTO github_issue (
+            id, repo_id, number, title, body, state, state_reason, user_login, user_id, locked,
+            comments, html_url, created_at, updated_at, closed_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (id) DO UPDATE SET
+            number = excluded.number, title = excluded.title, body = excluded.body,
+            state = excluded.state, state_reason = excluded.state_reason,
+            user_login = excluded.user_login, user_id = excluded.user_id,
+            locked = excluded.locked, comments = excluded.comments,
+            html_url = excluded.html_url, updated_at = excluded.updated_at,
+            closed_at = excluded.closed_at
+    ";
+    sqlx::query(query)
+        .bind(issue.id)
+        .bind(repo_id)
+        .bind(issue.number)
+        .bind(&issue.title)
+        .bind(&issue.body)
+        .bind(&issue.state)
+        .bind(&issue.state_reason)
+        .bind(issue.user.as_ref().map(|u| &u.login))
+        .bind(issue.user.as_ref().map(|u| u.id))
+        .bind(issue.locked)
+        .bind(issue.comments)
+        .bind(&issue.html_url)
+        .bind(&issue.created_at)
+        .bind(&issue.updated_at)
+        .bind(&issue.closed_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding issue to database"
+        })?;
+
+    // Labels and assignees are replaced, they may have been removed from the issue.
+    sqlx::query("DELETE FROM github_issue_label WHERE issue_id = ?")
+        .bind(issue.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing issue labels from database"
+        })?;
+    for label in &issue.labels {
+        let query = "
+            INSERT OR REPLACE INTO github_issue_label (issue_id, name, color, description)
+            VALUES (?, ?, ?, ?)
+        ";
+        sqlx::query(query)
+            .bind(issue.id)
+            .bind(&label.name)
+            .bind(&label.color)
+            .bind(&label.description)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding issue label to database"
+            })?;
+    }
+
+    sqlx::query("DELETE FROM github_issue_assignee WHERE issue_id = ?")
+        .bind(issue.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing issue assignees from database"
+        })?;
+    for assignee in &issue.assignees {
+        let query = "
+            INSERT OR REPLACE INTO github_issue_assignee (issue_id, login, user_id)
+            VALUES (?, ?, ?)
+        ";
+        sqlx::query(query)
+            .bind(issue.id)
+            .bind(&assignee.login)
+            .bind(assignee.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding issue assignee to database"
+            })?;
+    }
+
+    tx.commit().await.map_err(|e| {
+        error!("{:?}", e);
+        "Error adding issue to database"
+    })?;
+
+    Ok(())
+}
+
+/// Store a comment, unless its issue isn't archived (e.g. comments on pull requests).
+async fn add_github_issue_comment(
+    repo_id: i64,
+    comment: &GitHubApiIssueComment,
+    pool: &SqlitePool,
+) -> AppResult<bool> {
+    let Some(number) = comment
+        .issue_url
+        .rsplit('/')
+        .next()
+        .and_then(|n| n.parse::<i64>().ok())
+    else {
+        return Ok(false);
+    };
+
+    let query = "
+        INSERT INTO github_issue_comment (
+            id, issue_id, body, user_login, user_id, html_url, created_at, updated_at
+        )
+        SELECT ?, id, ?, ?, ?, ?, ?, ?
+        FROM github_issue
+        WHERE repo_id = ? AND number = ?
+        ON CONFLICT (id) DO UPDATE SET
+            body = excluded.body, user_login = excluded.user_login, user_id = excluded.user_id,
+            updated_at = excluded.updated_at
+    ";
+    let result = sqlx::query(query)
+        .bind(comment.id)
+        .bind(&comment.body)
+        .bind(comment.user.as_ref().map(|u| &u.login))
+        .bind(comment.user.as_ref().map(|u| u.id))
+        .bind(&comment.html_url)
+        .bind(&comment.created_at)
+        .bind(&comment.updated_at)
+        .bind(repo_id)
+        .bind(number)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding issue comment to database"
+        })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Fetch and store the issues of a repo with their comments, only those updated since
+/// the previous archive if there was one.
+pub async fn archive_github_issues(
+    ctx: &RepoContext,
+    api: &GitHubAPI,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let start = Instant::now();
+
+    let query = "SELECT issues_since, comments_since FROM github_issue_sync WHERE repo_id = ?";
+    let (issues_since, comments_since) =
+        sqlx::query_as::<_, (Option<String>, Option<String>)>(query)
+            .bind(ctx.repo_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error getting issue sync from database"
+            })?
+            .unwrap_or_default();
+
+    let since = |since: &Option<String>| {
+        since
+            .as_ref()
+            .map(|s| format!("&since={s}"))
+            .unwrap_or_default()
+    };
+
+    let issues = api
+        .fetch_repo_list_all::<GitHubApiIssue>(
+            &ctx.user,
+            &ctx.repo,
+            &format!(
+                "issues?state=all&sort=updated&direction=asc{}",
+                since(&issues_since)
+            ),
+            pool,
+        )
+        .await?;
+    let issues: Vec<GitHubApiIssue> = issues
+        .into_iter()
+        .filter(|issue| issue.pull_request.is_none())
+        .collect();
+
+    for issue in &issues {
+        add_github_issue(ctx.repo_id, issue, pool).await?;
+        if let Some(body) = &issue.body {
+            mirror_body_images(ctx, body, pool).await?;
+        }
+    }
+
+    let comments = api
+        .fetch_repo_list_all::<GitHubApiIssueComment>(
+            &ctx.user,
+            &ctx.repo,
+            &format!(
+                "issues/comments?sort=updated&direction=asc{}",
+                since(&comments_since)
+            ),
+            pool,
+        )
+        .await?;
+
+    for comment in &comments {
+        let added = add_github_issue_comment(ctx.repo_id, comment, pool).await?;
+        if let (true, Some(body)) = (added, &comment.body) {
+            mirror_body_images(ctx, body, pool).await?;
+        }
+    }
+
+    // GitHub's timestamps are used, so the local clock can't skip updates. `since` is
+    // inclusive, the latest items are fetched again next time.
+    let query = "
+        INSERT INTO github_issue_sync (repo_id, issues_since, comments_since)
+        VALUES (?, ?, ?)
+        ON CONFLICT (repo_id) DO UPDATE SET
+            issues_since = COALESCE(excluded.issues_since, issues_since),
+            comments_since = COALESCE(excluded.comments_since, comments_since)
+    ";
+    sqlx::query(query)
+        .bind(ctx.repo_id)
+        .bind(issues.iter().map(|i| &i.updated_at).max())
+        .bind(comments.iter().map(|c| &c.updated_at).max())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating issue sync in database"
+        })?;
+
+    info!(
+        "archived {} issues and {} comments of {}/{} in {:?}",
+        issues.len(),
+        comments.len(),
+        ctx.user,
+        ctx.repo,
+        start.elapsed()
+    );
+
+    Ok(())
+}
+
+/// The archived issues of a repo, most recently updated first.
+pub async fn get_github_issues(
+    repo_id: i64,
+    state: Option<&str>,
+    offset: i64,
+    limit: i64,
+    pool: &SqlitePool,
+) -> AppResult<Vec<GitHubIssuePreview>> {
+    let query = "
+        SELECT
+            i.number, i.title, i.state, i.user_login, i.comments,
+            (SELECT group_concat(name, ',') FROM github_issue_label WHERE issue_id = i.id) AS labels,
+            i.created_at, i.updated_at, i.closed_at
+        FROM github_issue AS i
+        WHERE i.repo_id = ? AND (? IS NULL OR i.state = ?)
+        ORDER BY i.updated_at DESC
+        LIMIT ? OFFSET ?
+    ";
+    let issues = sqlx::query_as::<_, GitHubIssuePreview>(query)
+        .bind(repo_id)
+        .bind(state)
+        .bind(state)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting issues from database"
+        })?;
+    Ok(issues)
+}
+
+/// An archived issue with its labels, assignees and comments, oldest comment first.
+pub async fn get_github_issue(
+    ctx: &RepoContext,
+    number: i64,
+    pool: &SqlitePool,
+) -> AppResult<GitHubFullIssue> {
+    let query = "
+        SELECT
+            id, number, title, body, state, state_reason, user_login, locked, comments,
+            html_url, created_at, updated_at, closed_at
+        FROM github_issue
+        WHERE repo_id = ? AND number = ?
+    ";
+    let mut issue = sqlx::query_as::<_, GitHubIssue>(query)
+        .bind(ctx.repo_id)
+        .bind(number)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting issue from database"
+        })?
+        .ok_or("Issue not found")?;
+
+    let query = "SELECT name, color, description FROM github_issue_label WHERE issue_id = ?";
+    let labels = sqlx::query_as::<_, GitHubIssueLabel>(query)
+        .bind(issue.id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting issue labels from database"
+        })?;
+
+    let query = "SELECT login FROM github_issue_assignee WHERE issue_id = ?";
+    let assignees = sqlx::query_scalar::<_, String>(query)
+        .bind(issue.id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting issue assignees from database"
+        })?;
+
+    let query = "
+        SELECT id, body, user_login, html_url, created_at, updated_at
+        FROM github_issue_comment
+        WHERE issue_id = ?
+        ORDER BY created_at
+    ";
+    let mut comments = sqlx::query_as::<_, GitHubIssueComment>(query)
+        .bind(issue.id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting issue comments from database"
+        })?;
+
+    let assets = get_issue_assets(ctx.repo_id, &ctx.user, &ctx.repo, pool).await?;
+    issue.body = issue
+        .body
+        .map(|body| replace_mirrored_images(&body, &assets));
+    for comment in &mut comments {
+        comment.body = comment
+            .body
+            .take()
+            .map(|body| replace_mirrored_images(&body, &assets));
+    }
+
+    Ok(GitHubFullIssue {
+        issue,
+        labels,
+        assignees,
+        comments,
+    })
+}
@@ -1,6 +1,8 @@
 use serde::Serialize;
 use sqlx::prelude::FromRow;
 
+use crate::repo::NewRepoTreeItem;
+
 /// Excluding `repo_id`.
 #[derive(Serialize, FromRow)]
 pub struct GitHubRepo {
@@ -41,7 +43,7 @@ pub struct GitHubRepo {
     subscribers_count: i32,
 }
 
-/// Excluding `github_repo_id`.
+/// Excluding `repo_id`.
 #[derive(Serialize, FromRow)]
 pub struct GitHubRepoOwner {
     login: String,
@@ -52,7 +54,7 @@ pub struct GitHubRepoOwner {
     site_admin: bool,
 }
 
-/// Excluding `github_repo_id`.
+/// Excluding `repo_id`.
 #[derive(Serialize, FromRow)]
 pub struct GitHubRepoOrg {
     login: String,
@@ -63,7 +65,7 @@ pub struct GitHubRepoOrg {
     site_admin: bool,
 }
 
-/// Excluding `github_repo_id`.
+/// Excluding `repo_id`.
 #[derive(Serialize, FromRow)]
 pub struct GitHubRepoLicense {
     key: String,
@@ -72,7 +74,7 @@ pub struct GitHubRepoLicense {
     node_id: String,
 }
 
-/// Excluding `github_repo_id`.
+/// Excluding `repo_id`.
 #[derive(Serialize, FromRow)]
 pub struct GitHubRepoCustomProperty {
     key: String,
@@ -117,3 +119,78 @@ pub struct GitHubUser {
 pub struct GitHubUserData {
     pub user: GitHubUser,
 }
+
+/// What a GitHub token grants access to, derived from its OAuth scopes. Fine-grained tokens
+/// have no scopes, so each permission is `None` (unknown) for them.
+#[derive(Serialize)]
+pub struct GitHubTokenScopes {
+    pub scopes: Option<Vec<String>>,
+    pub private_repos: Option<bool>,
+    pub read_org: Option<bool>,
+    pub read_user: Option<bool>,
+}
+
+impl GitHubTokenScopes {
+    fn new(scopes: Option<Vec<String>>) -> Self {
+        let has = |names: &[&str]| {
+            scopes
+                .as_ref()
+                .map(|scopes| scopes.iter().any(|s| names.contains(&s.as_str())))
+        };
+
+        Self {
+            private_repos: has(&["repo"]),
+            read_org: has(&["read:org", "write:org", "admin:org"]),
+            read_user: has(&["user", "read:user"]),
+            scopes,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct GitHubTokenInfo {
+    /// The user the token belongs to.
+    pub login: String,
+    pub expires_at: Option<String>,
+    /// Requests per hour allowed for the token.
+    pub rate_limit: i64,
+    pub scopes: GitHubTokenScopes,
+}
+
+impl GitHubTokenInfo {
+    pub fn new(
+        login: String,
+        scopes: Option<Vec<String>>,
+        expires_at: Option<String>,
+        rate_limit: i64,
+    ) -> Self {
+        Self {
+            login,
+            expires_at,
+            rate_limit,
+            scopes: GitHubTokenScopes::new(scopes),
+        }
+    }
+}
+
+/// A tree item stored while walking a truncated tree.
+#[derive(FromRow)]
+pub struct GitHubTreeWalkItem {
+    pub path: String,
+    pub mode: String,
+    pub r#type: String,
+    pub sha: String,
+    pub size: Option<i32>,
+}
+
+impl From<GitHubTreeWalkItem> for NewRepoTreeItem {
+    fn from(item: GitHubTreeWalkItem) -> Self {
+        Self {
+            path: item.path,
+            mode: item.mode,
+            r#type: item.r#type,
+            sha: Some(item.sha),
+            size: item.size,
+        }
+    }
+}
@@ -0,0 +1,513 @@
+use serde::Serialize;
+use sqlx::{prelude::FromRow, SqlitePool};
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    error::AppResult,
+    platforms::{
+        github::{
+            api::GitHubAPI,
+            api_models::{GitHubApiPull, GitHubApiPullReview, GitHubApiPullReviewComment},
+        },
+        provider::RepoContext,
+    },
+    utils::data::{compress_text, decompress_text},
+};
+
+/// A pull request in the list of a repo's pull requests.
+#[derive(Serialize, FromRow)]
+pub struct GitHubPullPreview {
+    pub number: i64,
+    pub title: String,
+    pub state: String,
+    pub draft: bool,
+    pub user_login: Option<String>,
+    pub head_ref: String,
+    pub base_ref: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub closed_at: Option<String>,
+    pub merged_at: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct GitHubPull {
+    #[serde(skip)]
+    pub id: i64,
+    pub number: i64,
+    pub title: String,
+    pub body: Option<String>,
+    pub state: String,
+    pub draft: bool,
+    pub user_login: Option<String>,
+    pub head_ref: String,
+    pub head_sha: String,
+    pub base_ref: String,
+    pub base_sha: String,
+    pub html_url: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub closed_at: Option<String>,
+    pub merged_at: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct GitHubPullReview {
+    pub id: i64,
+    pub body: Option<String>,
+    pub state: String,
+    pub user_login: Option<String>,
+    pub commit_id: Option<String>,
+    pub html_url: String,
+    pub submitted_at: Option<String>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct GitHubPullReviewComment {
+    pub id: i64,
+    pub review_id: Option<i64>,
+    pub in_reply_to_id: Option<i64>,
+    pub body: String,
+    pub user_login: Option<String>,
+    pub path: String,
+    pub line: Option<i64>,
+    pub start_line: Option<i64>,
+    pub original_line: Option<i64>,
+    pub side: Option<String>,
+    pub commit_id: String,
+    pub diff_hunk: String,
+    pub html_url: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Serialize)]
+pub struct GitHubFullPull {
+    #[serde(flatten)]
+    pub pull: GitHubPull,
+    pub reviews: Vec<GitHubPullReview>,
+    pub review_comments: Vec<GitHubPullReviewComment>,
+    /// The `.diff` text, `None` if it wasn't archived, e.g. because it was too large.
+    pub diff: Option<String>,
+}
+
+async fn add_github_pull(repo_id: i64, pull: &GitHubApiPull, pool: &SqlitePool) -> AppResult<()> {
+    let query = "
+        INSERT INTO github_pull (
+            id, repo_id, number, title, body, state, draft, user_login, user_id, head_ref,
+            head_sha, base_ref, base_sha, html_url, created_at, updated_at, closed_at, merged_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (id) DO UPDATE SET
+            number = excluded.number, title = excluded.title, body = excluded.body,
+            state = excluded.state, draft = excluded.draft, user_login = excluded.user_login,
+            user_id = excluded.user_id, head_ref = excluded.head_ref,
+            head_sha = excluded.head_sha, base_ref = excluded.base_ref,
+            base_sha = excluded.base_sha, html_url = excluded.html_url,
+            updated_at = excluded.updated_at, closed_at = excluded.closed_at,
+            merged_at = excluded.merged_at
+    ";
+    sqlx::query(query)
+        .bind(pull.id)
+        .bind(repo_id)
+        .bind(pull.number)
+        .bind(&pull.title)
+        .bind(&pull.body)
+        .bind(&pull.state)
+        .bind(pull.draft)
+        .bind(pull.user.as_ref().map(|u| &u.login))
+        .bind(pull.user.as_ref().map(|u| u.id))
+        .bind(&pull.head.r#ref)
+        .bind(&pull.head.sha)
+        .bind(&pull.base.r#ref)
+        .bind(&pull.base.sha)
+        .bind(&pull.html_url)
+        .bind(&pull.created_at)
+        .bind(&pull.updated_at)
+        .bind(&pull.closed_at)
+        .bind(&pull.merged_at)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding pull request to database"
+        })?;
+    Ok(())
+}
+
+async fn add_github_pull_reviews(
+    pull_id: i64,
+    reviews: &[GitHubApiPullReview],
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    for review in reviews {
+        let query = "
+            INSERT OR REPLACE INTO github_pull_review (
+                id, pull_id, body, state, user_login, user_id, commit_id, html_url, submitted_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ";
+        sqlx::query(query)
+            .bind(review.id)
+            .bind(pull_id)
+            .bind(&review.body)
+            .bind(&review.state)
+            .bind(review.user.as_ref().map(|u| &u.login))
+            .bind(review.user.as_ref().map(|u| u.id))
+            .bind(&review.commit_id)
+            .bind(&review.html_url)
+            .bind(&review.submitted_at)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding pull request review to database"
+            })?;
+    }
+    Ok(())
+}
+
+/// Store a review comment, unless its pull request isn't archived.
+async fn add_github_pull_review_comment(
+    repo_id: i64,
+    comment: &GitHubApiPullReviewComment,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let Some(number) = comment
+        .pull_request_url
+        .rsplit('/')
+        .next()
+        .and_then(|n| n.parse::<i64>().ok())
+    else {
+        return Ok(());
+    };
+
+    let query = "
+        INSERT INTO github_pull_review_comment (
+            id, pull_id, review_id, in_reply_to_id, body, user_login, user_id, path, line,
+            start_line, original_line, side, commit_id, diff_hunk, html_url, created_at,
+            updated_at
+        )
+        SELECT ?, id, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?
+        FROM github_pull
+        WHERE repo_id = ? AND number = ?
+        ON CONFLICT (id) DO UPDATE SET
+            review_id = excluded.review_id, body = excluded.body,
+            user_login = excluded.user_login, user_id = excluded.user_id, line = excluded.line,
+            start_line = excluded.start_line, side = excluded.side,
+            commit_id = excluded.commit_id, diff_hunk = excluded.diff_hunk,
+            updated_at = excluded.updated_at
+    ";
+    sqlx::query(query)
+        .bind(comment.id)
+        .bind(comment.pull_request_review_id)
+        .bind(comment.in_reply_to_id)
+        .bind(&comment.body)
+        .bind(comment.user.as_ref().map(|u| &u.login))
+        .bind(comment.user.as_ref().map(|u| u.id))
+        .bind(&comment.path)
+        .bind(comment.line)
+        .bind(comment.start_line)
+        .bind(comment.original_line)
+        .bind(&comment.side)
+        .bind(&comment.commit_id)
+        .bind(&comment.diff_hunk)
+        .bind(&comment.html_url)
+        .bind(&comment.created_at)
+        .bind(&comment.updated_at)
+        .bind(repo_id)
+        .bind(number)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding pull request review comment to database"
+        })?;
+    Ok(())
+}
+
+/// Fetch and store the diff of a pull request, unless it was already stored for its
+/// head commit. Diffs GitHub refuses to render (too large) are skipped.
+async fn archive_github_pull_diff(
+    ctx: &RepoContext,
+    pull: &GitHubApiPull,
+    api: &GitHubAPI,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let query = "SELECT 1 FROM github_pull_diff WHERE pull_id = ? AND head_sha = ?";
+    let is_current = sqlx::query_scalar::<_, i64>(query)
+        .bind(pull.id)
+        .bind(&pull.head.sha)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting pull request diff from database"
+        })?
+        .is_some();
+    if is_current {
+        return Ok(());
+    }
+
+    let diff = match api
+        .fetch_pull_diff(&ctx.user, &ctx.repo, pull.number, pool)
+        .await
+    {
+        Ok(diff) => diff,
+        Err(e) => {
+            error!("{:?}", e);
+            return Ok(());
+        }
+    };
+
+    let query = "
+        INSERT OR REPLACE INTO github_pull_diff (pull_id, head_sha, size, diff)
+        VALUES (?, ?, ?, ?)
+    ";
+    sqlx::query(query)
+        .bind(pull.id)
+        .bind(&pull.head.sha)
+        .bind(diff.len() as i64)
+        .bind(compress_text(&diff)?)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding pull request diff to database"
+        })?;
+
+    Ok(())
+}
+
+/// Fetch and store the pull requests of a repo with their reviews, review comments and
+/// diffs, only those updated since the previous archive if there was one.
+pub async fn archive_github_pulls(
+    ctx: &RepoContext,
+    api: &GitHubAPI,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let start = Instant::now();
+
+    let query = "SELECT pulls_since, review_comments_since FROM github_pull_sync WHERE repo_id = ?";
+    let (pulls_since, review_comments_since) =
+        sqlx::query_as::<_, (Option<String>, Option<String>)>(query)
+            .bind(ctx.repo_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error getting pull request sync from database"
+            })?
+            .unwrap_or_default();
+
+    // The pulls endpoint has no `since`, pages are fetched most recently updated first
+    // until one reaches pull requests that were already archived.
+    let mut pulls = Vec::new();
+    let mut page = 1;
+    loop {
+        let page_pulls = api
+            .fetch_repo_list_page::<GitHubApiPull>(
+                &ctx.user,
+                &ctx.repo,
+                "pulls?state=all&sort=updated&direction=desc",
+                page,
+                pool,
+            )
+            .await?;
+        let is_last = page_pulls.len() < 100;
+        let len = pulls.len();
+        pulls.extend(
+            page_pulls
+                .into_iter()
+                .filter(|pull| pulls_since.as_ref().map_or(true, |s| &pull.updated_at >= s)),
+        );
+        if is_last || pulls.len() - len < 100 {
+            break;
+        }
+        page += 1;
+    }
+
+    for pull in &pulls {
+        add_github_pull(ctx.repo_id, pull, pool).await?;
+
+        let reviews = api
+            .fetch_repo_list_all::<GitHubApiPullReview>(
+                &ctx.user,
+                &ctx.repo,
+                &format!("pulls/{}/reviews", pull.number),
+                pool,
+            )
+            .await?;
+        add_github_pull_reviews(pull.id, &reviews, pool).await?;
+
+        archive_github_pull_diff(ctx, pull, api, pool).await?;
+    }
+
+    let since = review_comments_since
+        .as_ref()
+        .map(|s| format!("&since={s}"))
+        .unwrap_or_default();
+    let review_comments = api
+        .fetch_repo_list_all::<GitHubApiPullReviewComment>(
+            &ctx.user,
+            &ctx.repo,
+            &format!("pulls/comments?sort=updated&direction=asc{since}"),
+            pool,
+        )
+        .await?;
+    for comment in &review_comments {
+        add_github_pull_review_comment(ctx.repo_id, comment, pool).await?;
+    }
+
+    let query = "
+        INSERT INTO github_pull_sync (repo_id, pulls_since, review_comments_since)
+        VALUES (?, ?, ?)
+        ON CONFLICT (repo_id) DO UPDATE SET
+            pulls_since = COALESCE(excluded.pulls_since, pulls_since),
+            review_comments_since = COALESCE(excluded.review_comments_since, review_comments_since)
+    ";
+    sqlx::query(query)
+        .bind(ctx.repo_id)
+        .bind(pulls.iter().map(|p| &p.updated_at).max())
+        .bind(review_comments.iter().map(|c| &c.updated_at).max())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating pull request sync in database"
+        })?;
+
+    info!(
+        "archived {} pull requests and {} review comments of {}/{} in {:?}",
+        pulls.len(),
+        review_comments.len(),
+        ctx.user,
+        ctx.repo,
+        start.elapsed()
+    );
+
+    Ok(())
+}
+
+/// The number of archived open pull requests of a repo.
+pub async fn count_open_github_pulls(repo_id: i64, pool: &SqlitePool) -> AppResult<i64> {
+    let query = "SELECT COUNT(*) FROM github_pull WHERE repo_id = ? AND state = 'open'";
+    let count = sqlx::query_scalar::<_, i64>(query)
+        .bind(repo_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error counting pull requests in database"
+        })?;
+    Ok(count)
+}
+
+/// The archived pull requests of a repo, most recently updated first.
+pub async fn get_github_pulls(
+    repo_id: i64,
+    state: Option<&str>,
+    offset: i64,
+    limit: i64,
+    pool: &SqlitePool,
+) -> AppResult<Vec<GitHubPullPreview>> {
+    let query = "
+        SELECT
+            number, title, state, draft, user_login, head_ref, base_ref, created_at,
+            updated_at, closed_at, merged_at
+        FROM github_pull
+        WHERE repo_id = ? AND (? IS NULL OR state = ?)
+        ORDER BY updated_at DESC
+        LIMIT ? OFFSET ?
+    ";
+    let pulls = sqlx::query_as::<_, GitHubPullPreview>(query)
+        .bind(repo_id)
+        .bind(state)
+        .bind(state)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting pull requests from database"
+        })?;
+    Ok(pulls)
+}
+
+/// An archived pull request with its reviews, review comments and diff.
+pub async fn get_github_pull(
+    repo_id: i64,
+    number: i64,
+    pool: &SqlitePool,
+) -> AppResult<GitHubFullPull> {
+    let query = "
+        SELECT
+            id, number, title, body, state, draft, user_login, head_ref, head_sha, base_ref,
+            base_sha, html_url, created_at, updated_at, closed_at, merged_at
+        FROM github_pull
+        WHERE repo_id = ? AND number = ?
+    ";
+    let pull = sqlx::query_as::<_, GitHubPull>(query)
+        .bind(repo_id)
+        .bind(number)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting pull request from database"
+        })?
+        .ok_or("Pull request not found")?;
+
+    let query = "
+        SELECT id, body, state, user_login, commit_id, html_url, submitted_at
+        FROM github_pull_review
+        WHERE pull_id = ?
+        ORDER BY submitted_at
+    ";
+    let reviews = sqlx::query_as::<_, GitHubPullReview>(query)
+        .bind(pull.id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting pull request reviews from database"
+        })?;
+
+    let query = "
+        SELECT
+            id, review_id, in_reply_to_id, body, user_login, path, line, start_line,
+            original_line, side, commit_id, diff_hunk, html_url, created_at, updated_at
+        FROM github_pull_review_comment
+        WHERE pull_id = ?
+        ORDER BY path, COALESCE(line, original_line), created_at
+    ";
+    let review_comments = sqlx::query_as::<_, GitHubPullReviewComment>(query)
+        .bind(pull.id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting pull request review comments from database"
+        })?;
+
+    let query = "SELECT diff FROM github_pull_diff WHERE pull_id = ?";
+    let diff = sqlx::query_scalar::<_, Vec<u8>>(query)
+        .bind(pull.id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting pull request diff from database"
+        })?
+        .map(|diff| decompress_text(&diff))
+        .transpose()?;
+
+    Ok(GitHubFullPull {
+        pull,
+        reviews,
+        review_comments,
+        diff,
+    })
+}
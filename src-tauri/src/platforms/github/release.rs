@@ -0,0 +1,490 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use chrono::Utc;
+use glob::Pattern;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{prelude::FromRow, SqlitePool};
+use tokio::{fs, io::AsyncWriteExt, time::Instant};
+use tracing::{error, info, warn};
+
+use crate::{
+    error::{AppError, AppResult},
+    platforms::{
+        github::{
+            api::GitHubAPI,
+            api_models::{GitHubApiRelease, GitHubApiReleaseAsset},
+        },
+        provider::RepoContext,
+    },
+    utils::dirs::{ensure_dir, get_data_dir},
+};
+
+/// Where the downloaded release assets of a repo are stored.
+pub fn get_release_asset_dir(repo_id: i64) -> PathBuf {
+    get_data_dir().join(format!("releases/{repo_id}"))
+}
+
+/// Assets are stored under their ID, names are only unique within a release.
+fn get_release_asset_path(repo_id: i64, asset_id: i64, name: &str) -> PathBuf {
+    get_release_asset_dir(repo_id)
+        .join(asset_id.to_string())
+        .join(name)
+}
+
+#[derive(Serialize, FromRow)]
+pub struct GitHubRelease {
+    pub id: i64,
+    pub tag_name: String,
+    pub name: Option<String>,
+    /// Markdown.
+    pub body: Option<String>,
+    pub draft: bool,
+    pub prerelease: bool,
+    pub author_login: Option<String>,
+    pub html_url: String,
+    pub created_at: String,
+    pub published_at: Option<String>,
+    #[sqlx(skip)]
+    pub assets: Vec<GitHubReleaseAsset>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct GitHubReleaseAsset {
+    #[serde(skip)]
+    pub release_id: i64,
+    pub id: i64,
+    pub name: String,
+    pub label: Option<String>,
+    pub content_type: String,
+    pub size: i64,
+    pub download_count: i64,
+    pub browser_download_url: String,
+    /// The checksum GitHub reports, e.g. 'sha256:{hex}'.
+    pub digest: Option<String>,
+    /// The checksum of the downloaded file.
+    pub sha256: Option<String>,
+    pub downloaded_at: Option<String>,
+    /// The downloaded file, if the asset was downloaded.
+    #[sqlx(skip)]
+    pub path: Option<String>,
+}
+
+/// Which release assets of a repo are downloaded.
+#[derive(FromRow)]
+struct ReleaseAssetOptions {
+    download_release_assets: bool,
+    release_asset_max_size: i64,
+}
+
+async fn add_github_release(
+    repo_id: i64,
+    release: &GitHubApiRelease,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let query = "
+        INSERT INTO github_release (
+            id, repo_id, tag_name, name, body, draft, prerelease, author_login, html_url,
+            created_at, published_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (id) DO UPDATE SET
+            tag_name = excluded.tag_name, name = excluded.name, body = excluded.body,
+            draft = excluded.draft, prerelease = excluded.prerelease,
+            author_login = excluded.author_login, html_url = excluded.html_url,
+            published_at = excluded.published_at
+    ";
+    sqlx::query(query)
+        .bind(release.id)
+        .bind(repo_id)
+        .bind(&release.tag_name)
+        .bind(&release.name)
+        .bind(&release.body)
+        .bind(release.draft)
+        .bind(release.prerelease)
+        .bind(release.author.as_ref().map(|a| &a.login))
+        .bind(&release.html_url)
+        .bind(&release.created_at)
+        .bind(&release.published_at)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding release to database"
+        })?;
+
+    for asset in &release.assets {
+        // A re-uploaded asset keeps its ID, its previous download is invalidated.
+        let query = "
+            INSERT INTO github_release_asset (
+                id, release_id, name, label, content_type, size, download_count,
+                browser_download_url, digest, created_at, updated_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (id) DO UPDATE SET
+                name = excluded.name, label = excluded.label,
+                content_type = excluded.content_type, size = excluded.size,
+                download_count = excluded.download_count,
+                browser_download_url = excluded.browser_download_url, digest = excluded.digest,
+                updated_at = excluded.updated_at,
+                sha256 = CASE WHEN updated_at = excluded.updated_at THEN sha256 END,
+                downloaded_at = CASE WHEN updated_at = excluded.updated_at THEN downloaded_at END
+        ";
+        sqlx::query(query)
+            .bind(asset.id)
+            .bind(release.id)
+            .bind(&asset.name)
+            .bind(&asset.label)
+            .bind(&asset.content_type)
+            .bind(asset.size)
+            .bind(asset.download_count)
+            .bind(&asset.browser_download_url)
+            .bind(&asset.digest)
+            .bind(&asset.created_at)
+            .bind(&asset.updated_at)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding release asset to database"
+            })?;
+    }
+
+    Ok(())
+}
+
+/// Remove the releases and assets that were deleted on GitHub, with their downloads.
+async fn remove_deleted_github_releases(
+    repo_id: i64,
+    releases: &[GitHubApiRelease],
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let release_ids: Vec<i64> = releases.iter().map(|r| r.id).collect();
+    let asset_ids: Vec<i64> = releases
+        .iter()
+        .flat_map(|r| r.assets.iter().map(|a| a.id))
+        .collect();
+
+    let query = "
+        DELETE FROM github_release_asset
+        WHERE release_id IN (SELECT id FROM github_release WHERE repo_id = ?)
+            AND id NOT IN (SELECT value FROM json_each(?))
+        RETURNING id
+    ";
+    let removed_asset_ids = sqlx::query_scalar::<_, i64>(query)
+        .bind(repo_id)
+        .bind(serde_json::to_string(&asset_ids)?)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing release assets from database"
+        })?;
+
+    let query = "
+        DELETE FROM github_release
+        WHERE repo_id = ? AND id NOT IN (SELECT value FROM json_each(?))
+    ";
+    sqlx::query(query)
+        .bind(repo_id)
+        .bind(serde_json::to_string(&release_ids)?)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing releases from database"
+        })?;
+
+    for asset_id in removed_asset_ids {
+        let dir = get_release_asset_dir(repo_id).join(asset_id.to_string());
+        if fs::try_exists(&dir).await? {
+            fs::remove_dir_all(&dir).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Stream an asset to its file, recording its SHA-256 checksum. The download is
+/// written to a `.part` file first, so an interrupted one is never taken as complete.
+async fn download_github_release_asset(
+    ctx: &RepoContext,
+    asset: &GitHubApiReleaseAsset,
+    max_size: i64,
+    api: &GitHubAPI,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let start = Instant::now();
+
+    let path = get_release_asset_path(ctx.repo_id, asset.id, &asset.name);
+    let part_path = path.with_file_name(format!("{}.part", asset.name));
+    ensure_dir(path.parent().unwrap()).await?;
+
+    let mut res = api
+        .fetch_release_asset(&ctx.user, &ctx.repo, asset.id, pool)
+        .await?;
+    let mut file = fs::File::create(&part_path).await?;
+    let mut hasher = Sha256::new();
+    let mut size = 0;
+    while let Some(chunk) = res.chunk().await? {
+        size += chunk.len() as i64;
+        if size > max_size {
+            drop(file);
+            fs::remove_file(&part_path).await?;
+            return AppError::new(&format!(
+                "Release asset \"{}\" is larger than the maximum size",
+                asset.name
+            ));
+        }
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    drop(file);
+
+    let sha256 = format!("{:x}", hasher.finalize());
+    if let Some(expected) = asset
+        .digest
+        .as_ref()
+        .and_then(|d| d.strip_prefix("sha256:"))
+    {
+        if !expected.eq_ignore_ascii_case(&sha256) {
+            fs::remove_file(&part_path).await?;
+            return AppError::new(&format!(
+                "Checksum of release asset \"{}\" doesn't match",
+                asset.name
+            ));
+        }
+    }
+    fs::rename(&part_path, &path).await?;
+
+    let query = "UPDATE github_release_asset SET sha256 = ?, downloaded_at = ? WHERE id = ?";
+    sqlx::query(query)
+        .bind(&sha256)
+        .bind(Utc::now().to_rfc3339())
+        .bind(asset.id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating release asset in database"
+        })?;
+
+    info!(
+        "downloaded release asset \"{}\" of {}/{} in {:?}",
+        asset.name,
+        ctx.user,
+        ctx.repo,
+        start.elapsed()
+    );
+
+    Ok(())
+}
+
+/// Download the release assets of a repo that match its globs, weren't downloaded yet
+/// and aren't larger than the repo's maximum size.
+async fn download_github_release_assets(
+    ctx: &RepoContext,
+    releases: &[GitHubApiRelease],
+    api: &GitHubAPI,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let query = "
+        SELECT download_release_assets, release_asset_max_size
+        FROM repo
+        WHERE id = ?
+    ";
+    let options = sqlx::query_as::<_, ReleaseAssetOptions>(query)
+        .bind(ctx.repo_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository from database"
+        })?;
+    if !options.download_release_assets {
+        return Ok(());
+    }
+
+    let globs = get_release_asset_globs(ctx.repo_id, pool).await?;
+    let patterns: Vec<Pattern> = globs
+        .iter()
+        .filter_map(|glob| Pattern::new(glob).ok())
+        .collect();
+
+    let query = "SELECT id FROM github_release_asset WHERE id = ? AND downloaded_at IS NOT NULL";
+    for asset in releases.iter().flat_map(|r| &r.assets) {
+        if asset.size > options.release_asset_max_size
+            || !(patterns.is_empty() || patterns.iter().any(|p| p.matches(&asset.name)))
+        {
+            continue;
+        }
+
+        let downloaded = sqlx::query_scalar::<_, i64>(query)
+            .bind(asset.id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error getting release asset from database"
+            })?
+            .is_some();
+        if downloaded {
+            continue;
+        }
+
+        // A failed asset is retried on the next sync, the others are still downloaded.
+        if let Err(e) =
+            download_github_release_asset(ctx, asset, options.release_asset_max_size, api, pool)
+                .await
+        {
+            warn!("{:?}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Fetch and store the releases of a repo with their asset metadata, downloading the
+/// assets if enabled for the repo.
+pub async fn archive_github_releases(
+    ctx: &RepoContext,
+    api: &GitHubAPI,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let start = Instant::now();
+
+    let releases = api
+        .fetch_repo_list_all::<GitHubApiRelease>(&ctx.user, &ctx.repo, "releases", pool)
+        .await?;
+
+    for release in &releases {
+        add_github_release(ctx.repo_id, release, pool).await?;
+    }
+    remove_deleted_github_releases(ctx.repo_id, &releases, pool).await?;
+
+    download_github_release_assets(ctx, &releases, api, pool).await?;
+
+    info!(
+        "archived {} releases of {}/{} in {:?}",
+        releases.len(),
+        ctx.user,
+        ctx.repo,
+        start.elapsed()
+    );
+
+    Ok(())
+}
+
+pub async fn get_release_asset_globs(repo_id: i64, pool: &SqlitePool) -> AppResult<Vec<String>> {
+    let query = "SELECT glob FROM repo_release_asset_glob WHERE repo_id = ? ORDER BY glob";
+    let globs = sqlx::query_scalar::<_, String>(query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting release asset globs from database"
+        })?;
+    Ok(globs)
+}
+
+/// Replace the asset name globs of a repo, failing on invalid ones.
+pub async fn set_release_asset_globs(
+    repo_id: i64,
+    globs: &[String],
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    if let Some(glob) = globs.iter().find(|glob| Pattern::new(glob).is_err()) {
+        return AppError::new(&format!("Invalid asset name pattern \"{glob}\""));
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("{:?}", e);
+        "Error updating release asset globs in database"
+    })?;
+
+    sqlx::query("DELETE FROM repo_release_asset_glob WHERE repo_id = ?")
+        .bind(repo_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing release asset globs from database"
+        })?;
+
+    for glob in globs {
+        let query = "INSERT OR IGNORE INTO repo_release_asset_glob (repo_id, glob) VALUES (?, ?)";
+        sqlx::query(query)
+            .bind(repo_id)
+            .bind(glob)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding release asset glob to database"
+            })?;
+    }
+
+    tx.commit().await.map_err(|e| {
+        error!("{:?}", e);
+        "Error updating release asset globs in database"
+    })?;
+
+    Ok(())
+}
+
+/// The archived releases of a repo with their assets, most recently published first.
+pub async fn get_github_releases(repo_id: i64, pool: &SqlitePool) -> AppResult<Vec<GitHubRelease>> {
+    let query = "
+        SELECT
+            id, tag_name, name, body, draft, prerelease, author_login, html_url, created_at,
+            published_at
+        FROM github_release
+        WHERE repo_id = ?
+        ORDER BY COALESCE(published_at, created_at) DESC
+    ";
+    let mut releases = sqlx::query_as::<_, GitHubRelease>(query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting releases from database"
+        })?;
+
+    let query = "
+        SELECT
+            a.release_id, a.id, a.name, a.label, a.content_type, a.size, a.download_count,
+            a.browser_download_url, a.digest, a.sha256, a.downloaded_at
+        FROM github_release_asset AS a
+        JOIN github_release AS r ON r.id = a.release_id
+        WHERE r.repo_id = ?
+        ORDER BY a.name
+    ";
+    let assets = sqlx::query_as::<_, GitHubReleaseAsset>(query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting release assets from database"
+        })?;
+
+    let mut assets_by_release: HashMap<i64, Vec<GitHubReleaseAsset>> = HashMap::new();
+    for mut asset in assets {
+        if asset.downloaded_at.is_some() {
+            let path = get_release_asset_path(repo_id, asset.id, &asset.name);
+            asset.path = Some(path.to_string_lossy().into_owned());
+        }
+        assets_by_release
+            .entry(asset.release_id)
+            .or_default()
+            .push(asset);
+    }
+    for release in &mut releases {
+        release.assets = assets_by_release.remove(&release.id).unwrap_or_default();
+    }
+
+    Ok(releases)
+}
@@ -0,0 +1,682 @@
+use api::GitLabAPI;
+use api_models::{GitLabApiBranch, GitLabApiProject, GitLabApiTag};
+use async_trait::async_trait;
+use models::{GitLabNamespace, GitLabProject, GitLabProjectData, GitLabUserData};
+use sqlx::{prelude::FromRow, SqlitePool};
+use tauri::AppHandle;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    commands::{
+        repo::{AddRepoProgress, DbPlatformRepo, RepoPreviewOwner},
+        user::PlatformUserData,
+    },
+    error::AppResult,
+    platforms::{
+        provider::{CloneRemote, FetchedTree, PlatformProvider, RepoContext, RepoRef},
+        Platform,
+    },
+    repo::{get_repo_stats, NewRepoTreeItem, PlatformRepoData, RepoStats},
+    user::{add_user, add_user_avatar, delete_user, get_user_avatar_path, get_user_id},
+    utils::data::is_commit_sha,
+};
+
+pub mod api;
+pub mod api_models;
+pub mod models;
+
+async fn add_gitlab_project_topics(
+    repo_id: i64,
+    topics: Vec<String>,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let delete_query = "DELETE FROM gitlab_project_topic WHERE repo_id = ?";
+    sqlx::query(delete_query)
+        .bind(repo_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing GitLab project topics from database"
+        })?;
+
+    for topic in topics {
+        let topic_query = "
+            INSERT INTO gitlab_project_topic (
+                repo_id, topic
+            )
+            VALUES (?, ?)
+        ";
+        sqlx::query(topic_query)
+            .bind(repo_id)
+            .bind(topic)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding GitLab project topic to database"
+            })?;
+    }
+
+    Ok(())
+}
+
+async fn add_gitlab_project_row(
+    repo_id: i64,
+    project: &GitLabApiProject,
+    default_branch: &str,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let query = "
+        INSERT INTO gitlab_project (
+            repo_id, id, name, name_with_namespace, path,
+            path_with_namespace, description, default_branch, visibility, web_url,
+            star_count, forks_count, open_issues_count, archived, empty_repo,
+            license_key, license_name, namespace_id, created_at, last_activity_at
+        )
+        VALUES (
+            ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?
+        )
+        ON CONFLICT (repo_id) DO UPDATE SET
+            id = excluded.id, name = excluded.name,
+            name_with_namespace = excluded.name_with_namespace, path = excluded.path,
+            path_with_namespace = excluded.path_with_namespace,
+            description = excluded.description, default_branch = excluded.default_branch,
+            visibility = excluded.visibility, web_url = excluded.web_url,
+            star_count = excluded.star_count, forks_count = excluded.forks_count,
+            open_issues_count = excluded.open_issues_count, archived = excluded.archived,
+            empty_repo = excluded.empty_repo, license_key = excluded.license_key,
+            license_name = excluded.license_name, namespace_id = excluded.namespace_id,
+            created_at = excluded.created_at, last_activity_at = excluded.last_activity_at
+    ";
+    sqlx::query(query)
+        .bind(repo_id)
+        .bind(project.id)
+        .bind(&project.name)
+        .bind(&project.name_with_namespace)
+        .bind(&project.path)
+        .bind(&project.path_with_namespace)
+        .bind(&project.description)
+        .bind(default_branch)
+        .bind(&project.visibility)
+        .bind(&project.web_url)
+        .bind(project.star_count)
+        .bind(project.forks_count)
+        .bind(project.open_issues_count)
+        .bind(project.archived)
+        .bind(project.empty_repo)
+        .bind(project.license.as_ref().map(|l| &l.key))
+        .bind(project.license.as_ref().map(|l| &l.name))
+        .bind(project.namespace.id)
+        .bind(&project.created_at)
+        .bind(&project.last_activity_at)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding GitLab project to database"
+        })?;
+
+    Ok(())
+}
+
+/// Add the namespace (user or group) owning the project as a user, the project response
+/// already includes everything needed.
+async fn add_gitlab_namespace_user(
+    instance_id: Option<i64>,
+    user: &str,
+    repo: &str,
+    api: &GitLabAPI,
+    pool: &SqlitePool,
+    app: &AppHandle,
+) -> AppResult<()> {
+    AddRepoProgress::Owner.send("gitlab", user, repo, 0, 0, 4, app);
+
+    if get_user_id("gitlab", instance_id, user, pool)
+        .await?
+        .is_some()
+    {
+        AddRepoProgress::Owner.send("gitlab", user, repo, 100, 1, 1, app);
+        return Ok(());
+    }
+
+    let namespace = api.fetch_project(user, repo, pool).await?.namespace;
+    AddRepoProgress::Owner.send("gitlab", user, repo, 25, 1, 4, app);
+
+    let user_id = add_user("gitlab", instance_id, user, pool).await?;
+    // The user is removed again if anything fails, so the next add doesn't skip the owner.
+    let added: AppResult<()> = async {
+        AddRepoProgress::Owner.send("gitlab", user, repo, 50, 2, 4, app);
+
+        let query = "
+            INSERT INTO gitlab_namespace (
+                user_id, id, name, path, kind,
+                full_path, avatar_url, web_url
+            )
+            VALUES (
+                ?, ?, ?, ?, ?,
+                ?, ?, ?
+            )
+        ";
+        sqlx::query(query)
+            .bind(user_id)
+            .bind(namespace.id)
+            .bind(&namespace.name)
+            .bind(&namespace.path)
+            .bind(&namespace.kind)
+            .bind(&namespace.full_path)
+            .bind(&namespace.avatar_url)
+            .bind(&namespace.web_url)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding GitLab namespace to database"
+            })?;
+
+        AddRepoProgress::Owner.send("gitlab", user, repo, 75, 3, 4, app);
+
+        if let Some(avatar_url) = namespace.avatar_url {
+            // Self-hosted instances return avatar paths relative to the instance.
+            let avatar_url = if avatar_url.starts_with('/') {
+                format!("{}{avatar_url}", api.base_url())
+            } else {
+                avatar_url
+            };
+            add_user_avatar(user_id, "gitlab", &avatar_url, pool).await?;
+        }
+        Ok(())
+    }
+    .await;
+    if let Err(e) = added {
+        if let Err(e) = delete_user(user_id, pool).await {
+            error!("{:?}", e);
+        }
+        return Err(e);
+    }
+
+    AddRepoProgress::Owner.send("gitlab", user, repo, 100, 4, 4, app);
+
+    Ok(())
+}
+
+/// Add the project metadata, returning the default branch.
+pub async fn add_gitlab_project(
+    repo_id: i64,
+    user: &str,
+    repo: &str,
+    api: &GitLabAPI,
+    pool: &SqlitePool,
+    app: &AppHandle,
+) -> AppResult<String> {
+    AddRepoProgress::Metadata.send("gitlab", user, repo, 0, 0, 2, app);
+    let project = api.fetch_project(user, repo, pool).await?;
+    AddRepoProgress::Metadata.send("gitlab", user, repo, 50, 1, 2, app);
+
+    let Some(default_branch) = project.default_branch.clone() else {
+        return Err("GitLab project has an empty repository".into());
+    };
+
+    add_gitlab_project_row(repo_id, &project, &default_branch, pool).await?;
+    add_gitlab_project_topics(repo_id, project.topics, pool).await?;
+    AddRepoProgress::Metadata.send("gitlab", user, repo, 100, 2, 2, app);
+
+    Ok(default_branch)
+}
+
+#[derive(FromRow)]
+struct GitLabProjectPreview {
+    description: Option<String>,
+    star_count: i32,
+    forks_count: i32,
+    open_issues_count: Option<i32>,
+    visibility: String,
+}
+
+#[derive(FromRow)]
+struct DbProjectOwner {
+    id: i64,
+    user: String,
+}
+
+pub async fn get_gitlab_project_preview(
+    repo_id: i64,
+    pool: &SqlitePool,
+) -> AppResult<(DbPlatformRepo, RepoPreviewOwner)> {
+    let start = Instant::now();
+
+    let query = "SELECT description, star_count, forks_count, open_issues_count, visibility FROM gitlab_project WHERE repo_id = ?";
+    let project = sqlx::query_as::<_, GitLabProjectPreview>(query)
+        .bind(repo_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting GitLab project from database"
+        })?;
+
+    let owner_query = "
+        SELECT u.id, u.user
+        FROM repo r
+        JOIN user u ON u.platform = r.platform AND u.instance_id IS r.instance_id AND u.user = r.user
+        WHERE r.id = ?
+    ";
+    let owner = sqlx::query_as::<_, DbProjectOwner>(owner_query)
+        .bind(repo_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting GitLab project owner from database"
+        })?;
+
+    let avatar = get_user_avatar_path(owner.id, pool)
+        .await?
+        .unwrap_or_default();
+
+    info!(
+        "got gitlab project preview from database in {:?}",
+        start.elapsed()
+    );
+
+    Ok((
+        DbPlatformRepo {
+            description: project.description.unwrap_or_default(),
+            stars: project.star_count,
+            forks: project.forks_count,
+            issues: project.open_issues_count.unwrap_or(0),
+            pull_requests: 0,
+            visibility: project.visibility,
+        },
+        RepoPreviewOwner {
+            id: owner.id,
+            user: owner.user,
+            avatar,
+        },
+    ))
+}
+
+pub async fn get_gitlab_project(repo_id: i64, pool: &SqlitePool) -> AppResult<GitLabProjectData> {
+    let query = "SELECT * FROM gitlab_project WHERE repo_id = ?";
+    let project = sqlx::query_as::<_, GitLabProject>(query)
+        .bind(repo_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting gitlab project from database"
+        })?;
+
+    let namespace_query = "
+        SELECT n.*
+        FROM gitlab_namespace n
+        JOIN user u ON u.id = n.user_id
+        JOIN repo r ON r.instance_id IS u.instance_id
+        WHERE r.id = ? AND n.id = ?
+    ";
+    let namespace = sqlx::query_as::<_, GitLabNamespace>(namespace_query)
+        .bind(repo_id)
+        .bind(project.namespace_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting gitlab namespace from database"
+        })?;
+
+    let topics_query = "SELECT topic FROM gitlab_project_topic WHERE repo_id = ?";
+    let topics = sqlx::query_scalar::<_, String>(topics_query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting gitlab topics from database"
+        })?;
+
+    Ok(GitLabProjectData {
+        project,
+        namespace,
+        topics,
+    })
+}
+
+pub async fn get_gitlab_user(user_id: i64, pool: &SqlitePool) -> AppResult<GitLabUserData> {
+    let query = "SELECT * FROM gitlab_namespace WHERE user_id = ?";
+    let namespace = sqlx::query_as::<_, GitLabNamespace>(query)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting gitlab namespace from database"
+        })?;
+
+    Ok(GitLabUserData { namespace })
+}
+
+#[async_trait]
+impl PlatformProvider for GitLabAPI {
+    fn platform(&self) -> Platform {
+        Platform::GitLab
+    }
+
+    fn set_token(&self, token: Option<String>) {
+        GitLabAPI::set_token(self, token);
+    }
+
+    async fn check_core_rate_limit(&self, pool: &SqlitePool) -> AppResult<()> {
+        self.check_rate_limit(pool).await
+    }
+
+    async fn add_repo(
+        &self,
+        ctx: &RepoContext,
+        pool: &SqlitePool,
+        app: &AppHandle,
+    ) -> AppResult<String> {
+        add_gitlab_project(ctx.repo_id, &ctx.user, &ctx.repo, self, pool, app).await
+    }
+
+    async fn fetch_tree(
+        &self,
+        ctx: &RepoContext,
+        rev: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<FetchedTree> {
+        let commit = if is_commit_sha(rev) {
+            rev.to_string()
+        } else {
+            self.fetch_branch(&ctx.user, &ctx.repo, rev, pool)
+                .await?
+                .commit
+                .id
+        };
+        let tree = self
+            .fetch_repo_tree(&ctx.user, &ctx.repo, &commit, pool)
+            .await?;
+
+        // GitLab never truncates a tree, it is paginated instead.
+        Ok(FetchedTree {
+            sha: commit.clone(),
+            rev: commit,
+            truncated: false,
+            items: tree.into_iter().map(NewRepoTreeItem::from).collect(),
+        })
+    }
+
+    async fn fetch_refs(&self, ctx: &RepoContext, pool: &SqlitePool) -> AppResult<Vec<RepoRef>> {
+        let branches: Vec<GitLabApiBranch> = self
+            .fetch_project_list(&ctx.user, &ctx.repo, "repository/branches", pool)
+            .await?;
+        let tags: Vec<GitLabApiTag> = self
+            .fetch_project_list(&ctx.user, &ctx.repo, "repository/tags", pool)
+            .await?;
+
+        let branches = branches
+            .into_iter()
+            .map(|branch| RepoRef::branch(branch.name, branch.commit.id, branch.protected));
+        let tags = tags.into_iter().map(|tag| {
+            let annotated = tag.target != tag.commit.id;
+            RepoRef {
+                annotated: Some(annotated),
+                tagged_at: tag.created_at,
+                message: tag.message.filter(|_| annotated),
+                ..RepoRef::tag(tag.name, tag.commit.id)
+            }
+        });
+        Ok(branches.chain(tags).collect())
+    }
+
+    async fn fetch_file(
+        &self,
+        ctx: &RepoContext,
+        rev: &str,
+        path: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<String> {
+        self.fetch_repo_readme(&ctx.user, &ctx.repo, rev, path, pool)
+            .await
+    }
+
+    async fn fetch_blob(
+        &self,
+        ctx: &RepoContext,
+        _rev: &str,
+        _path: &str,
+        sha: Option<&str>,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<u8>> {
+        let sha = sha.ok_or("Blob SHA is unknown")?;
+        self.fetch_repo_blob(&ctx.user, &ctx.repo, sha, pool).await
+    }
+
+    async fn add_owner(
+        &self,
+        ctx: &RepoContext,
+        pool: &SqlitePool,
+        app: &AppHandle,
+    ) -> AppResult<()> {
+        add_gitlab_namespace_user(ctx.instance_id, &ctx.user, &ctx.repo, self, pool, app).await
+    }
+
+    fn clone_remote(&self, ctx: &RepoContext) -> CloneRemote {
+        CloneRemote {
+            url: format!("{}/{}/{}.git", self.base_url(), ctx.user, ctx.repo),
+            credentials: self.token().map(|t| ("oauth2".to_string(), t)),
+        }
+    }
+
+    async fn get_repo(&self, repo_id: i64, pool: &SqlitePool) -> AppResult<PlatformRepoData> {
+        Ok(PlatformRepoData::GitLab(
+            get_gitlab_project(repo_id, pool).await?,
+        ))
+    }
+
+    async fn get_repo_stats(
+        &self,
+        repo_id: i64,
+        pool: &SqlitePool,
+    ) -> AppResult<Option<RepoStats>> {
+        let query = "
+            SELECT
+                star_count AS stars, forks_count AS forks, NULL AS watchers,
+                open_issues_count AS open_issues, default_branch, archived, visibility,
+                license_key AS license,
+                (
+                    SELECT group_concat(topic, ',') FROM gitlab_project_topic t
+                    WHERE t.repo_id = p.repo_id
+                ) AS topics
+            FROM gitlab_project p
+            WHERE p.repo_id = ?
+        ";
+        get_repo_stats(query, repo_id, pool).await
+    }
+
+    async fn get_repo_preview(
+        &self,
+        repo_id: i64,
+        pool: &SqlitePool,
+    ) -> AppResult<(DbPlatformRepo, RepoPreviewOwner)> {
+        get_gitlab_project_preview(repo_id, pool).await
+    }
+
+    async fn get_user(&self, user_id: i64, pool: &SqlitePool) -> AppResult<PlatformUserData> {
+        Ok(PlatformUserData::GitLab(
+            get_gitlab_user(user_id, pool).await?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::{
+        repo::{add_repo_tree, refresh_repo_readme},
+        utils::{dirs::get_data_dir, testing::memory_pool},
+    };
+
+    const COMMIT: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    fn tree_item(path: &str, r#type: &str, sha: &str) -> serde_json::Value {
+        let name = path.rsplit('/').next().unwrap();
+        let mode = if r#type == "tree" { "040000" } else { "100644" };
+        json!({ "id": sha, "name": name, "type": r#type, "path": path, "mode": mode })
+    }
+
+    async fn mount_tree_page(
+        server: &MockServer,
+        page: &str,
+        next_page: &str,
+        items: serde_json::Value,
+    ) {
+        Mock::given(method("GET"))
+            .and(path("/api/v4/projects/group%2Fproject/repository/tree"))
+            .and(query_param("ref", COMMIT))
+            .and(query_param("page", page))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("X-Next-Page", next_page)
+                    .set_body_json(items),
+            )
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    async fn add_test_repo(repo: &str, pool: &SqlitePool) -> RepoContext {
+        let query = "
+            INSERT INTO repo (platform, user, repo, created_at, updated_at)
+            VALUES ('gitlab', 'group', ?, '', '')
+        ";
+        let repo_id = sqlx::query(query)
+            .bind(repo)
+            .execute(pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        RepoContext {
+            repo_id,
+            instance_id: None,
+            user: "group".to_string(),
+            repo: repo.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_tree_follows_tree_pages() {
+        let server = MockServer::start().await;
+        let first_page = json!([
+            tree_item("src", "tree", "t1"),
+            tree_item("src/main.rs", "blob", "b1"),
+        ]);
+        mount_tree_page(&server, "1", "2", first_page).await;
+        let last_page = json!([tree_item("README.md", "blob", "b2")]);
+        mount_tree_page(&server, "2", "", last_page).await;
+
+        let pool = memory_pool().await;
+        let ctx = add_test_repo("project", &pool).await;
+        let api = GitLabAPI::new(&server.uri(), None);
+        let tree = api.fetch_tree(&ctx, COMMIT, &pool).await.unwrap();
+
+        assert_eq!(tree.rev, COMMIT);
+        assert!(!tree.truncated);
+        let paths: Vec<&str> = tree.items.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(paths, ["src", "src/main.rs", "README.md"]);
+    }
+
+    #[tokio::test]
+    async fn readme_is_stored_with_its_images() {
+        let server = MockServer::start().await;
+        // Markdown images are only fetched over HTTPS, unlike HTML ones.
+        let readme = format!(
+            "# Project\n\n<img src=\"{}/logo.png\" alt=\"logo\">\n",
+            server.uri()
+        );
+        Mock::given(method("GET"))
+            .and(path(
+                "/api/v4/projects/group%2Freadme-project/repository/files/README.md/raw",
+            ))
+            .and(query_param("ref", COMMIT))
+            .respond_with(ResponseTemplate::new(200).set_body_string(readme))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/logo.png"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", "image/png")
+                    .set_body_bytes(b"\x89PNG\r\n\x1a\n".to_vec()),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let pool = memory_pool().await;
+        let ctx = add_test_repo("readme-project", &pool).await;
+        let api = GitLabAPI::new(&server.uri(), None);
+        let app = tauri::test::mock_app();
+        let tree = FetchedTree {
+            sha: COMMIT.to_string(),
+            rev: COMMIT.to_string(),
+            truncated: false,
+            items: vec![NewRepoTreeItem {
+                path: "README.md".to_string(),
+                mode: "100644".to_string(),
+                r#type: "blob".to_string(),
+                sha: Some("b1".to_string()),
+                size: None,
+            }],
+        };
+        let (user, repo) = (ctx.user.as_str(), ctx.repo.as_str());
+        add_repo_tree(
+            ctx.repo_id,
+            "main",
+            true,
+            &tree,
+            "gitlab",
+            user,
+            repo,
+            &pool,
+            app.handle(),
+        )
+        .await
+        .unwrap();
+
+        refresh_repo_readme(&api, &ctx, COMMIT, &pool, app.handle())
+            .await
+            .unwrap();
+
+        let query = "SELECT id, ext FROM repo_readme_asset WHERE repo_id = ?";
+        let (asset_id, ext) = sqlx::query_as::<_, (i64, Option<String>)>(query)
+            .bind(ctx.repo_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(ext.as_deref(), Some("png"));
+
+        let query = "SELECT content FROM repo_readme WHERE repo_id = ?";
+        let content = sqlx::query_scalar::<_, String>(query)
+            .bind(ctx.repo_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let asset_path = get_data_dir()
+            .join("assets/repos/group/readme-project/readme")
+            .join(format!("{asset_id}.png"));
+        assert!(content.starts_with("# Project"));
+        assert!(content.contains(&format!("src=\"{}\"", asset_path.display())));
+        assert!(asset_path.exists());
+    }
+}
@@ -0,0 +1,474 @@
+use std::sync::RwLock;
+
+use reqwest::RequestBuilder;
+use serde::de::DeserializeOwned;
+use sqlx::SqlitePool;
+use tauri::http::HeaderMap;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    error::{AppError, AppResult},
+    platforms::{
+        gitlab::api_models::{GitLabApiBranch, GitLabApiProject, GitLabApiTreeItem},
+        Platform,
+    },
+    utils::{
+        data::{parse_body, parse_header, parse_header_num, url_encode},
+        rate_limit::{check_rate_limit, send_with_retry, update_rate_limit, RateLimitKey},
+    },
+};
+
+/// Client for the GitLab REST API (v4) of gitlab.com or a self-hosted instance.
+pub struct GitLabAPI {
+    client: reqwest::Client,
+    /// The instance URL, e.g. `https://gitlab.com`.
+    base_url: String,
+    token: RwLock<Option<String>>,
+}
+
+impl GitLabAPI {
+    pub const DEFAULT_BASE_URL: &'static str = "https://gitlab.com";
+
+    pub fn new(base_url: &str, token: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: RwLock::new(token.filter(|t| !t.is_empty())),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Replace the token used for subsequent requests.
+    pub fn set_token(&self, token: Option<String>) {
+        *self.token.write().unwrap() = token.filter(|t| !t.is_empty());
+    }
+
+    pub fn token(&self) -> Option<String> {
+        self.token.read().unwrap().clone()
+    }
+
+    fn get(&self, path: &str) -> RequestBuilder {
+        let req = self
+            .client
+            .get(format!("{}/api/v4{path}", self.base_url))
+            .header("User-Agent", "Git Chest");
+        match self.token.read().unwrap().as_deref() {
+            Some(token) => req.header("PRIVATE-TOKEN", token),
+            None => req,
+        }
+    }
+
+    /// Self-hosted instances may not send rate limit headers at all.
+    pub async fn update_rate_limit(&self, headers: &HeaderMap, pool: &SqlitePool) -> AppResult<()> {
+        let Ok(max) = parse_header_num(headers, "RateLimit-Limit") else {
+            return Ok(());
+        };
+        let remaining = parse_header_num(headers, "RateLimit-Remaining").unwrap_or(0);
+        let reset_at = parse_header_num(headers, "RateLimit-Reset").unwrap_or(0);
+        update_rate_limit(
+            &self.rate_limit_key(),
+            max,
+            remaining,
+            max - remaining,
+            reset_at,
+            pool,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// GitLab has a single limit for API requests.
+    pub fn rate_limit_key(&self) -> RateLimitKey {
+        RateLimitKey::new(
+            Platform::GitLab,
+            &self.base_url,
+            self.token().as_deref(),
+            "core",
+        )
+    }
+
+    pub async fn check_rate_limit(&self, pool: &SqlitePool) -> AppResult<()> {
+        check_rate_limit(&self.rate_limit_key(), pool).await
+    }
+
+    /// The project ID can be the URL-encoded path, e.g. `group%2Fsubgroup%2Fproject`.
+    fn project_id(user: &str, repo: &str) -> String {
+        url_encode(&format!("{user}/{repo}"))
+    }
+
+    pub async fn fetch_project(
+        &self,
+        user: &str,
+        repo: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<GitLabApiProject> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(&format!(
+                "/projects/{}?license=true",
+                Self::project_id(user, repo)
+            ))
+            .header("Accept", "application/json")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key(), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching project from GitLab API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        self.update_rate_limit(res.headers(), pool).await?;
+
+        let body = res.text().await?;
+        let json_body: GitLabApiProject =
+            parse_body(&body, "Error parsing project from GitLab API")?;
+
+        info!("fetching gitlab project took {:?}", start.elapsed());
+
+        Ok(json_body)
+    }
+
+    pub async fn fetch_branch(
+        &self,
+        user: &str,
+        repo: &str,
+        branch: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<GitLabApiBranch> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(&format!(
+                "/projects/{}/repository/branches/{}",
+                Self::project_id(user, repo),
+                url_encode(branch)
+            ))
+            .header("Accept", "application/json")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key(), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching project branch from GitLab API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        self.update_rate_limit(res.headers(), pool).await?;
+
+        let body = res.text().await?;
+        let json_body: GitLabApiBranch =
+            parse_body(&body, "Error parsing project branch from GitLab API")?;
+
+        info!("fetching gitlab project branch took {:?}", start.elapsed());
+
+        Ok(json_body)
+    }
+
+    /// Fetch every page of a list endpoint of a project, e.g. `repository/branches`,
+    /// following the `X-Next-Page` header.
+    pub async fn fetch_project_list<T: DeserializeOwned>(
+        &self,
+        user: &str,
+        repo: &str,
+        path: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<T>> {
+        let start = Instant::now();
+        let mut items: Vec<T> = Vec::new();
+        let mut page = String::from("1");
+
+        loop {
+            let req = || {
+                self.get(&format!(
+                    "/projects/{}/{path}?per_page=100&page={page}",
+                    Self::project_id(user, repo),
+                ))
+                .header("Accept", "application/json")
+            };
+            let res = send_with_retry(req, &self.rate_limit_key(), pool)
+                .await
+                .map_err(|e| {
+                    error!("{:?}", e);
+                    "Error fetching project list from GitLab API"
+                })?;
+
+            if let Err(error) = res.error_for_status_ref() {
+                error!("{:?}", res.text().await?);
+                return AppError::new(&error.to_string());
+            }
+
+            self.update_rate_limit(res.headers(), pool).await?;
+
+            let next_page = parse_header(res.headers(), "X-Next-Page")
+                .ok()
+                .filter(|p| !p.is_empty())
+                .map(|p| p.to_string());
+
+            let body = res.text().await?;
+            let mut page_items: Vec<T> =
+                parse_body(&body, "Error parsing project list from GitLab API")?;
+            items.append(&mut page_items);
+
+            match next_page {
+                Some(next_page) => page = next_page,
+                None => break,
+            }
+        }
+
+        info!(
+            "fetching gitlab project {} took {:?}",
+            path,
+            start.elapsed()
+        );
+
+        Ok(items)
+    }
+
+    /// Fetch the full recursive tree, following the `X-Next-Page` header across pages.
+    pub async fn fetch_repo_tree(
+        &self,
+        user: &str,
+        repo: &str,
+        branch: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<GitLabApiTreeItem>> {
+        let start = Instant::now();
+        let mut items: Vec<GitLabApiTreeItem> = Vec::new();
+        let mut page = String::from("1");
+
+        loop {
+            let req = || {
+                self.get(&format!(
+                    "/projects/{}/repository/tree?ref={}&recursive=true&per_page=100&page={page}",
+                    Self::project_id(user, repo),
+                    url_encode(branch),
+                ))
+                .header("Accept", "application/json")
+            };
+            let res = send_with_retry(req, &self.rate_limit_key(), pool)
+                .await
+                .map_err(|e| {
+                    error!("{:?}", e);
+                    "Error fetching project tree from GitLab API"
+                })?;
+
+            if let Err(error) = res.error_for_status_ref() {
+                error!("{:?}", res.text().await?);
+                return AppError::new(&error.to_string());
+            }
+
+            self.update_rate_limit(res.headers(), pool).await?;
+
+            let next_page = parse_header(res.headers(), "X-Next-Page")
+                .ok()
+                .filter(|p| !p.is_empty())
+                .map(|p| p.to_string());
+
+            let body = res.text().await?;
+            let mut page_items: Vec<GitLabApiTreeItem> =
+                parse_body(&body, "Error parsing project tree from GitLab API")?;
+            items.append(&mut page_items);
+
+            match next_page {
+                Some(next_page) => page = next_page,
+                None => break,
+            }
+        }
+
+        info!("fetching gitlab project tree took {:?}", start.elapsed());
+
+        Ok(items)
+    }
+
+    /// Fetch the raw content of a blob by its SHA.
+    pub async fn fetch_repo_blob(
+        &self,
+        user: &str,
+        repo: &str,
+        sha: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<u8>> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(&format!(
+                "/projects/{}/repository/blobs/{sha}/raw",
+                Self::project_id(user, repo),
+            ))
+            .header("Accept", "*/*")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key(), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching project blob from GitLab API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        self.update_rate_limit(res.headers(), pool).await?;
+
+        let data = res.bytes().await.map_err(|e| {
+            error!("{:?}", e);
+            "Error parsing project blob from GitLab API"
+        })?;
+
+        info!("fetching gitlab project blob took {:?}", start.elapsed());
+
+        Ok(data.to_vec())
+    }
+
+    pub async fn fetch_repo_readme(
+        &self,
+        user: &str,
+        repo: &str,
+        branch: &str,
+        filename: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<String> {
+        let start = Instant::now();
+
+        let req = || {
+            self.get(&format!(
+                "/projects/{}/repository/files/{}/raw?ref={}",
+                Self::project_id(user, repo),
+                url_encode(filename),
+                url_encode(branch),
+            ))
+            .header("Accept", "*/*")
+        };
+        let res = send_with_retry(req, &self.rate_limit_key(), pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error fetching project README from GitLab API"
+            })?;
+
+        if let Err(error) = res.error_for_status_ref() {
+            error!("{:?}", res.text().await?);
+            return AppError::new(&error.to_string());
+        }
+
+        self.update_rate_limit(res.headers(), pool).await?;
+
+        let data = res.text().await.map_err(|e| {
+            error!("{:?}", e);
+            "Error parsing project README from GitLab API"
+        })?;
+
+        info!("fetching gitlab project readme took {:?}", start.elapsed());
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{
+        matchers::{header, method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::utils::testing::memory_pool;
+
+    fn branch(name: &str, sha: &str) -> serde_json::Value {
+        json!({ "name": name, "commit": { "id": sha }, "protected": false })
+    }
+
+    #[tokio::test]
+    async fn fetch_project_list_follows_next_page() {
+        let server = MockServer::start().await;
+        let branches = "/api/v4/projects/group%2Fproject/repository/branches";
+        Mock::given(method("GET"))
+            .and(path(branches))
+            .and(query_param("page", "1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("X-Next-Page", "2")
+                    .set_body_json(json!([branch("main", "a1")])),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(branches))
+            .and(query_param("page", "2"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("X-Next-Page", "")
+                    .set_body_json(json!([branch("dev", "b2")])),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let pool = memory_pool().await;
+        let api = GitLabAPI::new(&server.uri(), None);
+        let items: Vec<GitLabApiBranch> = api
+            .fetch_project_list("group", "project", "repository/branches", &pool)
+            .await
+            .unwrap();
+
+        let names: Vec<&str> = items.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["main", "dev"]);
+    }
+
+    #[tokio::test]
+    async fn fetch_branch_encodes_namespaced_paths() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path(
+                "/api/v4/projects/group%2Fsub.group%2Fmy-project/repository/branches/feature%2Fx",
+            ))
+            .and(header("PRIVATE-TOKEN", "secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(branch("feature/x", "c3")))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let pool = memory_pool().await;
+        // The trailing slash of a configured base URL is trimmed.
+        let api = GitLabAPI::new(&format!("{}/", server.uri()), Some("secret".to_string()));
+        let branch = api
+            .fetch_branch("group/sub.group", "my-project", "feature/x", &pool)
+            .await
+            .unwrap();
+
+        assert_eq!(branch.name, "feature/x");
+        assert_eq!(branch.commit.id, "c3");
+    }
+
+    #[tokio::test]
+    async fn fetch_branch_fails_on_error_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let pool = memory_pool().await;
+        let api = GitLabAPI::new(&server.uri(), None);
+        let result = api.fetch_branch("group", "missing", "main", &pool).await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use serde::Deserialize;
+
+use crate::repo::NewRepoTreeItem;
+
+#[derive(Deserialize)]
+pub struct GitLabApiNamespace {
+    pub id: i64,
+    pub name: String,
+    pub path: String,
+    /// 'user' or 'group'
+    pub kind: String,
+    pub full_path: String,
+    pub avatar_url: Option<String>,
+    pub web_url: String,
+}
+
+#[derive(Deserialize)]
+pub struct GitLabApiProjectLicense {
+    pub key: String,
+    pub name: String,
+}
+
+/// URL-related properties are not included, except for `web_url`.
+#[derive(Deserialize)]
+pub struct GitLabApiProject {
+    pub id: i64,
+    pub name: String,
+    pub name_with_namespace: String,
+    pub path: String,
+    pub path_with_namespace: String,
+    pub description: Option<String>,
+    /// Missing for projects with an empty repository.
+    pub default_branch: Option<String>,
+    pub visibility: String,
+    pub web_url: String,
+    pub star_count: i32,
+    pub forks_count: i32,
+    /// Missing if the issue tracker is disabled.
+    pub open_issues_count: Option<i32>,
+    pub archived: bool,
+    pub empty_repo: bool,
+    pub topics: Vec<String>,
+    /// Only included when requested with `license=true`.
+    pub license: Option<GitLabApiProjectLicense>,
+    pub namespace: GitLabApiNamespace,
+    pub created_at: String,
+    pub last_activity_at: String,
+}
+
+#[derive(Deserialize)]
+pub struct GitLabApiCommit {
+    pub id: String,
+}
+
+#[derive(Deserialize)]
+pub struct GitLabApiBranch {
+    pub name: String,
+    pub commit: GitLabApiCommit,
+    pub protected: bool,
+}
+
+#[derive(Deserialize)]
+pub struct GitLabApiTag {
+    pub name: String,
+    /// The tag object SHA of annotated tags, the commit SHA of lightweight ones.
+    pub target: String,
+    pub message: Option<String>,
+    /// Only set for annotated tags.
+    pub created_at: Option<String>,
+    pub commit: GitLabApiCommit,
+}
+
+#[derive(Deserialize)]
+pub struct GitLabApiTreeItem {
+    /// The object SHA.
+    pub id: String,
+    pub name: String,
+    /// 'tree' (directory), 'blob' (file) or 'commit' (submodule).
+    pub r#type: String,
+    pub path: String,
+    pub mode: String,
+}
+
+impl From<GitLabApiTreeItem> for NewRepoTreeItem {
+    fn from(item: GitLabApiTreeItem) -> Self {
+        Self {
+            path: item.path,
+            mode: item.mode,
+            r#type: item.r#type,
+            sha: Some(item.id),
+            size: None,
+        }
+    }
+}
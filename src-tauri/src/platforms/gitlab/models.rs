@@ -0,0 +1,51 @@
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+
+/// Excluding `repo_id`.
+#[derive(Serialize, FromRow)]
+pub struct GitLabProject {
+    pub id: i64,
+    name: String,
+    name_with_namespace: String,
+    path: String,
+    path_with_namespace: String,
+    description: Option<String>,
+    default_branch: String,
+    visibility: String,
+    web_url: String,
+    star_count: i32,
+    forks_count: i32,
+    open_issues_count: Option<i32>,
+    archived: bool,
+    empty_repo: bool,
+    license_key: Option<String>,
+    license_name: Option<String>,
+    pub namespace_id: i64,
+    created_at: String,
+    last_activity_at: String,
+}
+
+/// Excluding `user_id`.
+#[derive(Serialize, FromRow)]
+pub struct GitLabNamespace {
+    pub id: i64,
+    name: String,
+    pub path: String,
+    /// 'user' or 'group'
+    kind: String,
+    pub full_path: String,
+    avatar_url: Option<String>,
+    web_url: String,
+}
+
+#[derive(Serialize)]
+pub struct GitLabProjectData {
+    pub project: GitLabProject,
+    pub namespace: Option<GitLabNamespace>,
+    pub topics: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct GitLabUserData {
+    pub namespace: GitLabNamespace,
+}
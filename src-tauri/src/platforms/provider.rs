@@ -0,0 +1,197 @@
+use async_trait::async_trait;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tauri::AppHandle;
+
+use crate::{
+    commands::{
+        repo::{DbPlatformRepo, RepoPreviewOwner},
+        user::PlatformUserData,
+    },
+    error::AppResult,
+    events::{RepoEvent, WatchedItem},
+    platforms::Platform,
+    repo::{NewRepoTreeItem, PlatformRepoData, RepoStats},
+};
+
+/// Identifies the repo a provider is working on.
+pub struct RepoContext {
+    pub repo_id: i64,
+    pub instance_id: Option<i64>,
+    pub user: String,
+    pub repo: String,
+}
+
+/// The tree of a commit, fetched from a platform.
+pub struct FetchedTree {
+    pub sha: String,
+    /// The revision files of the tree are fetched at.
+    pub rev: String,
+    pub truncated: bool,
+    pub items: Vec<NewRepoTreeItem>,
+}
+
+/// A branch or tag of a repo, as listed by a platform.
+#[derive(Serialize)]
+pub struct RepoRef {
+    pub name: String,
+    /// 'branch' or 'tag'.
+    pub kind: String,
+    /// The commit the ref points to.
+    pub sha: String,
+    /// Whether the branch is protected, always false for tags.
+    pub protected: bool,
+    /// Whether the tag is annotated, `None` for branches or if the platform doesn't tell.
+    pub annotated: Option<bool>,
+    /// The tagger and message of an annotated tag, as far as the platform lists them.
+    pub tagger: Option<String>,
+    pub tagged_at: Option<String>,
+    pub message: Option<String>,
+}
+
+impl RepoRef {
+    pub fn branch(name: String, sha: String, protected: bool) -> Self {
+        Self {
+            name,
+            kind: "branch".to_string(),
+            sha,
+            protected,
+            annotated: None,
+            tagger: None,
+            tagged_at: None,
+            message: None,
+        }
+    }
+
+    /// A tag without details, set the others with struct update syntax.
+    pub fn tag(name: String, sha: String) -> Self {
+        Self {
+            kind: "tag".to_string(),
+            ..Self::branch(name, sha, false)
+        }
+    }
+}
+
+/// Where to clone a repo from.
+pub struct CloneRemote {
+    pub url: String,
+    /// Username and password for HTTP authentication, if a token is configured.
+    pub credentials: Option<(String, String)>,
+}
+
+/// A git host (or an instance of one) that repos can be added from and read back.
+///
+/// Methods that add data both fetch it from the platform and store it in the
+/// platform-specific tables, while the shared repo data (tree, README) is stored
+/// by the caller.
+#[async_trait]
+pub trait PlatformProvider: Send + Sync {
+    fn platform(&self) -> Platform;
+
+    /// Replace the token used for subsequent requests.
+    fn set_token(&self, token: Option<String>);
+
+    /// Fail if the host's core rate limit is known to be exhausted.
+    async fn check_core_rate_limit(&self, _pool: &SqlitePool) -> AppResult<()> {
+        Ok(())
+    }
+
+    /// Fetch and store the repo metadata, returning the default branch.
+    async fn add_repo(
+        &self,
+        ctx: &RepoContext,
+        pool: &SqlitePool,
+        app: &AppHandle,
+    ) -> AppResult<String>;
+
+    /// Fetch the tree at a branch name or a full commit SHA.
+    async fn fetch_tree(
+        &self,
+        ctx: &RepoContext,
+        rev: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<FetchedTree>;
+
+    /// Fetch every branch and tag of the repo.
+    async fn fetch_refs(&self, ctx: &RepoContext, pool: &SqlitePool) -> AppResult<Vec<RepoRef>>;
+
+    /// Fetch the raw contents of a file at a revision.
+    async fn fetch_file(
+        &self,
+        ctx: &RepoContext,
+        rev: &str,
+        path: &str,
+        pool: &SqlitePool,
+    ) -> AppResult<String>;
+
+    /// Fetch the raw content of a blob, by its SHA or by its path at the revision its
+    /// tree was fetched at, depending on what the platform supports. The SHA is unknown
+    /// for platforms that don't expose it.
+    async fn fetch_blob(
+        &self,
+        ctx: &RepoContext,
+        rev: &str,
+        path: &str,
+        sha: Option<&str>,
+        pool: &SqlitePool,
+    ) -> AppResult<Vec<u8>>;
+
+    /// Fetch and store the owner of the repo as a user, unless it was already added.
+    async fn add_owner(
+        &self,
+        ctx: &RepoContext,
+        pool: &SqlitePool,
+        app: &AppHandle,
+    ) -> AppResult<()>;
+
+    fn clone_remote(&self, ctx: &RepoContext) -> CloneRemote;
+
+    /// Fetch the current occurrences of a watched event, or `None` if the platform
+    /// doesn't support watching it.
+    async fn fetch_watched_items(
+        &self,
+        _ctx: &RepoContext,
+        _event: RepoEvent,
+        _pool: &SqlitePool,
+    ) -> AppResult<Option<Vec<WatchedItem>>> {
+        Ok(None)
+    }
+
+    /// Fetch and store the issues of the repo with their comments, updating a previous
+    /// archive. Platforms that don't support archiving issues do nothing.
+    async fn archive_issues(&self, _ctx: &RepoContext, _pool: &SqlitePool) -> AppResult<()> {
+        Ok(())
+    }
+
+    /// Fetch and store the pull requests of the repo with their reviews and diffs,
+    /// updating a previous archive. Platforms that don't support archiving them do nothing.
+    async fn archive_pulls(&self, _ctx: &RepoContext, _pool: &SqlitePool) -> AppResult<()> {
+        Ok(())
+    }
+
+    /// Fetch and store the discussions of the repo with their comments, updating a
+    /// previous archive. Platforms without discussions do nothing.
+    async fn archive_discussions(&self, _ctx: &RepoContext, _pool: &SqlitePool) -> AppResult<()> {
+        Ok(())
+    }
+
+    /// Fetch and store the releases of the repo, downloading their assets if enabled.
+    /// Platforms that don't support archiving releases do nothing.
+    async fn archive_releases(&self, _ctx: &RepoContext, _pool: &SqlitePool) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn get_repo(&self, repo_id: i64, pool: &SqlitePool) -> AppResult<PlatformRepoData>;
+
+    /// The stored stats of the repo, if its metadata was added.
+    async fn get_repo_stats(&self, repo_id: i64, pool: &SqlitePool)
+        -> AppResult<Option<RepoStats>>;
+
+    async fn get_repo_preview(
+        &self,
+        repo_id: i64,
+        pool: &SqlitePool,
+    ) -> AppResult<(DbPlatformRepo, RepoPreviewOwner)>;
+
+    async fn get_user(&self, user_id: i64, pool: &SqlitePool) -> AppResult<PlatformUserData>;
+}
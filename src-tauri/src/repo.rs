@@ -1,16 +1,30 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
 
+use chrono::Utc;
 use regex::Regex;
 use serde::Serialize;
-use sqlx::{prelude::FromRow, SqlitePool};
-use tauri::AppHandle;
-use tokio::{fs, io::AsyncWriteExt};
-use tracing::error;
+use sha2::{Digest, Sha256};
+use sqlx::{prelude::FromRow, QueryBuilder, Sqlite, SqlitePool};
+use tauri::{AppHandle, Runtime};
+use tokio::{fs, io::AsyncWriteExt, time::Instant};
+use tracing::{error, info};
 
 use crate::{
+    blob::remove_cached_blobs,
+    clone::remove_repo_clone,
     commands::repo::AddRepoProgress,
     error::AppResult,
-    platforms::github::models::GitHubRepoData,
+    events::add_repo_ref_events,
+    platforms::{
+        bitbucket::models::BitbucketRepoData,
+        gitea::models::GiteaRepoData,
+        github::{models::GitHubRepoData, release::get_release_asset_dir},
+        gitlab::models::GitLabProjectData,
+        provider::{FetchedTree, PlatformProvider, RepoContext, RepoRef},
+    },
     utils::{
         data::progress_percentage,
         dirs::{ensure_dir, get_data_dir},
@@ -23,6 +37,7 @@ use crate::{
 pub struct Repo {
     pub id: i64,
     pub platform: String,
+    pub instance_id: Option<i64>,
     pub user: String,
     pub repo: String,
     pub clone_data: bool,
@@ -30,42 +45,804 @@ pub struct Repo {
     pub auto_sync: u8,
     pub created_at: String,
     pub updated_at: String,
+    /// 'none', 'cloning', 'cloned' or 'failed'.
+    pub clone_status: String,
+    /// Size of the local clone in bytes.
+    pub clone_size: Option<i64>,
+    /// The commit HEAD pointed to when the clone was last fetched.
+    pub clone_commit: Option<String>,
+    pub clone_fetched_at: Option<String>,
+    /// 'adding' until all of its data is stored, then 'ready'.
+    pub status: String,
+    /// Whether to cache the content of every file up to `prefetch_max_size` bytes.
+    pub prefetch_blobs: bool,
+    pub prefetch_max_size: i64,
+    /// Whether to archive the issues and their comments on every sync.
+    pub archive_issues: bool,
+    /// Whether to archive the pull requests with their reviews and diffs on every sync.
+    pub archive_pulls: bool,
+    /// Whether to archive the discussions with their comments on every sync.
+    pub archive_discussions: bool,
+    /// Whether to archive the releases on every sync.
+    pub archive_releases: bool,
+    /// Whether to download the release assets matching the repo's globs, up to
+    /// `release_asset_max_size` bytes.
+    pub download_release_assets: bool,
+    pub release_asset_max_size: i64,
 }
 
 #[derive(Serialize)]
 #[serde(tag = "type", content = "data")]
 #[allow(clippy::large_enum_variant)]
 pub enum PlatformRepoData {
-    Bitbucket,
+    Bitbucket(BitbucketRepoData),
     GitHub(GitHubRepoData),
-    GitLab,
-    Gitea,
+    GitLab(GitLabProjectData),
+    Gitea(GiteaRepoData),
+}
+
+/// Counters and state of a repo, read from its platform table. Fields a platform doesn't
+/// have are `None`.
+#[derive(Serialize, FromRow, Clone, PartialEq)]
+pub struct RepoStats {
+    pub stars: Option<i64>,
+    pub forks: Option<i64>,
+    pub watchers: Option<i64>,
+    pub open_issues: Option<i64>,
+    pub default_branch: String,
+    pub archived: Option<bool>,
+    /// 'public', 'private' or 'internal'.
+    pub visibility: Option<String>,
+    pub license: Option<String>,
+    /// Comma-separated.
+    pub topics: Option<String>,
+}
+
+/// A snapshot of the stats of a repo at the time it was added or synced.
+#[derive(Serialize, FromRow)]
+pub struct RepoMetadataSnapshot {
+    pub captured_at: String,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub stats: RepoStats,
+}
+
+/// Read the stats of a repo with a query of a platform table, selecting the columns of
+/// `RepoStats` for a `repo_id`.
+pub async fn get_repo_stats(
+    query: &str,
+    repo_id: i64,
+    pool: &SqlitePool,
+) -> AppResult<Option<RepoStats>> {
+    let stats = sqlx::query_as::<_, RepoStats>(query)
+        .bind(repo_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository stats from database"
+        })?;
+    Ok(stats)
+}
+
+/// Append the current stats of a repo to its metadata history.
+pub async fn add_repo_metadata_snapshot(
+    repo_id: i64,
+    provider: &dyn PlatformProvider,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let Some(mut stats) = provider.get_repo_stats(repo_id, pool).await? else {
+        return Ok(());
+    };
+    // Topics are compared between snapshots, so their order shouldn't matter.
+    stats.topics = stats.topics.map(|topics| {
+        let mut topics = topics.split(',').collect::<Vec<&str>>();
+        topics.sort_unstable();
+        topics.join(",")
+    });
+
+    let query = "
+        INSERT INTO repo_metadata_history (
+            repo_id, captured_at, stars, forks, watchers, open_issues, default_branch,
+            archived, visibility, license, topics
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    ";
+    sqlx::query(query)
+        .bind(repo_id)
+        .bind(Utc::now().to_rfc3339())
+        .bind(stats.stars)
+        .bind(stats.forks)
+        .bind(stats.watchers)
+        .bind(stats.open_issues)
+        .bind(stats.default_branch)
+        .bind(stats.archived)
+        .bind(stats.visibility)
+        .bind(stats.license)
+        .bind(stats.topics)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding repository metadata snapshot to database"
+        })?;
+    Ok(())
+}
+
+/// The metadata history of a repo, oldest first.
+pub async fn get_repo_metadata_history(
+    repo_id: i64,
+    pool: &SqlitePool,
+) -> AppResult<Vec<RepoMetadataSnapshot>> {
+    let query = "
+        SELECT
+            captured_at, stars, forks, watchers, open_issues, default_branch,
+            archived, visibility, license, topics
+        FROM repo_metadata_history
+        WHERE repo_id = ?
+        ORDER BY captured_at
+    ";
+    let history = sqlx::query_as::<_, RepoMetadataSnapshot>(query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository metadata history from database"
+        })?;
+    Ok(history)
 }
 
 #[derive(Serialize, FromRow)]
 pub struct RepoTreeItem {
     id: i64,
     path: String,
+    /// The last component of the path.
+    name: String,
+    /// The number of directories above the item, 0 at the root of the tree.
+    depth: i64,
     mode: String,
     r#type: String,
-    sha: String,
+    /// Unknown for files on platforms that don't expose their SHA, until fetched.
+    sha: Option<String>,
     size: Option<i32>,
 }
 
 #[derive(Serialize, FromRow)]
 pub struct RepoTree {
-    sha: String,
-    truncated: bool,
+    pub id: i64,
+    /// The branch, tag or commit the tree was fetched at.
+    pub r#ref: String,
+    pub sha: String,
+    /// The revision the files of the tree are fetched at.
+    pub rev: Option<String>,
+    pub truncated: bool,
+    /// Whether this is the tree of the default branch, which is kept in sync.
+    pub is_default: bool,
 }
 
-pub async fn download_readme_assets(
-    text: &str,
+/// A tree item fetched from a platform, before it is stored.
+pub struct NewRepoTreeItem {
+    pub path: String,
+    pub mode: String,
+    /// 'tree' (directory), 'blob' (file) or 'commit' (submodule).
+    pub r#type: String,
+    /// None if the platform doesn't expose it, as Bitbucket.
+    pub sha: Option<String>,
+    pub size: Option<i32>,
+}
+
+/// Rows per multi-row insert of tree items, 11 parameters each stay well under SQLite's
+/// limit of 32766 variables per statement.
+const TREE_ITEM_BATCH_SIZE: usize = 1000;
+
+/// The row of a tree item, with its place in the tree resolved.
+struct TreeItemRow<'a> {
+    id: i64,
+    parent_id: Option<i64>,
+    name: &'a str,
+    depth: i64,
+}
+
+/// Assign row IDs to tree items, starting after `last_id`, and resolve the ID of each
+/// item's parent directory by its full path. Items whose parent directory isn't in the
+/// tree are placed at the root.
+fn assign_tree_item_ids(items: &[NewRepoTreeItem], last_id: i64) -> Vec<TreeItemRow<'_>> {
+    let tree_ids: HashMap<&str, i64> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.r#type == "tree")
+        .map(|(i, item)| (item.path.as_str(), last_id + 1 + i as i64))
+        .collect();
+
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let (parent_path, name) = match item.path.rsplit_once('/') {
+                Some((parent_path, name)) => (Some(parent_path), name),
+                None => (None, item.path.as_str()),
+            };
+            TreeItemRow {
+                id: last_id + 1 + i as i64,
+                parent_id: parent_path.and_then(|path| tree_ids.get(path).copied()),
+                name,
+                depth: item.path.matches('/').count() as i64,
+            }
+        })
+        .collect()
+}
+
+/// Store the tree of a repo at a ref, replacing the one previously stored for it, and
+/// return its ID. The items are inserted in batches within a single transaction, with
+/// their IDs assigned up front so children can reference their parents without a round
+/// trip per item. Blobs are stored once per repo, however many trees share them.
+#[allow(clippy::too_many_arguments)]
+pub async fn add_repo_tree<R: Runtime>(
     repo_id: i64,
+    tree_ref: &str,
+    is_default: bool,
+    tree: &FetchedTree,
     platform: &str,
     user: &str,
     repo: &str,
     pool: &SqlitePool,
+    app: &AppHandle<R>,
+) -> AppResult<i64> {
+    let items = &tree.items;
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("{:?}", e);
+        "Error adding repository tree to database"
+    })?;
+
+    // Files without a SHA get one once their content is fetched, which holds for every
+    // tree at the same revision.
+    let fetched_shas: HashMap<String, String> = if items.iter().any(|i| i.sha.is_none()) {
+        let fetched_query = "
+            SELECT DISTINCT i.path, i.sha
+            FROM repo_tree_item AS i
+            JOIN repo_tree AS t ON t.id = i.tree_id
+            WHERE i.repo_id = ? AND COALESCE(t.rev, t.sha) = ? AND i.type = 'blob'
+                AND i.sha IS NOT NULL
+        ";
+        sqlx::query_as::<_, (String, String)>(fetched_query)
+            .bind(repo_id)
+            .bind(&tree.rev)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error getting repository tree items from database"
+            })?
+            .into_iter()
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    let item_sha = |item: &NewRepoTreeItem| -> Option<String> {
+        item.sha
+            .clone()
+            .or_else(|| fetched_shas.get(&item.path).cloned())
+    };
+
+    if is_default {
+        let default_query = "UPDATE repo_tree SET is_default = 0 WHERE repo_id = ? AND ref != ?";
+        sqlx::query(default_query)
+            .bind(repo_id)
+            .bind(tree_ref)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error updating repository trees in database"
+            })?;
+    }
+
+    let query = "
+        INSERT INTO repo_tree (
+            repo_id, ref, sha, rev, truncated, is_default
+        )
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT (repo_id, ref) DO UPDATE SET
+            sha = excluded.sha, rev = excluded.rev, truncated = excluded.truncated,
+            is_default = excluded.is_default
+        RETURNING id
+    ";
+    let tree_id = sqlx::query_scalar::<_, i64>(query)
+        .bind(repo_id)
+        .bind(tree_ref)
+        .bind(&tree.sha)
+        .bind(&tree.rev)
+        .bind(tree.truncated)
+        .bind(is_default)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding repository tree to database"
+        })?;
+
+    let delete_query = "DELETE FROM repo_tree_item WHERE tree_id = ?";
+    sqlx::query(delete_query)
+        .bind(tree_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing repository tree items from database"
+        })?;
+
+    // The transaction holds the write lock now, so no other rows can take these IDs.
+    // IDs of deleted rows aren't reused, as with AUTOINCREMENT.
+    let last_id_query = "
+        SELECT MAX(
+            COALESCE((SELECT seq FROM sqlite_sequence WHERE name = 'repo_tree_item'), 0),
+            COALESCE((SELECT MAX(id) FROM repo_tree_item), 0)
+        )
+    ";
+    let last_id = sqlx::query_scalar::<_, i64>(last_id_query)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding repository tree items to database"
+        })?;
+
+    let rows = assign_tree_item_ids(items, last_id);
+
+    // Parents are resolved by path, so they may come after their children in the tree.
+    sqlx::query("PRAGMA defer_foreign_keys = ON")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding repository tree items to database"
+        })?;
+    let total_tree_items = items.len();
+    let mut last_progress = None;
+
+    for (batch, batch_rows) in items
+        .chunks(TREE_ITEM_BATCH_SIZE)
+        .zip(rows.chunks(TREE_ITEM_BATCH_SIZE))
+    {
+        let mut query_builder = QueryBuilder::<Sqlite>::new(
+            "INSERT INTO repo_tree_item (id, repo_id, tree_id, parent_id, path, name, depth, mode, type, sha, size) ",
+        );
+        query_builder.push_values(batch.iter().zip(batch_rows), |mut row, (item, item_row)| {
+            row.push_bind(item_row.id)
+                .push_bind(repo_id)
+                .push_bind(tree_id)
+                .push_bind(item_row.parent_id)
+                .push_bind(&item.path)
+                .push_bind(item_row.name)
+                .push_bind(item_row.depth)
+                .push_bind(&item.mode)
+                .push_bind(&item.r#type)
+                .push_bind(item_sha(item))
+                // Sizes of known blobs are stored once in 'repo_blob'.
+                .push_bind(item_sha(item).is_none().then_some(item.size).flatten());
+        });
+        query_builder.build().execute(&mut *tx).await.map_err(|e| {
+            error!("{:?}", e);
+            "Error adding repository tree items to database"
+        })?;
+
+        let blobs: Vec<&NewRepoTreeItem> = batch
+            .iter()
+            .filter(|item| item.r#type == "blob" && item_sha(item).is_some())
+            .collect();
+        if !blobs.is_empty() {
+            let mut query_builder = QueryBuilder::<Sqlite>::new(
+                "INSERT OR IGNORE INTO repo_blob (repo_id, sha, size) ",
+            );
+            query_builder.push_values(blobs, |mut row, item| {
+                row.push_bind(repo_id)
+                    .push_bind(item_sha(item))
+                    .push_bind(item.size);
+            });
+            query_builder.build().execute(&mut *tx).await.map_err(|e| {
+                error!("{:?}", e);
+                "Error adding repository blobs to database"
+            })?;
+        }
+
+        // Only changes in percentage are sent, large trees would flood the frontend.
+        let inserted = batch_rows
+            .last()
+            .map_or(0, |item_row| (item_row.id - last_id) as usize);
+        let progress = progress_percentage(inserted - 1, total_tree_items);
+        if last_progress != Some(progress) {
+            last_progress = Some(progress);
+            AddRepoProgress::InsertTree.send(
+                platform,
+                user,
+                repo,
+                progress,
+                inserted as u64,
+                total_tree_items as u64,
+                app,
+            );
+        }
+    }
+
+    // Blobs of the replaced tree that no tree uses anymore.
+    let orphan_query = "
+        DELETE FROM repo_blob
+        WHERE repo_id = ? AND sha NOT IN (
+            SELECT sha FROM repo_tree_item
+            WHERE repo_id = ? AND type = 'blob' AND sha IS NOT NULL
+        )
+        RETURNING sha
+    ";
+    let orphans = sqlx::query_scalar::<_, String>(orphan_query)
+        .bind(repo_id)
+        .bind(repo_id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing repository blobs from database"
+        })?;
+
+    tx.commit().await.map_err(|e| {
+        error!("{:?}", e);
+        "Error adding repository tree to database"
+    })?;
+
+    // Another repo with the same files may still use them, or have cached them since.
+    remove_cached_blobs(&orphans, pool).await?;
+
+    Ok(tree_id)
+}
+
+/// The stored trees of a repo, the default branch first.
+pub async fn get_repo_trees(repo_id: i64, pool: &SqlitePool) -> AppResult<Vec<RepoTree>> {
+    let query = "SELECT * FROM repo_tree WHERE repo_id = ? ORDER BY is_default DESC, ref";
+    let trees = sqlx::query_as::<_, RepoTree>(query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository trees from database"
+        })?;
+    Ok(trees)
+}
+
+/// A stored branch or tag of a repo.
+#[derive(Serialize, FromRow)]
+pub struct StoredRepoRef {
+    pub name: String,
+    /// 'branch' or 'tag'.
+    pub kind: String,
+    pub sha: String,
+    pub protected: bool,
+    pub annotated: Option<bool>,
+    pub tagger: Option<String>,
+    pub tagged_at: Option<String>,
+    pub message: Option<String>,
+    /// When the ref was first stored or last moved to another commit.
+    pub updated_at: String,
+    /// The stored tree of the ref, if it was fetched.
+    pub tree_id: Option<i64>,
+    pub is_default: bool,
+}
+
+/// Replace the stored refs of a repo, keeping when unchanged refs were last updated.
+pub async fn add_repo_refs(repo_id: i64, refs: &[RepoRef], pool: &SqlitePool) -> AppResult<()> {
+    let now = Utc::now().to_rfc3339();
+    let mut tx = pool.begin().await.map_err(|e| {
+        error!("{:?}", e);
+        "Error adding repository refs to database"
+    })?;
+
+    let query = "
+        INSERT INTO repo_ref (
+            repo_id, kind, name, sha, protected, annotated, tagger, tagged_at, message,
+            updated_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (repo_id, kind, name) DO UPDATE SET
+            updated_at = CASE WHEN sha = excluded.sha THEN updated_at ELSE excluded.updated_at END,
+            sha = excluded.sha,
+            protected = excluded.protected,
+            annotated = excluded.annotated,
+            tagger = excluded.tagger,
+            tagged_at = excluded.tagged_at,
+            message = excluded.message
+    ";
+    for repo_ref in refs {
+        sqlx::query(query)
+            .bind(repo_id)
+            .bind(&repo_ref.kind)
+            .bind(&repo_ref.name)
+            .bind(&repo_ref.sha)
+            .bind(repo_ref.protected)
+            .bind(repo_ref.annotated)
+            .bind(&repo_ref.tagger)
+            .bind(&repo_ref.tagged_at)
+            .bind(&repo_ref.message)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding repository ref to database"
+            })?;
+    }
+
+    let keys: Vec<String> = refs
+        .iter()
+        .map(|r| format!("{}:{}", r.kind, r.name))
+        .collect();
+    let query = "
+        DELETE FROM repo_ref
+        WHERE repo_id = ? AND kind || ':' || name NOT IN (SELECT value FROM json_each(?))
+    ";
+    sqlx::query(query)
+        .bind(repo_id)
+        .bind(serde_json::to_string(&keys)?)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing repository refs from database"
+        })?;
+
+    tx.commit().await.map_err(|e| {
+        error!("{:?}", e);
+        "Error adding repository refs to database"
+    })?;
+
+    Ok(())
+}
+
+/// The stored refs of a repo with their stored trees, the default branch first.
+pub async fn get_stored_repo_refs(
+    repo_id: i64,
+    pool: &SqlitePool,
+) -> AppResult<Vec<StoredRepoRef>> {
+    let query = "
+        SELECT
+            r.name, r.kind, r.sha, r.protected, r.annotated, r.tagger, r.tagged_at,
+            r.message, r.updated_at, t.id AS tree_id, COALESCE(t.is_default, 0) AS is_default
+        FROM repo_ref r
+        LEFT JOIN repo_tree t ON t.repo_id = r.repo_id AND t.ref = r.name
+        WHERE r.repo_id = ?
+        ORDER BY is_default DESC, r.kind, r.name
+    ";
+    let refs = sqlx::query_as::<_, StoredRepoRef>(query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository refs from database"
+        })?;
+    Ok(refs)
+}
+
+/// Fetch and store the refs of a repo, logging changes of watched branches and tags.
+pub async fn refresh_repo_refs(
+    provider: &dyn PlatformProvider,
+    ctx: &RepoContext,
+    pool: &SqlitePool,
+    app: &AppHandle,
+) -> AppResult<()> {
+    let start = Instant::now();
+
+    let refs = provider.fetch_refs(ctx, pool).await?;
+    add_repo_refs(ctx.repo_id, &refs, pool).await?;
+    add_repo_ref_events(ctx.repo_id, &refs, pool, app).await?;
+
+    info!(
+        "refreshed {} refs of {}/{} in {:?}",
+        refs.len(),
+        ctx.user,
+        ctx.repo,
+        start.elapsed()
+    );
+
+    Ok(())
+}
+
+/// Find the README file at the root of a repo's default tree.
+pub async fn get_repo_readme_filename(
+    repo_id: i64,
+    pool: &SqlitePool,
+) -> AppResult<Option<String>> {
+    let filename = sqlx::query_scalar::<_, String>(
+        "SELECT path
+        FROM repo_tree_item
+        WHERE tree_id = (SELECT id FROM repo_tree WHERE repo_id = ? AND is_default = 1)
+        AND parent_id IS NULL
+        AND (LOWER(path) LIKE '%readme%' OR LOWER(path) LIKE '%readme.md%')",
+    )
+    .bind(repo_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!("{:?}", e);
+        "Error looking for optional repository tree item from database"
+    })?;
+    Ok(filename)
+}
+
+/// Store the README of a repo, with the hash of its content as fetched.
+pub async fn add_repo_readme(
+    repo_id: i64,
+    content: &str,
+    raw_sha256: &str,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let readme_query = "
+        INSERT INTO repo_readme (repo_id, content, raw_sha256) VALUES (?, ?, ?)
+        ON CONFLICT (repo_id) DO UPDATE SET
+            content = excluded.content, raw_sha256 = excluded.raw_sha256
+    ";
+    sqlx::query(readme_query)
+        .bind(repo_id)
+        .bind(content)
+        .bind(raw_sha256)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding repository README to database"
+        })?;
+    Ok(())
+}
+
+/// Add a repo's metadata, tree, README and owner through its platform provider,
+/// returning the revision the tree was fetched at. Data of a repo that was already
+/// added is replaced, so this also refreshes it.
+pub async fn add_repo_data(
+    provider: &dyn PlatformProvider,
+    ctx: &RepoContext,
+    pool: &SqlitePool,
     app: &AppHandle,
+) -> AppResult<String> {
+    let platform = provider.platform().to_string();
+    let (user, repo) = (ctx.user.as_str(), ctx.repo.as_str());
+
+    let branch = provider.add_repo(ctx, pool, app).await?;
+
+    AddRepoProgress::FetchTree.send(&platform, user, repo, 0, 0, 1, app);
+    let tree = provider.fetch_tree(ctx, &branch, pool).await?;
+    AddRepoProgress::FetchTree.send(&platform, user, repo, 100, 1, 1, app);
+
+    add_repo_tree(
+        ctx.repo_id,
+        &branch,
+        true,
+        &tree,
+        &platform,
+        user,
+        repo,
+        pool,
+        app,
+    )
+    .await?;
+
+    refresh_repo_readme(provider, ctx, &tree.rev, pool, app).await?;
+
+    provider.add_owner(ctx, pool, app).await?;
+
+    Ok(tree.rev)
+}
+
+/// Fetch the README at the root of a repo's default tree at `rev`, replacing the stored
+/// one with its images downloaded. An unchanged README is kept as it is.
+pub async fn refresh_repo_readme<R: Runtime>(
+    provider: &dyn PlatformProvider,
+    ctx: &RepoContext,
+    rev: &str,
+    pool: &SqlitePool,
+    app: &AppHandle<R>,
+) -> AppResult<()> {
+    let platform = provider.platform().to_string();
+    let (user, repo) = (ctx.user.as_str(), ctx.repo.as_str());
+
+    AddRepoProgress::Readme.send(&platform, user, repo, 0, 0, 1, app);
+    let Some(filename) = get_repo_readme_filename(ctx.repo_id, pool).await? else {
+        remove_repo_readme(ctx.repo_id, user, repo, pool).await?;
+        AddRepoProgress::Readme.send(&platform, user, repo, 100, 2, 2, app);
+        return Ok(());
+    };
+
+    let readme_content = provider.fetch_file(ctx, rev, &filename, pool).await?;
+    let raw_sha256 = format!("{:x}", Sha256::digest(&readme_content));
+
+    let query = "SELECT raw_sha256 FROM repo_readme WHERE repo_id = ?";
+    let stored_sha256 = sqlx::query_scalar::<_, Option<String>>(query)
+        .bind(ctx.repo_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository README from database"
+        })?
+        .flatten();
+    if stored_sha256.as_ref() == Some(&raw_sha256) {
+        AddRepoProgress::Readme.send(&platform, user, repo, 100, 2, 2, app);
+        return Ok(());
+    }
+
+    let parsed_readme_content = download_readme_assets(
+        &readme_content,
+        ctx.repo_id,
+        &platform,
+        user,
+        repo,
+        pool,
+        app,
+    )
+    .await?;
+
+    add_repo_readme(ctx.repo_id, &parsed_readme_content, &raw_sha256, pool).await?;
+
+    Ok(())
+}
+
+/// The local file of a downloaded README asset.
+fn get_readme_asset_path(dir: &Path, id: i64, ext: Option<&str>) -> PathBuf {
+    match ext {
+        Some(ext) => dir.join(format!("{id}.{ext}")),
+        None => dir.join(id.to_string()),
+    }
+}
+
+/// Remove the stored README of a repo along with its downloaded assets. The asset
+/// directory is shared with repos of the same name on other platforms, so only the
+/// files of this repo's assets are removed.
+pub async fn remove_repo_readme(
+    repo_id: i64,
+    user: &str,
+    repo: &str,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let query = "DELETE FROM repo_readme WHERE repo_id = ?";
+    sqlx::query(query)
+        .bind(repo_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing repository README from database"
+        })?;
+
+    let query = "DELETE FROM repo_readme_asset WHERE repo_id = ? RETURNING id, ext";
+    let assets = sqlx::query_as::<_, (i64, Option<String>)>(query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error removing repository README assets from database"
+        })?;
+
+    let dir = get_data_dir().join(format!("assets/repos/{user}/{repo}/readme"));
+    for (id, ext) in assets {
+        let path = get_readme_asset_path(&dir, id, ext.as_deref());
+        if fs::try_exists(&path).await? {
+            fs::remove_file(&path).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Download the images of a README, replacing their URLs in the text with the local files.
+/// Images that were downloaded before are reused by URL, and the ones the README doesn't
+/// have anymore are removed.
+pub async fn download_readme_assets<R: Runtime>(
+    text: &str,
+    repo_id: i64,
+    platform: &str,
+    user: &str,
+    repo: &str,
+    pool: &SqlitePool,
+    app: &AppHandle<R>,
 ) -> AppResult<String> {
     let mut new_text = text.to_string();
     let images = parse_images(text);
@@ -74,39 +851,68 @@ pub async fn download_readme_assets(
     let initial_progress = progress_percentage(2, total_steps as usize);
     AddRepoProgress::Readme.send(platform, user, repo, initial_progress, 2, total_steps, app);
 
-    let dir = get_data_dir().join(&format!("assets/repos/{user}/{repo}/readme"));
+    let dir = get_data_dir().join(format!("assets/repos/{user}/{repo}/readme"));
     ensure_dir(&dir).await?;
 
+    let query = "SELECT url, id, ext FROM repo_readme_asset WHERE repo_id = ?";
+    let stored_assets = sqlx::query_as::<_, (String, i64, Option<String>)>(query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting README assets from database"
+        })?;
+    let mut assets: HashMap<String, (i64, Option<String>)> = stored_assets
+        .iter()
+        .map(|(url, id, ext)| (url.clone(), (*id, ext.clone())))
+        .collect();
+    let mut used_ids = HashSet::new();
+
     for (i, image) in images.into_iter().enumerate() {
-        let (bytes, ext) = download_image(&image.url).await?;
-        let ext_str = ext
-            .as_ref()
-            .map(|e| format!(".{e}"))
-            .unwrap_or("".to_string());
-
-        let query =
-            "INSERT INTO repo_readme_asset (repo_id, type, ext, url, alt) VALUES (?, ?, ?, ?, ?)";
-        let id = sqlx::query(query)
-            .bind(repo_id)
-            .bind("image")
-            .bind(&ext)
-            .bind(&image.alt)
-            .bind(&image.url)
-            .execute(pool)
-            .await
-            .map_err(|e| {
-                error!("{:?}", e);
-                "Error inserting README asset into database"
-            })?
-            .last_insert_rowid();
+        let downloaded = match assets.get(&image.url) {
+            Some((id, ext)) => {
+                let path = get_readme_asset_path(&dir, *id, ext.as_deref());
+                fs::try_exists(&path).await?.then_some((*id, path))
+            }
+            None => None,
+        };
+        let (id, path) = match downloaded {
+            Some(downloaded) => downloaded,
+            None => {
+                let (bytes, ext) = download_image(&image.url).await?;
 
-        let path = dir.join(&format!("{}{}", id, ext_str));
+                let query = "
+                    INSERT INTO repo_readme_asset (repo_id, type, ext, url, alt)
+                    VALUES (?, ?, ?, ?, ?)
+                ";
+                let id = sqlx::query(query)
+                    .bind(repo_id)
+                    .bind("image")
+                    .bind(&ext)
+                    .bind(&image.url)
+                    .bind(&image.alt)
+                    .execute(pool)
+                    .await
+                    .map_err(|e| {
+                        error!("{:?}", e);
+                        "Error inserting README asset into database"
+                    })?
+                    .last_insert_rowid();
+
+                let path = get_readme_asset_path(&dir, id, ext.as_deref());
+                let mut file = fs::File::create(&path).await.map_err(|e| {
+                    error!("{:?}", e);
+                    "Error creating local README asset file"
+                })?;
+                file.write_all(&bytes).await?;
+
+                assets.insert(image.url.clone(), (id, ext));
+                (id, path)
+            }
+        };
+        used_ids.insert(id);
         let path_str = path.to_str().unwrap();
-        let mut file = fs::File::create(&path).await.map_err(|e| {
-            error!("{:?}", e);
-            "Error creating local README asset file"
-        })?;
-        file.write_all(&bytes).await?;
 
         match image.kind {
             ReadmeImageKind::Markdown => {
@@ -141,6 +947,25 @@ pub async fn download_readme_assets(
         );
     }
 
+    for (_, id, ext) in stored_assets {
+        if used_ids.contains(&id) {
+            continue;
+        }
+        let query = "DELETE FROM repo_readme_asset WHERE id = ?";
+        sqlx::query(query)
+            .bind(id)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error removing README asset from database"
+            })?;
+        let path = get_readme_asset_path(&dir, id, ext.as_deref());
+        if fs::try_exists(&path).await? {
+            fs::remove_file(&path).await?;
+        }
+    }
+
     AddRepoProgress::Readme.send(platform, user, repo, 100, total_steps, total_steps, app);
 
     Ok(new_text)
@@ -228,3 +1053,365 @@ fn parse_images(text: &str) -> Vec<ParsedReadmeImage> {
 
     images
 }
+
+/// The URLs of every image in a markdown text, once each. HTML images are matched
+/// without requiring an `alt` attribute, as in issue bodies pasted from the GitHub UI.
+pub fn parse_image_urls(text: &str) -> Vec<String> {
+    let mut urls: Vec<String> = parse_images(text)
+        .into_iter()
+        .map(|image| image.url)
+        .collect();
+
+    let html_re = Regex::new(r#"<img\s+[^>]*\bsrc="(?P<src>https://[^"]+)""#).unwrap();
+    urls.extend(
+        html_re
+            .captures_iter(text)
+            .map(|cap| cap["src"].to_string()),
+    );
+
+    let mut seen = HashSet::new();
+    urls.retain(|url| seen.insert(url.clone()));
+    urls
+}
+
+/// Delete a repo with all of its rows, README assets and clone.
+pub async fn delete_repo(repo_id: i64, pool: &SqlitePool) -> AppResult<()> {
+    let query = "SELECT user, repo FROM repo WHERE id = ?";
+    let Some((user, repo)) = sqlx::query_as::<_, (String, String)>(query)
+        .bind(repo_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository from database"
+        })?
+    else {
+        return Ok(());
+    };
+
+    remove_repo_readme(repo_id, &user, &repo, pool).await?;
+
+    // The cached blobs are shared, only the ones no other repo has are removed.
+    let query = "
+        SELECT sha FROM repo_blob AS b
+        WHERE repo_id = ? AND NOT EXISTS (
+            SELECT 1 FROM repo_blob WHERE sha = b.sha AND repo_id != b.repo_id
+        )
+    ";
+    let blob_shas = sqlx::query_scalar::<_, String>(query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository blobs from database"
+        })?;
+
+    let query = "DELETE FROM repo WHERE id = ?";
+    sqlx::query(query)
+        .bind(repo_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error deleting repository from database"
+        })?;
+
+    // README assets are stored per user and repo, which a repo on another platform can share.
+    let query = "SELECT COUNT(*) FROM repo WHERE user = ? AND repo = ?";
+    let shared = sqlx::query_scalar::<_, i64>(query)
+        .bind(&user)
+        .bind(&repo)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository from database"
+        })?;
+    if shared == 0 {
+        let dir = get_data_dir().join(format!("assets/repos/{user}/{repo}"));
+        if fs::try_exists(&dir).await? {
+            fs::remove_dir_all(&dir).await?;
+        }
+    }
+
+    remove_repo_clone(repo_id).await?;
+
+    remove_cached_blobs(&blob_shas, pool).await?;
+
+    let release_dir = get_release_asset_dir(repo_id);
+    if fs::try_exists(&release_dir).await? {
+        fs::remove_dir_all(&release_dir).await?;
+    }
+
+    Ok(())
+}
+
+/// Remove the repos a crash left half-added, and mark clones that were interrupted as
+/// failed so they can be retried.
+pub async fn reconcile_repos(pool: &SqlitePool) -> AppResult<()> {
+    let query = "SELECT id FROM repo WHERE status = 'adding'";
+    let repo_ids = sqlx::query_scalar::<_, i64>(query)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repositories from database"
+        })?;
+    for repo_id in &repo_ids {
+        delete_repo(*repo_id, pool).await?;
+    }
+
+    let query = "UPDATE repo SET clone_status = 'failed' WHERE clone_status = 'cloning'";
+    sqlx::query(query).execute(pool).await.map_err(|e| {
+        error!("{:?}", e);
+        "Error updating repository clones in database"
+    })?;
+
+    if !repo_ids.is_empty() {
+        info!("removed {} half-added repos", repo_ids.len());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use sqlx::{migrate::Migrator, sqlite::SqlitePoolOptions};
+
+    use super::*;
+    use crate::{
+        platforms::github::api_models::{GitHubApiRepoTree, GitHubApiRepoTreeItem},
+        utils::testing::memory_pool,
+    };
+
+    async fn add_test_repo(pool: &SqlitePool) -> i64 {
+        let query = "
+            INSERT INTO repo (platform, user, repo, created_at, updated_at)
+            VALUES ('github', 'user', 'repo', '', '')
+        ";
+        sqlx::query(query)
+            .execute(pool)
+            .await
+            .unwrap()
+            .last_insert_rowid()
+    }
+
+    fn tree_item(path: &str, r#type: &str, sha: usize) -> GitHubApiRepoTreeItem {
+        GitHubApiRepoTreeItem {
+            path: path.to_string(),
+            mode: if r#type == "tree" { "040000" } else { "100644" }.to_string(),
+            sha: format!("{sha:040x}"),
+            r#type: r#type.to_string(),
+            size: (r#type == "blob").then_some(1024),
+        }
+    }
+
+    fn new_tree_item(path: &str, r#type: &str) -> NewRepoTreeItem {
+        tree_item(path, r#type, 0).into()
+    }
+
+    /// The ID, parent ID, name and depth of every item.
+    fn tree_item_rows(
+        items: &[NewRepoTreeItem],
+        last_id: i64,
+    ) -> Vec<(i64, Option<i64>, &str, i64)> {
+        assign_tree_item_ids(items, last_id)
+            .into_iter()
+            .map(|row| (row.id, row.parent_id, row.name, row.depth))
+            .collect()
+    }
+
+    #[test]
+    fn assign_tree_item_ids_resolves_parents_by_full_path() {
+        let items = [
+            new_tree_item("a", "tree"),
+            new_tree_item("a/src", "tree"),
+            new_tree_item("a/src/x", "blob"),
+            new_tree_item("b", "tree"),
+            new_tree_item("b/src", "tree"),
+            new_tree_item("b/src/y", "blob"),
+            new_tree_item("README.md", "blob"),
+        ];
+        assert_eq!(
+            tree_item_rows(&items, 10),
+            [
+                (11, None, "a", 0),
+                (12, Some(11), "src", 1),
+                (13, Some(12), "x", 2),
+                (14, None, "b", 0),
+                (15, Some(14), "src", 1),
+                (16, Some(15), "y", 2),
+                (17, None, "README.md", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn assign_tree_item_ids_resolves_parents_listed_after_children() {
+        let items = [
+            new_tree_item("b/src/y", "blob"),
+            new_tree_item("a/src/x", "blob"),
+            new_tree_item("b/src", "tree"),
+            new_tree_item("a/src", "tree"),
+            new_tree_item("b", "tree"),
+            new_tree_item("a", "tree"),
+        ];
+        assert_eq!(
+            tree_item_rows(&items, 0),
+            [
+                (1, Some(3), "y", 2),
+                (2, Some(4), "x", 2),
+                (3, Some(5), "src", 1),
+                (4, Some(6), "src", 1),
+                (5, None, "b", 0),
+                (6, None, "a", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn assign_tree_item_ids_places_items_without_parent_at_root() {
+        let items = [
+            new_tree_item("missing/file.txt", "blob"),
+            new_tree_item("a/b/c", "blob"),
+            new_tree_item("a", "tree"),
+        ];
+        assert_eq!(
+            tree_item_rows(&items, 0),
+            [
+                (1, None, "file.txt", 1),
+                (2, None, "c", 2),
+                (3, None, "a", 0),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn name_and_depth_migration_backfills_tree_items() {
+        const VERSION: i64 = 20240926084512;
+        let migrator: Migrator = sqlx::migrate!();
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        for migration in migrator.iter().filter(|m| m.version < VERSION) {
+            sqlx::raw_sql(&migration.sql).execute(&pool).await.unwrap();
+        }
+
+        // Parents were matched by directory name, so 'b/src/y' was put under 'a/src'.
+        let repo_id = add_test_repo(&pool).await;
+        let items = [
+            (1, None, "a", "tree"),
+            (2, Some(1), "a/src", "tree"),
+            (3, Some(2), "a/src/x", "blob"),
+            (4, None, "b", "tree"),
+            (5, Some(4), "b/src", "tree"),
+            (6, Some(2), "b/src/y", "blob"),
+            (7, None, "README.md", "blob"),
+        ];
+        for (id, parent_id, path, r#type) in items {
+            let query = "
+                INSERT INTO repo_tree_item (id, repo_id, parent_id, path, mode, type, sha)
+                VALUES (?, ?, ?, ?, '100644', ?, '')
+            ";
+            sqlx::query(query)
+                .bind(id)
+                .bind(repo_id)
+                .bind(parent_id)
+                .bind(path)
+                .bind(r#type)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let migration = migrator.iter().find(|m| m.version == VERSION).unwrap();
+        sqlx::raw_sql(&migration.sql).execute(&pool).await.unwrap();
+
+        let query = "SELECT id, parent_id, name, depth FROM repo_tree_item ORDER BY id";
+        let rows = sqlx::query_as::<_, (i64, Option<i64>, String, i64)>(query)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        let expected = [
+            (1, None, "a", 0),
+            (2, Some(1), "src", 1),
+            (3, Some(2), "x", 2),
+            (4, None, "b", 0),
+            (5, Some(4), "src", 1),
+            (6, Some(5), "y", 2),
+            (7, None, "README.md", 0),
+        ]
+        .map(|(id, parent_id, name, depth)| (id, parent_id, name.to_string(), depth));
+        assert_eq!(rows, expected);
+    }
+
+    /// A tree of `packages` directories, each with the same three subdirectories of
+    /// `files` files.
+    fn synthetic_tree(packages: usize, files: usize) -> GitHubApiRepoTree {
+        let mut tree = Vec::new();
+        for package in 0..packages {
+            let package_path = format!("package-{package}");
+            tree.push(tree_item(&package_path, "tree", tree.len()));
+            for dir in ["src", "tests", "docs"] {
+                let dir_path = format!("{package_path}/{dir}");
+                tree.push(tree_item(&dir_path, "tree", tree.len()));
+                for file in 0..files {
+                    let file_path = format!("{dir_path}/file-{file}.rs");
+                    tree.push(tree_item(&file_path, "blob", tree.len()));
+                }
+            }
+        }
+        GitHubApiRepoTree {
+            sha: "0".repeat(40),
+            tree,
+            truncated: false,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "benchmark, run with `cargo test --release -- --ignored --nocapture`"]
+    async fn bench_add_repo_tree() {
+        let pool = memory_pool().await;
+        let repo_id = add_test_repo(&pool).await;
+        // 500 * (1 + 3 * (1 + 132)) = 200,000 items.
+        let tree = synthetic_tree(500, 132);
+        let tree = FetchedTree {
+            sha: tree.sha.clone(),
+            rev: tree.sha,
+            truncated: tree.truncated,
+            items: tree.tree.into_iter().map(NewRepoTreeItem::from).collect(),
+        };
+        let app = tauri::test::mock_app();
+
+        let start = Instant::now();
+        add_repo_tree(
+            repo_id,
+            "main",
+            true,
+            &tree,
+            "github",
+            "user",
+            "repo",
+            &pool,
+            app.handle(),
+        )
+        .await
+        .unwrap();
+        println!(
+            "added {} tree items in {:?}",
+            tree.items.len(),
+            start.elapsed()
+        );
+
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM repo_tree_item")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 200_000);
+    }
+}
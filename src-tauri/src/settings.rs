@@ -29,6 +29,11 @@ impl FromStr for Theme {
 #[derive(Serialize, Deserialize)]
 pub struct AuthSettings {
     pub github_token: Option<String>,
+    /// Token for gitlab.com, self-hosted instances store their own.
+    #[serde(default)]
+    pub gitlab_token: Option<String>,
+    #[serde(default)]
+    pub bitbucket_token: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -41,7 +46,11 @@ pub struct Settings {
 const DEFAULT_SETTINGS: Settings = Settings {
     theme: Theme::Dark,
     auto_sync: false,
-    auth: AuthSettings { github_token: None },
+    auth: AuthSettings {
+        github_token: None,
+        gitlab_token: None,
+        bitbucket_token: None,
+    },
 };
 
 pub async fn load_settings() -> AppResult<Settings> {
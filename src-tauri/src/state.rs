@@ -1,15 +1,100 @@
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
 use sqlx::{migrate::MigrateDatabase, Sqlite, SqlitePool};
 use tokio::sync::Mutex;
 use tracing::info;
 
 use crate::{
-    error::AppResult,
-    platforms::github::api::GitHubAPI,
+    error::{AppError, AppResult},
+    instance::{get_instances, Instance},
+    platforms::{
+        bitbucket::api::BitbucketAPI, gitea::api::GiteaAPI, github::api::GitHubAPI,
+        gitlab::api::GitLabAPI, provider::PlatformProvider, Platform,
+    },
+    settings::load_settings,
     utils::dirs::{ensure_dirs, get_data_dir},
 };
 
+/// Registry of platform providers, keyed by platform and instance. The public host of
+/// a platform is registered without an instance.
 pub struct APIs {
-    pub github: GitHubAPI,
+    providers: HashMap<(Platform, Option<i64>), Arc<dyn PlatformProvider>>,
+    /// Also registered as a provider, kept for the GitHub-only token commands.
+    pub github: Arc<GitHubAPI>,
+}
+
+impl APIs {
+    fn new(
+        github_token: Option<String>,
+        gitlab_token: Option<String>,
+        bitbucket_token: Option<String>,
+    ) -> Self {
+        let github = Arc::new(GitHubAPI::init(github_token));
+        let mut apis = Self {
+            providers: HashMap::new(),
+            github: github.clone(),
+        };
+        apis.register(None, github);
+        apis.register(
+            None,
+            Arc::new(GitLabAPI::new(GitLabAPI::DEFAULT_BASE_URL, gitlab_token)),
+        );
+        apis.register(None, Arc::new(BitbucketAPI::init(bitbucket_token)));
+        apis
+    }
+
+    pub fn register(&mut self, instance_id: Option<i64>, provider: Arc<dyn PlatformProvider>) {
+        self.providers
+            .insert((provider.platform(), instance_id), provider);
+    }
+
+    /// Register a provider for a self-hosted instance, replacing an existing one.
+    pub fn register_instance(&mut self, instance: &Instance) -> AppResult<()> {
+        let token = instance.token.clone();
+        let provider: Arc<dyn PlatformProvider> = match Platform::from_str(&instance.platform)? {
+            Platform::GitHub => {
+                let api_url = instance
+                    .api_url
+                    .clone()
+                    .unwrap_or_else(|| format!("{}/api/v3", instance.base_url));
+                let content_url = instance
+                    .content_url
+                    .clone()
+                    .unwrap_or_else(|| format!("{}/raw", instance.base_url));
+                Arc::new(GitHubAPI::new(
+                    &instance.base_url,
+                    &api_url,
+                    &content_url,
+                    token,
+                ))
+            }
+            Platform::GitLab => Arc::new(GitLabAPI::new(&instance.base_url, token)),
+            Platform::Gitea => Arc::new(GiteaAPI::new(&instance.base_url, token)),
+            platform => {
+                return AppError::new(&format!("Instances are not supported for {platform}"));
+            }
+        };
+        self.register(Some(instance.id), provider);
+        Ok(())
+    }
+
+    pub fn unregister_instance(&mut self, instance_id: i64) {
+        self.providers.retain(|(_, id), _| *id != Some(instance_id));
+    }
+
+    pub fn get(
+        &self,
+        platform: Platform,
+        instance_id: Option<i64>,
+    ) -> AppResult<Arc<dyn PlatformProvider>> {
+        match self.providers.get(&(platform, instance_id)) {
+            Some(provider) => Ok(provider.clone()),
+            None if instance_id.is_some() => AppError::new("Unknown platform instance"),
+            None => AppError::new(&format!(
+                "{platform} repositories must be added from an instance"
+            )),
+        }
+    }
 }
 
 pub struct AppStateInner {
@@ -39,11 +124,23 @@ impl AppStateInner {
 
         info!("Connected to sqlite database");
 
+        let settings = load_settings().await?;
+
         Ok(Self {
             pool,
-            apis: APIs {
-                github: GitHubAPI::init(),
-            },
+            apis: APIs::new(
+                settings.auth.github_token,
+                settings.auth.gitlab_token,
+                settings.auth.bitbucket_token,
+            ),
         })
     }
+
+    /// Register a provider for every stored instance, must run after the migrations.
+    pub async fn register_instances(&mut self) -> AppResult<()> {
+        for instance in get_instances(&self.pool).await? {
+            self.apis.register_instance(&instance)?;
+        }
+        Ok(())
+    }
 }
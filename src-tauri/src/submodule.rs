@@ -0,0 +1,414 @@
+use std::collections::HashSet;
+
+use sqlx::SqlitePool;
+use tracing::{error, info};
+
+use crate::{
+    error::AppResult,
+    instance::Instance,
+    platforms::{
+        provider::{PlatformProvider, RepoContext},
+        Platform,
+    },
+};
+
+/// A submodule entry of a `.gitmodules` file.
+pub struct GitModule {
+    pub path: String,
+    pub url: String,
+}
+
+/// The repo a submodule URL points to.
+pub struct SubmoduleTarget {
+    pub platform: Platform,
+    pub instance_id: Option<i64>,
+    pub user: String,
+    pub repo: String,
+}
+
+/// Parse the `path` and `url` of every `[submodule "name"]` section.
+pub fn parse_gitmodules(content: &str) -> Vec<GitModule> {
+    let mut modules = Vec::new();
+    let mut section: Option<(Option<String>, Option<String>)> = None;
+
+    let mut push_section = |section: Option<(Option<String>, Option<String>)>| {
+        if let Some((Some(path), Some(url))) = section {
+            modules.push(GitModule { path, url });
+        }
+    };
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            push_section(section.take());
+            if line.starts_with("[submodule") {
+                section = Some((None, None));
+            }
+            continue;
+        }
+
+        let Some((path, url)) = section.as_mut() else {
+            continue;
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"').to_string();
+        match key.trim() {
+            "path" => *path = Some(value),
+            "url" => *url = Some(value),
+            _ => {}
+        }
+    }
+    push_section(section);
+
+    modules
+}
+
+/// The host of a URL without scheme, credentials or port.
+fn url_host(url: &str) -> &str {
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = url.split('/').next().unwrap_or_default();
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    host.split(':').next().unwrap_or_default()
+}
+
+/// Split a repo path into its user (or namespace) and repo name.
+fn split_repo_path(platform: Platform, path: &str) -> Option<(String, String)> {
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let (user, repo) = path.rsplit_once('/')?;
+    // Only GitLab has nested namespaces (subgroups).
+    if user.is_empty() || repo.is_empty() || (user.contains('/') && platform != Platform::GitLab) {
+        return None;
+    }
+    Some((user.to_string(), repo.to_string()))
+}
+
+/// Resolve a submodule URL to a platform repo. Relative URLs are resolved against the
+/// parent repo, absolute ones against the public hosts and the registered instances.
+pub fn resolve_submodule_url(
+    url: &str,
+    parent_platform: Platform,
+    parent: &RepoContext,
+    instances: &[Instance],
+) -> Option<SubmoduleTarget> {
+    if url.starts_with("./") || url.starts_with("../") {
+        let mut segments: Vec<&str> = parent.user.split('/').collect();
+        segments.push(&parent.repo);
+        for segment in url.split('/') {
+            match segment {
+                "." | "" => {}
+                ".." => {
+                    segments.pop()?;
+                }
+                segment => segments.push(segment),
+            }
+        }
+        let (user, repo) = split_repo_path(parent_platform, &segments.join("/"))?;
+        return Some(SubmoduleTarget {
+            platform: parent_platform,
+            instance_id: parent.instance_id,
+            user,
+            repo,
+        });
+    }
+
+    // Either `scheme://[user@]host[:port]/path` or the scp-like `user@host:path`.
+    let (host, path) = match url.split_once("://") {
+        Some((_, rest)) => {
+            let (authority, path) = rest.split_once('/')?;
+            (url_host(authority), path)
+        }
+        None => {
+            let (authority, path) = url.split_once(':')?;
+            (url_host(authority), path)
+        }
+    };
+
+    let (platform, instance_id) = match host {
+        "github.com" => (Platform::GitHub, None),
+        "gitlab.com" => (Platform::GitLab, None),
+        "bitbucket.org" => (Platform::Bitbucket, None),
+        host => {
+            let instance = instances
+                .iter()
+                .find(|instance| url_host(&instance.base_url).eq_ignore_ascii_case(host))?;
+            (instance.platform.parse().ok()?, Some(instance.id))
+        }
+    };
+
+    let (user, repo) = split_repo_path(platform, path)?;
+    Some(SubmoduleTarget {
+        platform,
+        instance_id,
+        user,
+        repo,
+    })
+}
+
+/// Fetch and parse the `.gitmodules` file of a repo, keeping the submodules that are
+/// still in the tree.
+pub async fn get_repo_submodules(
+    provider: &dyn PlatformProvider,
+    ctx: &RepoContext,
+    rev: &str,
+    pool: &SqlitePool,
+) -> AppResult<Vec<GitModule>> {
+    let query = "
+        SELECT path
+        FROM repo_tree_item
+        WHERE tree_id = (SELECT id FROM repo_tree WHERE repo_id = ? AND is_default = 1)
+        AND (path = '.gitmodules' OR type = 'commit')
+    ";
+    let paths = sqlx::query_scalar::<_, String>(query)
+        .bind(ctx.repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository submodule paths from database"
+        })?;
+
+    let mut paths: HashSet<String> = paths.into_iter().collect();
+    if !paths.remove(".gitmodules") {
+        return Ok(Vec::new());
+    }
+
+    let content = provider.fetch_file(ctx, rev, ".gitmodules", pool).await?;
+    let modules = parse_gitmodules(&content)
+        .into_iter()
+        .filter(|module| paths.contains(&module.path))
+        .collect::<Vec<_>>();
+
+    info!(
+        "found {} submodules in {}/{}",
+        modules.len(),
+        ctx.user,
+        ctx.repo
+    );
+
+    Ok(modules)
+}
+
+/// Link a repo to one of its submodules, ignoring links that already exist.
+pub async fn add_submodule_link(
+    repo_id: i64,
+    submodule_repo_id: i64,
+    path: &str,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let query =
+        "INSERT OR IGNORE INTO submodule (repo_id, submodule_repo_id, path) VALUES (?, ?, ?)";
+    sqlx::query(query)
+        .bind(repo_id)
+        .bind(submodule_repo_id)
+        .bind(path)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding submodule to database"
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parent(instance_id: Option<i64>, user: &str, repo: &str) -> RepoContext {
+        RepoContext {
+            repo_id: 1,
+            instance_id,
+            user: user.to_string(),
+            repo: repo.to_string(),
+        }
+    }
+
+    fn instance(id: i64, platform: &str, base_url: &str) -> Instance {
+        Instance {
+            id,
+            platform: platform.to_string(),
+            base_url: base_url.to_string(),
+            token: None,
+            created_at: String::new(),
+            api_url: None,
+            content_url: None,
+        }
+    }
+
+    fn resolve(
+        url: &str,
+        platform: Platform,
+        parent: &RepoContext,
+        instances: &[Instance],
+    ) -> Option<(Platform, Option<i64>, String, String)> {
+        resolve_submodule_url(url, platform, parent, instances)
+            .map(|t| (t.platform, t.instance_id, t.user, t.repo))
+    }
+
+    #[test]
+    fn parse_gitmodules_reads_path_and_url() {
+        let content = r#"
+            # A comment
+            [core]
+                path = ignored
+            [submodule "lib"]
+                path = vendor/lib
+                url = https://github.com/user/lib.git
+            ; Another comment
+            [submodule "quoted name"]
+                url = "../tools"
+                path = "tools"
+                branch = main
+        "#;
+        let modules = parse_gitmodules(content);
+
+        assert_eq!(modules.len(), 2);
+        assert_eq!(modules[0].path, "vendor/lib");
+        assert_eq!(modules[0].url, "https://github.com/user/lib.git");
+        assert_eq!(modules[1].path, "tools");
+        assert_eq!(modules[1].url, "../tools");
+    }
+
+    #[test]
+    fn parse_gitmodules_skips_incomplete_sections() {
+        let content = "
+            [submodule \"no-url\"]
+                path = a
+            [submodule \"no-path\"]
+                url = https://github.com/user/b
+            [submodule \"complete\"]
+                path = c
+                url = https://github.com/user/c
+        ";
+        let modules = parse_gitmodules(content);
+
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].path, "c");
+    }
+
+    #[test]
+    fn resolve_absolute_urls_on_public_hosts() {
+        let parent = parent(None, "user", "repo");
+        let cases = [
+            ("https://github.com/rust-lang/rust.git", Platform::GitHub),
+            ("git@github.com:rust-lang/rust.git", Platform::GitHub),
+            ("ssh://git@github.com:22/rust-lang/rust", Platform::GitHub),
+            (
+                "https://token@bitbucket.org/rust-lang/rust.git",
+                Platform::Bitbucket,
+            ),
+        ];
+        for (url, platform) in cases {
+            let target = resolve(url, Platform::GitLab, &parent, &[]).unwrap();
+            assert!(target.0 == platform, "{url}");
+            assert_eq!(target.1, None, "{url}");
+            assert_eq!(
+                (target.2.as_str(), target.3.as_str()),
+                ("rust-lang", "rust")
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_nested_namespaces_only_on_gitlab() {
+        let parent = parent(None, "user", "repo");
+        let target = resolve(
+            "https://gitlab.com/group/sub/project.git",
+            Platform::GitHub,
+            &parent,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            (target.2.as_str(), target.3.as_str()),
+            ("group/sub", "project")
+        );
+
+        let target = resolve("https://github.com/a/b/c", Platform::GitHub, &parent, &[]);
+        assert!(target.is_none());
+    }
+
+    #[test]
+    fn resolve_absolute_urls_on_instances() {
+        let parent = parent(None, "user", "repo");
+        let instances = [
+            instance(3, "gitea", "https://git.example.com"),
+            instance(4, "github", "https://ghe.example.com/"),
+        ];
+
+        let target = resolve(
+            "git@GIT.example.com:team/tool.git",
+            Platform::GitHub,
+            &parent,
+            &instances,
+        )
+        .unwrap();
+        assert!(target.0 == Platform::Gitea);
+        assert_eq!(target.1, Some(3));
+        assert_eq!((target.2.as_str(), target.3.as_str()), ("team", "tool"));
+
+        let target = resolve(
+            "https://ghe.example.com/team/tool",
+            Platform::GitHub,
+            &parent,
+            &instances,
+        )
+        .unwrap();
+        assert_eq!(target.1, Some(4));
+
+        let target = resolve(
+            "https://unknown.example.com/a/b",
+            Platform::GitHub,
+            &parent,
+            &instances,
+        );
+        assert!(target.is_none());
+    }
+
+    #[test]
+    fn resolve_relative_urls_against_parent() {
+        let parent = parent(Some(7), "group/sub", "app");
+
+        let target = resolve("../lib.git", Platform::GitLab, &parent, &[]).unwrap();
+        assert!(target.0 == Platform::GitLab);
+        assert_eq!(target.1, Some(7));
+        assert_eq!((target.2.as_str(), target.3.as_str()), ("group/sub", "lib"));
+
+        let target = resolve("../../other/lib", Platform::GitLab, &parent, &[]).unwrap();
+        assert_eq!(
+            (target.2.as_str(), target.3.as_str()),
+            ("group/other", "lib")
+        );
+
+        let target = resolve("./nested", Platform::GitLab, &parent, &[]).unwrap();
+        assert_eq!(
+            (target.2.as_str(), target.3.as_str()),
+            ("group/sub/app", "nested")
+        );
+    }
+
+    #[test]
+    fn resolve_relative_urls_past_the_root() {
+        let parent = parent(None, "user", "repo");
+        assert!(resolve("../../../lib", Platform::GitHub, &parent, &[]).is_none());
+        // The parent path would be a single segment.
+        assert!(resolve("../../lib", Platform::GitHub, &parent, &[]).is_none());
+    }
+
+    #[test]
+    fn resolve_relative_url_to_the_parent_itself() {
+        // A cycle, the caller finds the parent as an already added repo.
+        let parent = parent(None, "user", "repo");
+        let target = resolve("../repo", Platform::GitHub, &parent, &[]).unwrap();
+        assert_eq!((target.2.as_str(), target.3.as_str()), ("user", "repo"));
+    }
+}
@@ -0,0 +1,544 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    time::Duration,
+};
+
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{prelude::FromRow, SqlitePool};
+use tauri::{AppHandle, Manager};
+use tokio::time::Instant;
+use tracing::{error, info};
+
+use crate::{
+    blob::prefetch_repo_blobs,
+    clone::update_repo_clone,
+    error::{AppError, AppResult},
+    platforms::{
+        provider::{PlatformProvider, RepoContext},
+        Platform,
+    },
+    repo::{add_repo_data, add_repo_metadata_snapshot, refresh_repo_refs, Repo, RepoStats},
+    settings::load_settings,
+    state::AppState,
+    utils::rate_limit::{add_sync_run_usage, track_usage},
+};
+
+/// How often the scheduler looks for repos that are due for a sync.
+const SYNC_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// How long after its last sync (or being added) a repo is synced again.
+const SYNC_INTERVAL: chrono::Duration = chrono::Duration::hours(6);
+
+#[derive(Serialize, FromRow)]
+pub struct SyncRun {
+    pub id: i64,
+    pub repo_id: i64,
+    /// 'auto' (scheduler), 'manual' or 'add' (a step of adding the repo that failed).
+    pub kind: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    /// 'running', 'success', 'failed' or 'rate_limited'.
+    pub outcome: String,
+    pub error: Option<String>,
+}
+
+/// Record the start of a sync run, failing if the repo is already being synced. A unique
+/// index allows a single running sync per repo, so concurrent starts can't both succeed.
+pub async fn start_sync_run(repo_id: i64, kind: &str, pool: &SqlitePool) -> AppResult<i64> {
+    let query =
+        "INSERT INTO sync_run (repo_id, kind, started_at, outcome) VALUES (?, ?, ?, 'running')";
+    let result = sqlx::query(query)
+        .bind(repo_id)
+        .bind(kind)
+        .bind(Utc::now().to_rfc3339())
+        .execute(pool)
+        .await;
+    match result {
+        Ok(result) => Ok(result.last_insert_rowid()),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            AppError::new("Repository is already being synced")
+        }
+        Err(e) => {
+            error!("{:?}", e);
+            AppError::new("Error adding sync run to database")
+        }
+    }
+}
+
+pub async fn finish_sync_run(
+    run_id: i64,
+    outcome: &str,
+    error: Option<String>,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    let query = "UPDATE sync_run SET finished_at = ?, outcome = ?, error = ? WHERE id = ?";
+    sqlx::query(query)
+        .bind(Utc::now().to_rfc3339())
+        .bind(outcome)
+        .bind(error)
+        .bind(run_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating sync run in database"
+        })?;
+    Ok(())
+}
+
+/// Sync runs that were still running when the app exited can't finish anymore, and
+/// would otherwise keep their repo from being synced again.
+pub async fn fail_interrupted_sync_runs(pool: &SqlitePool) -> AppResult<()> {
+    let query = "
+        UPDATE sync_run SET finished_at = ?, outcome = 'failed', error = 'Interrupted'
+        WHERE outcome = 'running'
+    ";
+    sqlx::query(query)
+        .bind(Utc::now().to_rfc3339())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating sync runs in database"
+        })?;
+    Ok(())
+}
+
+pub async fn get_sync_runs(repo_id: i64, pool: &SqlitePool) -> AppResult<Vec<SyncRun>> {
+    let query = "SELECT * FROM sync_run WHERE repo_id = ? ORDER BY started_at DESC LIMIT 100";
+    let runs = sqlx::query_as::<_, SyncRun>(query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting sync runs from database"
+        })?;
+    Ok(runs)
+}
+
+/// What changed in a repo with a sync.
+#[derive(Serialize, Default)]
+pub struct RepoSyncDiff {
+    pub stars: Option<i64>,
+    pub stars_delta: Option<i64>,
+    /// The new default branch, if it changed.
+    pub default_branch: Option<String>,
+    /// Paths of the files (and submodules) added, removed or modified, compared by sha.
+    /// Files whose sha the platform doesn't expose are only reported as added or removed.
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+    pub readme_changed: bool,
+}
+
+/// The stored state of a repo that syncs are diffed on.
+struct RepoSnapshot {
+    stats: Option<RepoStats>,
+    /// Sha of every file by path, directories are left out.
+    files: HashMap<String, Option<String>>,
+    /// Hash of the README as fetched, the stored one links to the downloaded images.
+    readme_sha256: Option<String>,
+}
+
+async fn get_repo_snapshot(
+    repo_id: i64,
+    provider: &dyn PlatformProvider,
+    pool: &SqlitePool,
+) -> AppResult<RepoSnapshot> {
+    let stats = provider.get_repo_stats(repo_id, pool).await?;
+
+    let query = "
+        SELECT path, sha
+        FROM repo_tree_item
+        WHERE tree_id = (SELECT id FROM repo_tree WHERE repo_id = ? AND is_default = 1)
+        AND type != 'tree'
+    ";
+    let files = sqlx::query_as::<_, (String, Option<String>)>(query)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository tree from database"
+        })?;
+
+    let query = "SELECT raw_sha256 FROM repo_readme WHERE repo_id = ?";
+    let readme_sha256 = sqlx::query_scalar::<_, Option<String>>(query)
+        .bind(repo_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repository README from database"
+        })?
+        .flatten();
+
+    Ok(RepoSnapshot {
+        stats,
+        files: files.into_iter().collect(),
+        readme_sha256,
+    })
+}
+
+impl RepoSyncDiff {
+    fn new(before: RepoSnapshot, after: RepoSnapshot) -> Self {
+        let mut diff = Self {
+            readme_changed: before.readme_sha256 != after.readme_sha256,
+            ..Default::default()
+        };
+
+        if let Some(stats) = &after.stats {
+            diff.stars = stats.stars;
+            let before_stats = before.stats.as_ref();
+            diff.stars_delta = stats
+                .stars
+                .zip(before_stats.and_then(|s| s.stars))
+                .map(|(after, before)| after - before);
+            if before_stats.map(|s| &s.default_branch) != Some(&stats.default_branch) {
+                diff.default_branch = Some(stats.default_branch.clone());
+            }
+        }
+
+        for (path, sha) in &after.files {
+            match before.files.get(path) {
+                None => diff.added.push(path.clone()),
+                Some(Some(before_sha)) if sha.as_ref().is_some_and(|sha| sha != before_sha) => {
+                    diff.modified.push(path.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed = before
+            .files
+            .into_keys()
+            .filter(|path| !after.files.contains_key(path))
+            .collect();
+
+        diff.added.sort();
+        diff.removed.sort();
+        diff.modified.sort();
+
+        diff
+    }
+}
+
+/// Re-fetch the metadata, tree, README and refs of a repo, fetch into its clone and cache new
+/// files if set to.
+pub async fn sync_repo_data(
+    repo: &Repo,
+    provider: &dyn PlatformProvider,
+    pool: &SqlitePool,
+    app: &AppHandle,
+) -> AppResult<()> {
+    let ctx = RepoContext {
+        repo_id: repo.id,
+        instance_id: repo.instance_id,
+        user: repo.user.clone(),
+        repo: repo.repo.clone(),
+    };
+    add_repo_data(provider, &ctx, pool, app).await?;
+    refresh_repo_refs(provider, &ctx, pool, app).await?;
+
+    if repo.clone_data {
+        update_repo_clone(repo.id, provider.clone_remote(&ctx), pool).await?;
+    }
+
+    if repo.prefetch_blobs {
+        prefetch_repo_blobs(&ctx, repo.prefetch_max_size, provider, pool, app).await?;
+    }
+
+    if repo.archive_issues {
+        provider.archive_issues(&ctx, pool).await?;
+    }
+
+    if repo.archive_pulls {
+        provider.archive_pulls(&ctx, pool).await?;
+    }
+
+    if repo.archive_discussions {
+        provider.archive_discussions(&ctx, pool).await?;
+    }
+
+    if repo.archive_releases {
+        provider.archive_releases(&ctx, pool).await?;
+    }
+
+    let query = "UPDATE repo SET updated_at = ? WHERE id = ?";
+    sqlx::query(query)
+        .bind(Utc::now().to_rfc3339())
+        .bind(repo.id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error updating repository in database"
+        })?;
+
+    Ok(())
+}
+
+/// Sync a repo, recording the run, and return what changed.
+pub async fn sync_repo(
+    repo: &Repo,
+    provider: &dyn PlatformProvider,
+    kind: &str,
+    pool: &SqlitePool,
+    app: &AppHandle,
+) -> AppResult<RepoSyncDiff> {
+    let run_id = start_sync_run(repo.id, kind, pool).await?;
+
+    if let Err(e) = provider.check_core_rate_limit(pool).await {
+        finish_sync_run(run_id, "rate_limited", Some(e.to_string()), pool).await?;
+        return Err(e);
+    }
+
+    let (result, usage) = track_usage(async {
+        let before = get_repo_snapshot(repo.id, provider, pool).await?;
+        sync_repo_data(repo, provider, pool, app).await?;
+        add_repo_metadata_snapshot(repo.id, provider, pool).await?;
+        let after = get_repo_snapshot(repo.id, provider, pool).await?;
+        Ok::<_, AppError>(RepoSyncDiff::new(before, after))
+    })
+    .await;
+    add_sync_run_usage(run_id, usage, pool).await?;
+
+    match &result {
+        Ok(_) => finish_sync_run(run_id, "success", None, pool).await?,
+        Err(e) => finish_sync_run(run_id, "failed", Some(e.to_string()), pool).await?,
+    }
+
+    result
+}
+
+/// Repos with auto sync enabled, either on the repo or through the global setting, that
+/// haven't been synced (or attempted) within the sync interval.
+async fn get_due_repos(global_auto_sync: bool, pool: &SqlitePool) -> AppResult<Vec<Repo>> {
+    let due_before = (Utc::now() - SYNC_INTERVAL).to_rfc3339();
+    let query = "
+        SELECT r.*
+        FROM repo r
+        WHERE (r.auto_sync = 1 OR (r.auto_sync = 2 AND ?))
+        AND r.status = 'ready'
+        AND r.updated_at <= ?
+        AND NOT EXISTS (
+            SELECT 1 FROM sync_run s WHERE s.repo_id = r.id AND s.started_at > ?
+        )
+        ORDER BY r.updated_at
+    ";
+    let repos = sqlx::query_as::<_, Repo>(query)
+        .bind(global_auto_sync)
+        .bind(&due_before)
+        .bind(&due_before)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting repositories due for sync from database"
+        })?;
+    Ok(repos)
+}
+
+async fn run_auto_sync(app: &AppHandle) -> AppResult<()> {
+    let start = Instant::now();
+    let settings = load_settings().await?;
+    let state = app.state::<AppState>();
+
+    let pool = state.lock().await.pool.clone();
+
+    let repos = get_due_repos(settings.auto_sync, &pool).await?;
+    if repos.is_empty() {
+        return Ok(());
+    }
+
+    // Hosts whose rate limit ran out are skipped until the next check.
+    let mut rate_limited: HashSet<(Platform, Option<i64>)> = HashSet::new();
+    let mut synced = 0;
+
+    for repo in &repos {
+        let platform = Platform::from_str(&repo.platform)?;
+        if rate_limited.contains(&(platform, repo.instance_id)) {
+            continue;
+        }
+
+        // The state is only locked to get the provider, so commands aren't blocked while
+        // the repo syncs.
+        let provider = state.lock().await.apis.get(platform, repo.instance_id)?;
+
+        match sync_repo(repo, provider.as_ref(), "auto", &pool, app).await {
+            Ok(_) => synced += 1,
+            Err(e) => {
+                error!("{:?}", e);
+                if provider.check_core_rate_limit(&pool).await.is_err() {
+                    rate_limited.insert((platform, repo.instance_id));
+                }
+            }
+        }
+    }
+
+    info!(
+        "auto synced {}/{} repos in {:?}",
+        synced,
+        repos.len(),
+        start.elapsed()
+    );
+
+    Ok(())
+}
+
+/// Periodically sync the repos that are due in the background.
+pub fn start_sync_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SYNC_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = run_auto_sync(&app).await {
+                error!("{:?}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::{
+        platforms::{gitlab::api::GitLabAPI, provider::FetchedTree},
+        repo::{add_repo_tree, refresh_repo_readme, NewRepoTreeItem},
+        utils::testing::memory_pool,
+    };
+
+    #[tokio::test]
+    async fn a_repo_is_synced_once_at_a_time() {
+        let pool = memory_pool().await;
+        let query = "
+            INSERT INTO repo (platform, user, repo, created_at, updated_at)
+            VALUES ('github', 'user', 'repo', '', '')
+        ";
+        let repo_id = sqlx::query(query)
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+
+        let (first, second) = tokio::join!(
+            start_sync_run(repo_id, "auto", &pool),
+            start_sync_run(repo_id, "manual", &pool),
+        );
+        let run_id = first.unwrap();
+        assert_eq!(
+            second.unwrap_err().to_string(),
+            "App: Repository is already being synced"
+        );
+
+        finish_sync_run(run_id, "success", None, &pool)
+            .await
+            .unwrap();
+        start_sync_run(repo_id, "manual", &pool).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn unchanged_readme_with_images_is_not_reported_changed() {
+        let server = MockServer::start().await;
+        let image = format!("<img src=\"{}/logo.png\" alt=\"logo\">", server.uri());
+        let readme_path = "/api/v4/projects/group%2Fsynced/repository/files/README.md/raw";
+        Mock::given(method("GET"))
+            .and(path(readme_path))
+            .respond_with(ResponseTemplate::new(200).set_body_string(format!("# A\n{image}")))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(readme_path))
+            .respond_with(ResponseTemplate::new(200).set_body_string(format!("# B\n{image}")))
+            .mount(&server)
+            .await;
+        // Downloaded once, the changed README reuses it.
+        Mock::given(method("GET"))
+            .and(path("/logo.png"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", "image/png")
+                    .set_body_bytes(b"\x89PNG\r\n\x1a\n".to_vec()),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let pool = memory_pool().await;
+        let query = "
+            INSERT INTO repo (platform, user, repo, created_at, updated_at)
+            VALUES ('gitlab', 'group', 'synced', '', '')
+        ";
+        let repo_id = sqlx::query(query)
+            .execute(&pool)
+            .await
+            .unwrap()
+            .last_insert_rowid();
+        let ctx = RepoContext {
+            repo_id,
+            instance_id: None,
+            user: "group".to_string(),
+            repo: "synced".to_string(),
+        };
+        let api = GitLabAPI::new(&server.uri(), None);
+        let app = tauri::test::mock_app();
+        let tree = FetchedTree {
+            sha: "c1".to_string(),
+            rev: "c1".to_string(),
+            truncated: false,
+            items: vec![NewRepoTreeItem {
+                path: "README.md".to_string(),
+                mode: "100644".to_string(),
+                r#type: "blob".to_string(),
+                sha: Some("b1".to_string()),
+                size: None,
+            }],
+        };
+        add_repo_tree(
+            repo_id,
+            "main",
+            true,
+            &tree,
+            "gitlab",
+            "group",
+            "synced",
+            &pool,
+            app.handle(),
+        )
+        .await
+        .unwrap();
+        refresh_repo_readme(&api, &ctx, "c1", &pool, app.handle())
+            .await
+            .unwrap();
+
+        let before = get_repo_snapshot(repo_id, &api, &pool).await.unwrap();
+        refresh_repo_readme(&api, &ctx, "c1", &pool, app.handle())
+            .await
+            .unwrap();
+        let after = get_repo_snapshot(repo_id, &api, &pool).await.unwrap();
+        assert!(!RepoSyncDiff::new(before, after).readme_changed);
+
+        let before = get_repo_snapshot(repo_id, &api, &pool).await.unwrap();
+        refresh_repo_readme(&api, &ctx, "c1", &pool, app.handle())
+            .await
+            .unwrap();
+        let after = get_repo_snapshot(repo_id, &api, &pool).await.unwrap();
+        assert!(RepoSyncDiff::new(before, after).readme_changed);
+
+        let query = "SELECT COUNT(*) FROM repo_readme_asset WHERE repo_id = ?";
+        let assets = sqlx::query_scalar::<_, i64>(query)
+            .bind(repo_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(assets, 1);
+    }
+}
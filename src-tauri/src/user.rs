@@ -1,2 +1,139 @@
+use chrono::Utc;
+use sqlx::SqlitePool;
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::error;
 
+use crate::{
+    error::AppResult,
+    utils::{
+        dirs::{ensure_dir, get_data_dir},
+        image::download_image,
+    },
+};
 
+/// Get the id of a user on a platform instance, if it has been added.
+pub async fn get_user_id(
+    platform: &str,
+    instance_id: Option<i64>,
+    user: &str,
+    pool: &SqlitePool,
+) -> AppResult<Option<i64>> {
+    let query = "SELECT id FROM user WHERE platform = ? AND instance_id IS ? AND user = ?";
+    let user_id = sqlx::query_scalar::<_, i64>(query)
+        .bind(platform)
+        .bind(instance_id)
+        .bind(user)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error checking if user exists in database"
+        })?;
+    Ok(user_id)
+}
+
+pub async fn add_user(
+    platform: &str,
+    instance_id: Option<i64>,
+    user: &str,
+    pool: &SqlitePool,
+) -> AppResult<i64> {
+    let query = "INSERT INTO user (platform, instance_id, user, created_at, updated_at) VALUES (?, ?, ?, ?, ?)";
+    let user_id = sqlx::query(query)
+        .bind(platform)
+        .bind(instance_id)
+        .bind(user)
+        .bind(Utc::now().to_rfc3339())
+        .bind(Utc::now().to_rfc3339())
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error adding user data into database"
+        })?
+        .last_insert_rowid();
+    Ok(user_id)
+}
+
+/// Download a user's avatar into the data directory and record it.
+pub async fn add_user_avatar(
+    user_id: i64,
+    platform: &str,
+    url: &str,
+    pool: &SqlitePool,
+) -> AppResult<i64> {
+    let (bytes, ext) = download_image(url).await?;
+    let avatar_query = "INSERT INTO user_avatar (user_id, platform, ext, url) VALUES (?, ?, ?, ?)";
+    let avatar_id = sqlx::query(avatar_query)
+        .bind(user_id)
+        .bind(platform)
+        .bind(ext)
+        .bind(url)
+        .execute(pool)
+        .await?
+        .last_insert_rowid();
+
+    let dir = get_data_dir().join("assets/avatars");
+    ensure_dir(&dir).await?;
+    let path = dir.join(avatar_id.to_string());
+    let mut file = fs::File::create(&path).await.map_err(|e| {
+        error!("{:?}", e);
+        "Error creating avatar image file"
+    })?;
+    file.write_all(&bytes).await?;
+
+    Ok(avatar_id)
+}
+
+/// Delete a user with its platform data and avatars.
+pub async fn delete_user(user_id: i64, pool: &SqlitePool) -> AppResult<()> {
+    let query = "DELETE FROM user_avatar WHERE user_id = ? RETURNING id";
+    let avatar_ids = sqlx::query_scalar::<_, i64>(query)
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error deleting user avatar from database"
+        })?;
+
+    let query = "DELETE FROM user WHERE id = ?";
+    sqlx::query(query)
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error deleting user from database"
+        })?;
+
+    let dir = get_data_dir().join("assets/avatars");
+    for avatar_id in avatar_ids {
+        let path = dir.join(avatar_id.to_string());
+        if fs::try_exists(&path).await? {
+            fs::remove_file(&path).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The local path of a user's avatar, if one was downloaded.
+pub async fn get_user_avatar_path(user_id: i64, pool: &SqlitePool) -> AppResult<Option<String>> {
+    let avatar_id_query = "SELECT id FROM user_avatar WHERE user_id = ?";
+    let avatar_id = sqlx::query_scalar::<_, i64>(avatar_id_query)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting user avatar id from database"
+        })?;
+
+    Ok(avatar_id.map(|avatar_id| {
+        let path = get_data_dir()
+            .join("assets/avatars")
+            .join(avatar_id.to_string());
+        path.to_str().unwrap().to_string()
+    }))
+}
@@ -2,3 +2,5 @@ pub mod data;
 pub mod dirs;
 pub mod image;
 pub mod rate_limit;
+#[cfg(test)]
+pub mod testing;
@@ -1,3 +1,6 @@
+use std::io::{Read, Write};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
 use serde::Deserialize;
 use tauri::http::HeaderMap;
 use tracing::error;
@@ -58,3 +61,36 @@ pub fn progress_percentage(index: usize, total: usize) -> u8 {
         percentage.min(255.0) as u8
     }
 }
+
+/// Percent-encode a value for use as a single URL path segment or query value.
+pub fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Whether a revision is a full commit SHA (SHA-1 or SHA-256) rather than a ref name.
+pub fn is_commit_sha(rev: &str) -> bool {
+    matches!(rev.len(), 40 | 64) && rev.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Compress text with zlib, e.g. to store large diffs.
+pub fn compress_text(text: &str) -> AppResult<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(text.as_bytes())?;
+    Ok(encoder.finish()?)
+}
+
+/// Decompress text compressed with `compress_text`.
+pub fn decompress_text(bytes: &[u8]) -> AppResult<String> {
+    let mut text = String::new();
+    ZlibDecoder::new(bytes).read_to_string(&mut text)?;
+    Ok(text)
+}
@@ -10,8 +10,12 @@ pub fn get_config_dir() -> PathBuf {
     dirs::config_local_dir().unwrap().join("git-chest")
 }
 
-/// Where the non-cache data for the application is stored.
+/// Where the non-cache data for the application is stored. Tests use a directory of
+/// their own, so they don't write into the data of an installed app.
 pub fn get_data_dir() -> PathBuf {
+    if cfg!(test) {
+        return std::env::temp_dir().join(format!("git-chest-test-{}", std::process::id()));
+    }
     dirs::data_local_dir().unwrap().join("git-chest")
 }
 
@@ -1,16 +1,92 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    future::Future,
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
 use chrono::{DateTime, Utc};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use sqlx::{prelude::FromRow, SqlitePool};
-use tracing::error;
+use tauri::{http::HeaderMap, AppHandle, Emitter};
+use tracing::{error, warn};
+
+use crate::{
+    error::{AppError, AppResult},
+    platforms::Platform,
+    utils::data::parse_header_num,
+};
+
+/// Attempts of a request failing with a network or server error.
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Base delay of the exponential backoff between attempts.
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+
+/// How often a request is retried after being rate limited.
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+
+/// GitHub asks to wait at least a minute after a secondary rate limit without `Retry-After`.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Used to emit rate limit events, requests are made before the app is set up.
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+/// Identifies a rate limit. Hosts and tokens each have their own budget, which platforms
+/// split per resource (e.g. `core`, `search` and `graphql` on GitHub).
+#[derive(Serialize, Clone, PartialEq, Eq, Hash)]
+pub struct RateLimitKey {
+    pub platform: Platform,
+    /// The API URL of the host.
+    pub host: String,
+    /// The SHA-256 digest of the token, empty for unauthenticated requests.
+    pub token: String,
+    pub resource: String,
+}
+
+impl RateLimitKey {
+    pub fn new(platform: Platform, host: &str, token: Option<&str>, resource: &str) -> Self {
+        // The token itself isn't stored, its digest still tells every token apart.
+        let token = token
+            .map(|token| format!("{:x}", Sha256::digest(token)))
+            .unwrap_or_default();
+        Self {
+            platform,
+            host: host.to_string(),
+            token,
+            resource: resource.to_string(),
+        }
+    }
+
+    /// The same host and token, for another resource.
+    pub fn with_resource(&self, resource: &str) -> Self {
+        Self {
+            resource: resource.to_string(),
+            ..self.clone()
+        }
+    }
+}
 
-use crate::error::{AppError, AppResult};
+impl std::fmt::Display for RateLimitKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} ({})", self.platform, self.host, self.resource)
+    }
+}
 
-#[derive(FromRow)]
+#[derive(Serialize, FromRow)]
 pub struct RateLimit {
+    pub platform: String,
+    pub host: String,
+    pub token: String,
+    pub resource: String,
     pub max: i32,
     pub remaining: i32,
     pub used: i32,
     pub reset_at: i64,
-    pub resource: String,
+    pub updated_at: String,
 }
 
 impl RateLimit {
@@ -19,35 +95,211 @@ impl RateLimit {
     }
 }
 
-async fn get_rate_limit(id: &str, resource: &str, pool: &SqlitePool) -> AppResult<RateLimit> {
-    let rate_limit = sqlx::query_as::<_, RateLimit>(
-        "SELECT max, remaining, used, reset_at, resource FROM rate_limit WHERE id = ? AND resource = ?",
-    )
-    .bind(id)
-    .bind(resource)
-    .fetch_one(pool)
-    .await?;
+/// The calls a sync run made against a rate limit, and what was left afterwards.
+#[derive(Serialize, FromRow)]
+pub struct RateLimitUsage {
+    pub sync_run_id: i64,
+    pub repo_id: i64,
+    pub started_at: String,
+    pub platform: String,
+    pub host: String,
+    pub token: String,
+    pub resource: String,
+    pub calls: i64,
+    pub remaining: Option<i64>,
+}
+
+#[derive(Serialize, Clone)]
+struct RateLimitWaitData {
+    #[serde(flatten)]
+    key: RateLimitKey,
+    /// Unix timestamp the request is retried at, `None` once the wait is over.
+    until: Option<i64>,
+}
+
+tokio::task_local! {
+    /// Calls made per rate limit by the current task, while tracked.
+    static USAGE: RefCell<HashMap<RateLimitKey, i64>>;
+}
+
+/// Run a future, counting the requests it makes per rate limit.
+pub async fn track_usage<F: Future>(future: F) -> (F::Output, HashMap<RateLimitKey, i64>) {
+    USAGE
+        .scope(RefCell::new(HashMap::new()), async {
+            let output = future.await;
+            (output, USAGE.with(|usage| usage.take()))
+        })
+        .await
+}
+
+fn count_call(key: &RateLimitKey) {
+    let _ = USAGE.try_with(|usage| {
+        *usage.borrow_mut().entry(key.clone()).or_default() += 1;
+    });
+}
+
+pub fn set_app_handle(app: AppHandle) {
+    let _ = APP_HANDLE.set(app);
+}
+
+fn emit_rate_limit_wait(key: &RateLimitKey, until: Option<i64>) {
+    if let Some(app) = APP_HANDLE.get() {
+        let data = RateLimitWaitData {
+            key: key.clone(),
+            until,
+        };
+        if let Err(e) = app.emit("rate-limit-wait", data) {
+            error!("{:?}", e);
+        }
+    }
+}
+
+/// Sleep for a rate limit, letting the UI show how long it waits.
+async fn wait_rate_limited(key: &RateLimitKey, wait: Duration) {
+    let until = Utc::now().timestamp() + wait.as_secs() as i64;
+    warn!("waiting {:?} for rate limit {}", wait, key);
+    emit_rate_limit_wait(key, Some(until));
+    tokio::time::sleep(wait).await;
+    emit_rate_limit_wait(key, None);
+}
+
+/// Exponential backoff with up to 50% jitter, so parallel requests don't retry in step.
+fn backoff(attempt: u32) -> Duration {
+    let delay = BACKOFF_BASE * 2u32.pow(attempt);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    let jitter = delay.mul_f64(f64::from(nanos % 1000) / 2000.0);
+    delay + jitter
+}
+
+/// How long to wait before retrying a rate limited response, from `Retry-After` or the
+/// reset time of an exhausted limit. `None` if the response wasn't rate limited.
+fn retry_after(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+
+    if let Ok(secs) = parse_header_num(headers, "Retry-After") {
+        return Some(Duration::from_secs(secs.max(1) as u64));
+    }
+
+    // GitHub sends `X-RateLimit-*` headers, GitLab `RateLimit-*`.
+    let remaining = parse_header_num(headers, "X-RateLimit-Remaining")
+        .or_else(|_| parse_header_num(headers, "RateLimit-Remaining"));
+    let reset_at = parse_header_num(headers, "X-RateLimit-Reset")
+        .or_else(|_| parse_header_num(headers, "RateLimit-Reset"));
+    if let (Ok(0), Ok(reset_at)) = (remaining, reset_at) {
+        let secs = (reset_at - Utc::now().timestamp()).max(0) as u64 + 1;
+        return Some(Duration::from_secs(secs));
+    }
+
+    // A 403 without rate limit headers is a permission error.
+    (status == StatusCode::TOO_MANY_REQUESTS).then_some(DEFAULT_RETRY_AFTER)
+}
+
+/// Send a request, waiting for the rate limit to reset instead of failing. Rate limited
+/// responses are retried after the time the host asks for, network and server errors
+/// with a jittered exponential backoff. The request is built again for every attempt,
+/// since requests with a streamed body can't be cloned.
+pub async fn send_with_retry(
+    build: impl Fn() -> RequestBuilder,
+    key: &RateLimitKey,
+    pool: &SqlitePool,
+) -> AppResult<Response> {
+    let mut attempt = 0;
+    let mut rate_limit_retries = 0;
+
+    loop {
+        wait_for_rate_limit(key, pool).await?;
+        count_call(key);
+
+        match build().send().await {
+            Ok(res) => {
+                let status = res.status();
+                if status.is_server_error() && attempt + 1 < MAX_ATTEMPTS {
+                    warn!("retrying request after {}", status);
+                    tokio::time::sleep(backoff(attempt)).await;
+                    attempt += 1;
+                    continue;
+                }
+
+                if rate_limit_retries < MAX_RATE_LIMIT_RETRIES {
+                    if let Some(wait) = retry_after(status, res.headers()) {
+                        wait_rate_limited(key, wait).await;
+                        rate_limit_retries += 1;
+                        continue;
+                    }
+                }
+
+                return Ok(res);
+            }
+            Err(e) if (e.is_connect() || e.is_timeout()) && attempt + 1 < MAX_ATTEMPTS => {
+                warn!("retrying request after {:?}", e);
+                tokio::time::sleep(backoff(attempt)).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+async fn get_rate_limit(key: &RateLimitKey, pool: &SqlitePool) -> AppResult<RateLimit> {
+    let query = "
+        SELECT * FROM rate_limit
+        WHERE platform = ? AND host = ? AND token = ? AND resource = ?
+    ";
+    let rate_limit = sqlx::query_as::<_, RateLimit>(query)
+        .bind(key.platform.to_string())
+        .bind(&key.host)
+        .bind(&key.token)
+        .bind(&key.resource)
+        .fetch_one(pool)
+        .await?;
     Ok(rate_limit)
 }
 
+/// Every known rate limit, most recently updated first.
+pub async fn get_rate_limits(pool: &SqlitePool) -> AppResult<Vec<RateLimit>> {
+    let query = "SELECT * FROM rate_limit ORDER BY updated_at DESC";
+    let rate_limits = sqlx::query_as::<_, RateLimit>(query)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting rate limits from database"
+        })?;
+    Ok(rate_limits)
+}
+
 pub async fn update_rate_limit(
-    id: &str,
+    key: &RateLimitKey,
     max: i64,
     remaining: i64,
     used: i64,
     reset_at: i64,
-    resource: &str,
     pool: &SqlitePool,
 ) -> AppResult<()> {
-    let query =
-        "INSERT OR REPLACE INTO rate_limit (id, max, remaining, used, reset_at, resource) VALUES (?, ?, ?, ?, ?, ?)";
+    let query = "
+        INSERT INTO rate_limit (
+            platform, host, token, resource, max, remaining, used, reset_at, updated_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (platform, host, token, resource) DO UPDATE SET
+            max = excluded.max, remaining = excluded.remaining, used = excluded.used,
+            reset_at = excluded.reset_at, updated_at = excluded.updated_at
+    ";
     sqlx::query(query)
-        .bind(id)
+        .bind(key.platform.to_string())
+        .bind(&key.host)
+        .bind(&key.token)
+        .bind(&key.resource)
         .bind(max)
         .bind(remaining)
         .bind(used)
         .bind(reset_at)
-        .bind(resource)
+        .bind(Utc::now().to_rfc3339())
         .execute(pool)
         .await
         .map_err(|e| {
@@ -57,8 +309,23 @@ pub async fn update_rate_limit(
     Ok(())
 }
 
-pub async fn check_rate_limit(id: &str, resource: &str, pool: &SqlitePool) -> AppResult<()> {
-    let rate_limit = get_rate_limit(id, resource, pool).await;
+/// Wait until an exhausted rate limit resets.
+pub async fn wait_for_rate_limit(key: &RateLimitKey, pool: &SqlitePool) -> AppResult<()> {
+    if let Ok(rate_limit) = get_rate_limit(key, pool).await {
+        let reset_at = rate_limit.to_reset_datetime();
+        let now = Utc::now();
+        if rate_limit.remaining <= 0 && reset_at > now {
+            let wait = (reset_at - now).to_std().unwrap_or_default() + Duration::from_secs(1);
+            wait_rate_limited(key, wait).await;
+        }
+    }
+    Ok(())
+}
+
+/// Fail instead of waiting if a rate limit is exhausted, for background work that can
+/// be skipped.
+pub async fn check_rate_limit(key: &RateLimitKey, pool: &SqlitePool) -> AppResult<()> {
+    let rate_limit = get_rate_limit(key, pool).await;
 
     if let Ok(rate_limit) = rate_limit {
         let reset_at = rate_limit.to_reset_datetime();
@@ -70,3 +337,174 @@ pub async fn check_rate_limit(id: &str, resource: &str, pool: &SqlitePool) -> Ap
 
     Ok(())
 }
+
+/// Record the calls a sync run made per rate limit.
+pub async fn add_sync_run_usage(
+    sync_run_id: i64,
+    usage: HashMap<RateLimitKey, i64>,
+    pool: &SqlitePool,
+) -> AppResult<()> {
+    for (key, calls) in usage {
+        let remaining = get_rate_limit(&key, pool).await.ok().map(|r| r.remaining);
+        let query = "
+            INSERT INTO sync_run_rate_limit (
+                sync_run_id, platform, host, token, resource, calls, remaining
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+        ";
+        sqlx::query(query)
+            .bind(sync_run_id)
+            .bind(key.platform.to_string())
+            .bind(&key.host)
+            .bind(&key.token)
+            .bind(&key.resource)
+            .bind(calls)
+            .bind(remaining)
+            .execute(pool)
+            .await
+            .map_err(|e| {
+                error!("{:?}", e);
+                "Error adding sync run rate limit usage to database"
+            })?;
+    }
+    Ok(())
+}
+
+/// The calls made by the sync runs of a repo, or of every repo, newest first.
+pub async fn get_rate_limit_usage(
+    repo_id: Option<i64>,
+    pool: &SqlitePool,
+) -> AppResult<Vec<RateLimitUsage>> {
+    let query = "
+        SELECT u.*, s.repo_id, s.started_at
+        FROM sync_run_rate_limit u
+        JOIN sync_run s ON s.id = u.sync_run_id
+        WHERE ? IS NULL OR s.repo_id = ?
+        ORDER BY s.started_at DESC
+        LIMIT 500
+    ";
+    let usage = sqlx::query_as::<_, RateLimitUsage>(query)
+        .bind(repo_id)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            error!("{:?}", e);
+            "Error getting rate limit usage from database"
+        })?;
+    Ok(usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::Instant;
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::utils::testing::memory_pool;
+
+    /// Send a request to the mock server, returning the final status and how long the
+    /// retries waited.
+    async fn send(server: &MockServer) -> (StatusCode, Duration) {
+        let pool = memory_pool().await;
+        let client = reqwest::Client::new();
+        let key = RateLimitKey::new(Platform::GitHub, &server.uri(), None, "core");
+        let start = Instant::now();
+        let res = send_with_retry(|| client.get(server.uri()), &key, &pool)
+            .await
+            .unwrap();
+        (res.status(), start.elapsed())
+    }
+
+    #[test]
+    fn key_tells_tokens_with_the_same_suffix_apart() {
+        let host = "https://api.github.com";
+        let a = RateLimitKey::new(Platform::GitHub, host, Some("ghp_first_abcd"), "core");
+        let b = RateLimitKey::new(Platform::GitHub, host, Some("ghp_other_abcd"), "core");
+        assert_ne!(a.token, b.token);
+        assert!(!a.token.contains("abcd"));
+        assert_eq!(
+            a.token,
+            RateLimitKey::new(Platform::GitHub, host, Some("ghp_first_abcd"), "search").token
+        );
+    }
+
+    #[test]
+    fn key_without_token_is_empty() {
+        let key = RateLimitKey::new(Platform::GitLab, "https://gitlab.com", None, "api");
+        assert!(key.token.is_empty());
+    }
+
+    #[tokio::test]
+    async fn retries_after_the_time_a_429_asks_for() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let (status, waited) = send(&server).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(waited >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn retries_a_403_of_an_exhausted_limit_after_its_reset() {
+        let server = MockServer::start().await;
+        // The reset is waited for with a second to spare.
+        let reset_at = Utc::now().timestamp();
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(403)
+                    .insert_header("x-ratelimit-remaining", "0")
+                    .insert_header("x-ratelimit-reset", reset_at.to_string()),
+            )
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let (status, waited) = send(&server).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(waited >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn returns_a_403_without_rate_limit_headers() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(403))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let (status, _) = send(&server).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn returns_the_rate_limited_response_after_the_last_retry() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+            .expect(u64::from(MAX_RATE_LIMIT_RETRIES) + 1)
+            .mount(&server)
+            .await;
+
+        let (status, waited) = send(&server).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert!(waited >= Duration::from_secs(MAX_RATE_LIMIT_RETRIES.into()));
+    }
+}
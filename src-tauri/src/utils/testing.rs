@@ -0,0 +1,13 @@
+use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+
+/// A migrated in-memory database. A single connection is used, as every connection to
+/// `sqlite::memory:` opens its own database.
+pub async fn memory_pool() -> SqlitePool {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!().run(&pool).await.unwrap();
+    pool
+}
@@ -71,6 +71,11 @@ interface AddRepoForm {
   repo: string;
   add_submodules: boolean;
   clone_data: boolean;
+  prefetch_blobs: boolean;
+  archive_issues: boolean;
+  archive_pulls: boolean;
+  archive_discussions: boolean;
+  archive_releases: boolean;
   watch_events: RepoEvent[];
   auto_sync: AutoSync;
 }
@@ -276,6 +281,11 @@ export default function Add() {
         user: data.user,
         repo: data.repo,
         clone_data: data.clone_data,
+        prefetch_blobs: data.prefetch_blobs,
+        archive_issues: data.archive_issues,
+        archive_pulls: data.archive_pulls,
+        archive_discussions: data.archive_discussions,
+        archive_releases: data.archive_releases,
         auto_sync: data.auto_sync,
         add_submodules: data.add_submodules,
         watch_events: data.watch_events ?? [],
@@ -544,6 +554,86 @@ export default function Add() {
             />
           </div>
 
+          <div className="py-5 border-t border-border">
+            <Controller
+              name="prefetch_blobs"
+              control={control}
+              defaultValue={false}
+              render={({ field: { value, onChange } }) => (
+                <SwitchBox
+                  title="Prefetch Files"
+                  description="Download every file up to 1 MiB to browse the tree offline."
+                  value={value}
+                  onChange={onChange}
+                />
+              )}
+            />
+          </div>
+
+          <div className="py-5 border-t border-border">
+            <Controller
+              name="archive_issues"
+              control={control}
+              defaultValue={false}
+              render={({ field: { value, onChange } }) => (
+                <SwitchBox
+                  title="Archive Issues"
+                  description="Download the issues and their comments on every sync.\nOnly supported for GitHub."
+                  value={value}
+                  onChange={onChange}
+                />
+              )}
+            />
+          </div>
+
+          <div className="py-5 border-t border-border">
+            <Controller
+              name="archive_pulls"
+              control={control}
+              defaultValue={false}
+              render={({ field: { value, onChange } }) => (
+                <SwitchBox
+                  title="Archive Pull Requests"
+                  description="Download the pull requests with their reviews and diffs on every sync.\nOnly supported for GitHub."
+                  value={value}
+                  onChange={onChange}
+                />
+              )}
+            />
+          </div>
+
+          <div className="py-5 border-t border-border">
+            <Controller
+              name="archive_discussions"
+              control={control}
+              defaultValue={false}
+              render={({ field: { value, onChange } }) => (
+                <SwitchBox
+                  title="Archive Discussions"
+                  description="Download the discussions and their comments on every sync.\nOnly supported for GitHub and requires a token."
+                  value={value}
+                  onChange={onChange}
+                />
+              )}
+            />
+          </div>
+
+          <div className="py-5 border-t border-border">
+            <Controller
+              name="archive_releases"
+              control={control}
+              defaultValue={false}
+              render={({ field: { value, onChange } }) => (
+                <SwitchBox
+                  title="Archive Releases"
+                  description="Download the release notes and asset details on every sync.\nOnly supported for GitHub."
+                  value={value}
+                  onChange={onChange}
+                />
+              )}
+            />
+          </div>
+
           <div className="py-5 border-t border-border flex">
             <div className="w-1/2 pr-2">
               <Label text="Watch Events" />
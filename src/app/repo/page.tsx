@@ -127,13 +127,18 @@ interface PlatformRepo {
 }
 
 interface RepoTree {
+  id: number;
+  ref: string;
   sha: string;
   truncated: boolean;
+  is_default: boolean;
 }
 
 interface RepoTreeItem {
   id: number;
   path: string;
+  name: string;
+  depth: number;
   mode: string;
   type: string;
   sha: string;
@@ -165,6 +170,7 @@ export default function Page() {
   const params = useSearchParams();
   const id = params.get("id");
   const tree_id = params.get("tree_id");
+  const tree_ref = params.get("ref");
 
   const updateCodeSyntaxHighlighting = () => {
     hljs.configure({ ignoreUnescapedHTML: true });
@@ -186,6 +192,7 @@ export default function Page() {
     invoke<FullRepo>("get_repo", {
       id: parseInt(id),
       tree_id: tree_id ? parseInt(tree_id) : undefined,
+      tree_ref: tree_ref ?? undefined,
     })
       .then((data) => {
         setFullRepo(data);
@@ -194,7 +201,7 @@ export default function Page() {
         setError(err);
         console.error(err);
       });
-  }, [id, tree_id]);
+  }, [id, tree_id, tree_ref]);
 
   const removeRepo = () => {
     if (typeof id !== "string" || !fullRepo) return;
@@ -327,10 +334,10 @@ export default function Page() {
                       <GoFile className="mt-05 h-5 w-5 text-fg-tertiary" />
                     )}
                     <Link
-                      href={`/repo?id=${id}&tree_id=${item.id}`}
+                      href={`/repo?id=${id}&tree_id=${item.id}${tree_ref ? `&ref=${encodeURIComponent(tree_ref)}` : ""}`}
                       className="ml-2 hover:text-link hover:underline"
                     >
-                      {item.path}
+                      {item.name}
                     </Link>
                   </div>
                 ))}